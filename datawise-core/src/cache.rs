@@ -0,0 +1,465 @@
+//! 查询结果缓存
+//!
+//! 默认关闭，打开后只读查询的结果摘要（行数、列数、预览）按规范化的 SQL、参数和
+//! 查询读取的表的版本缓存。表的版本在导入、DDL 或修改数据的 SQL 改变该表时递增，
+//! 缓存的结果随之失效。缓存占用的内存超出上限时淘汰最久未使用的结果。
+//!
+//! 查询读取的表按 SQL 中出现的名称确定（视图展开为定义中出现的名称），只会多算不会漏算。
+//! 结果可能随时间或外部数据变化的查询不缓存：调用随机数、当前时间等函数，
+//! 直接读取文件，读取附加的 SQLite、PostgreSQL、MySQL 等外部数据库，
+//! 或读取目录元数据（`information_schema`、`duckdb_tables()` 等，任何 DDL 都可能改变结果）。
+
+use crate::protocol::CacheStatus;
+use crate::sql::{normalize_sql, tokenize, Token};
+use crate::QueryResult;
+use anyhow::Result;
+use duckdb::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// 默认的内存上限（64 MiB）
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// 只读语句的第一个关键字
+const READ_ONLY_KEYWORDS: &[&str] = &[
+    "select", "with", "from", "values", "table", "summarize", "describe", "pivot", "unpivot",
+];
+
+/// 结果不确定或读取外部数据的函数，调用它们的查询不缓存
+const VOLATILE_FUNCTIONS: &[&str] = &[
+    "random", "uuid", "gen_random_uuid", "setseed", "now", "current_date", "current_time",
+    "current_timestamp", "get_current_time", "get_current_timestamp", "today", "nextval", "currval",
+    "read_csv", "read_csv_auto", "read_parquet", "parquet_scan", "read_json", "read_json_auto",
+    "read_ndjson", "read_ndjson_auto", "read_text", "read_blob", "read_xlsx", "st_read", "glob",
+    "sqlite_scan", "postgres_scan", "postgres_query", "mysql_scan", "mysql_query",
+];
+
+/// 目录元数据的 schema 和视图；`duckdb_*`、`pragma_*` 表函数另行判断
+const CATALOG_SOURCES: &[&str] = &["information_schema", "pg_catalog", "sqlite_master", "sqlite_schema"];
+
+/// 缓存统计
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheStats {
+    /// 是否开启
+    pub enabled: bool,
+    /// 内存上限（字节）
+    pub max_bytes: usize,
+    /// 已缓存结果占用的内存（估算，字节）
+    pub used_bytes: usize,
+    /// 缓存的结果数
+    pub entries: usize,
+    /// 命中次数
+    pub hits: u64,
+    /// 未命中次数
+    pub misses: u64,
+}
+
+/// 缓存键：规范化的 SQL、参数和读取的表的版本
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub(crate) struct CacheKey {
+    sql: String,
+    params: String,
+    /// （表名, 版本），按表名排序
+    tables: Vec<(String, u64)>,
+}
+
+impl CacheKey {
+    /// 估算占用的内存
+    fn size(&self) -> usize {
+        self.sql.len() + self.params.len() + self.tables.iter().map(|(name, _)| name.len() + 8).sum::<usize>()
+    }
+}
+
+/// 查找缓存的结果
+pub(crate) enum Lookup {
+    /// 不缓存（未开启或查询不可缓存）
+    Bypass,
+    /// 命中
+    Hit(QueryResult),
+    /// 未命中，执行后用该键保存结果
+    Miss(CacheKey),
+}
+
+struct CacheEntry {
+    result: QueryResult,
+    size: usize,
+    created: Instant,
+    /// 最近使用的序号，用于淘汰
+    last_used: u64,
+}
+
+struct CacheState {
+    enabled: bool,
+    max_bytes: usize,
+    used_bytes: usize,
+    /// 表名（小写，不带前缀）-> 版本
+    versions: HashMap<String, u64>,
+    entries: HashMap<CacheKey, CacheEntry>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for CacheState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: DEFAULT_MAX_BYTES,
+            used_bytes: 0,
+            versions: HashMap::new(),
+            entries: HashMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+
+impl CacheState {
+    fn version(&self, table: &str) -> u64 {
+        self.versions.get(table).copied().unwrap_or(0)
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.used_bytes -= entry.size;
+        }
+    }
+
+    /// 淘汰最久未使用的结果，直到占用的内存不超过上限
+    fn evict(&mut self) {
+        while self.used_bytes > self.max_bytes {
+            let Some(key) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&key);
+        }
+    }
+}
+
+/// 查询结果缓存
+pub struct ResultCache {
+    conn: Arc<Mutex<Connection>>,
+    state: Mutex<CacheState>,
+}
+
+impl ResultCache {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self {
+            conn,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// 开启或关闭缓存，设置内存上限；关闭时清空缓存
+    pub fn configure(&self, enabled: bool, max_bytes: Option<usize>) -> CacheStats {
+        let mut state = self.state.lock().unwrap();
+        state.enabled = enabled;
+        if let Some(max_bytes) = max_bytes {
+            state.max_bytes = max_bytes;
+        }
+        if !enabled {
+            state.entries.clear();
+            state.used_bytes = 0;
+        }
+        state.evict();
+        drop(state);
+        self.stats()
+    }
+
+    /// 清空缓存的结果和统计
+    pub fn clear(&self) -> CacheStats {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.used_bytes = 0;
+        state.hits = 0;
+        state.misses = 0;
+        drop(state);
+        self.stats()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap();
+        CacheStats {
+            enabled: state.enabled,
+            max_bytes: state.max_bytes,
+            used_bytes: state.used_bytes,
+            entries: state.entries.len(),
+            hits: state.hits,
+            misses: state.misses,
+        }
+    }
+
+    /// 表被修改（新建、删除、修改结构或数据），递增版本并删除读取它的缓存结果
+    ///
+    /// 表名可以带数据库和 schema 前缀，按最后一段（不区分大小写）匹配。
+    pub fn invalidate<S: AsRef<str>>(&self, tables: &[S]) {
+        if tables.is_empty() {
+            return;
+        }
+        let names: BTreeSet<String> = tables.iter().map(|t| unqualified(t.as_ref())).collect();
+        let mut state = self.state.lock().unwrap();
+        for name in &names {
+            *state.versions.entry(name.clone()).or_insert(0) += 1;
+        }
+        let stale: Vec<CacheKey> = state
+            .entries
+            .keys()
+            .filter(|key| key.tables.iter().any(|(table, _)| names.contains(table)))
+            .cloned()
+            .collect();
+        for key in &stale {
+            state.remove(key);
+        }
+    }
+
+    /// SQL 执行完成后调用：修改数据的语句使它涉及的表失效（目录检测不到 `UPDATE` 等修改）
+    pub fn statement_executed(&self, sql: &str) {
+        let tokens = tokenize(sql);
+        if !is_read_only(&tokens) {
+            self.invalidate(&referenced_names(&tokens).into_iter().collect::<Vec<_>>());
+        }
+    }
+
+    /// 查找缓存的结果
+    pub(crate) fn lookup(&self, sql: &str, params: &BTreeMap<String, serde_json::Value>) -> Result<Lookup> {
+        if !self.state.lock().unwrap().enabled {
+            return Ok(Lookup::Bypass);
+        }
+        let tokens = tokenize(sql);
+        if !is_read_only(&tokens) || reads_external_data(&tokens) || reads_catalog(&tokens) {
+            return Ok(Lookup::Bypass);
+        }
+
+        // 视图展开为定义中出现的名称；读取外部数据库的查询不缓存
+        let mut tables = referenced_names(&tokens);
+        let (views, external) = self.catalog_info()?;
+        if tables.iter().any(|name| external.contains(name)) {
+            return Ok(Lookup::Bypass);
+        }
+        let mut pending: Vec<String> = tables.iter().filter(|n| views.contains_key(*n)).cloned().collect();
+        while let Some(view) = pending.pop() {
+            let view_tokens = tokenize(&views[&view]);
+            if reads_external_data(&view_tokens) || reads_catalog(&view_tokens) {
+                return Ok(Lookup::Bypass);
+            }
+            for name in referenced_names(&view_tokens) {
+                if external.contains(&name) {
+                    return Ok(Lookup::Bypass);
+                }
+                if views.contains_key(&name) && !tables.contains(&name) {
+                    pending.push(name.clone());
+                }
+                tables.insert(name);
+            }
+        }
+
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        let key = CacheKey {
+            sql: normalize_sql(sql),
+            params: serde_json::to_string(params)?,
+            tables: tables
+                .into_iter()
+                .map(|name| {
+                    let version = state.version(&name);
+                    (name, version)
+                })
+                .collect(),
+        };
+        state.tick += 1;
+        match state.entries.get_mut(&key) {
+            Some(entry) => {
+                entry.last_used = state.tick;
+                state.hits += 1;
+                let mut result = entry.result.clone();
+                result.cache = Some(CacheStatus::Hit {
+                    age_ms: entry.created.elapsed().as_millis() as u64,
+                });
+                Ok(Lookup::Hit(result))
+            }
+            None => {
+                state.misses += 1;
+                Ok(Lookup::Miss(key))
+            }
+        }
+    }
+
+    /// 保存查询结果；查询期间读取的表已被修改或缓存已关闭时不保存
+    pub(crate) fn insert(&self, key: CacheKey, result: &QueryResult) {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled || key.tables.iter().any(|(table, version)| state.version(table) != *version) {
+            return;
+        }
+        let size = key.size() + result.preview.len() + std::mem::size_of::<CacheEntry>();
+        if size > state.max_bytes {
+            return;
+        }
+        state.remove(&key);
+        state.tick += 1;
+        let entry = CacheEntry {
+            result: QueryResult {
+                cache: None,
+                ..result.clone()
+            },
+            size,
+            created: Instant::now(),
+            last_used: state.tick,
+        };
+        state.used_bytes += size;
+        state.entries.insert(key, entry);
+        state.evict();
+    }
+
+    /// 视图定义（视图名小写 -> SQL）和外部数据库名（小写）
+    fn catalog_info(&self) -> Result<(HashMap<String, String>, BTreeSet<String>)> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT lower(view_name), sql FROM duckdb_views() WHERE NOT internal")?;
+        let views = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))?
+            .collect::<duckdb::Result<Vec<_>>>()?
+            .into_iter()
+            .map(|(name, sql)| (name, sql.unwrap_or_default()))
+            .collect();
+        let mut stmt =
+            conn.prepare("SELECT lower(database_name) FROM duckdb_databases() WHERE NOT internal AND type <> 'duckdb'")?;
+        let external = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<duckdb::Result<BTreeSet<_>>>()?;
+        Ok((views, external))
+    }
+}
+
+/// 表名的最后一段（小写）
+fn unqualified(name: &str) -> String {
+    name.rsplit('.').next().unwrap_or(name).to_lowercase()
+}
+
+/// SQL 中出现的所有名称（关键字也包括在内，只会多算）
+fn referenced_names(tokens: &[Token]) -> BTreeSet<String> {
+    tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Word(name) | Token::Quoted(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 每条语句都是只读的查询
+fn is_read_only(tokens: &[Token]) -> bool {
+    let mut statements = tokens.split(|t| *t == Token::Semicolon).filter(|s| !s.is_empty()).peekable();
+    statements.peek().is_some()
+        && statements.all(|statement| {
+            let first = statement.iter().find_map(|token| match token {
+                Token::Word(word) => Some(word.as_str()),
+                Token::Other('(') => None,
+                _ => Some(""),
+            });
+            first.is_some_and(|word| READ_ONLY_KEYWORDS.contains(&word))
+        })
+}
+
+/// 调用结果不确定的函数，或直接读取文件（`FROM 'data.csv'`）
+fn reads_external_data(tokens: &[Token]) -> bool {
+    tokens.iter().enumerate().any(|(i, token)| {
+        let after_from = i > 0 && matches!(&tokens[i - 1], Token::Word(w) if w == "from" || w == "join");
+        match token {
+            Token::Word(word) => VOLATILE_FUNCTIONS.contains(&word.as_str()),
+            Token::Literal(_) => after_from,
+            Token::Quoted(name) => after_from && (name.contains('.') || name.contains('/')),
+            _ => false,
+        }
+    })
+}
+
+/// 读取目录元数据；只有 `DESCRIBE` 的语句列出所有表
+fn reads_catalog(tokens: &[Token]) -> bool {
+    let metadata = tokens.iter().any(|token| match token {
+        Token::Word(name) | Token::Quoted(name) => {
+            CATALOG_SOURCES.contains(&name.as_str()) || name.starts_with("duckdb_") || name.starts_with("pragma_")
+        }
+        _ => false,
+    });
+    metadata
+        || tokens
+            .split(|t| *t == Token::Semicolon)
+            .any(|statement| matches!(statement, [Token::Word(word)] if word == "describe"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cacheable_queries() {
+        let cacheable = |sql: &str| {
+            let tokens = tokenize(sql);
+            is_read_only(&tokens) && !reads_external_data(&tokens) && !reads_catalog(&tokens)
+        };
+        assert!(cacheable("SELECT region, SUM(amount) FROM sales GROUP BY region"));
+        assert!(cacheable("(SELECT 1); WITH t AS (SELECT 2) SELECT * FROM t;"));
+        assert!(!cacheable("UPDATE sales SET amount = 0"));
+        assert!(!cacheable("SELECT 1; DELETE FROM sales"));
+        assert!(!cacheable("SELECT random()"));
+        assert!(!cacheable("SELECT * FROM 'data/sales.csv'"));
+        assert!(!cacheable("SELECT * FROM \"sales.parquet\""));
+        assert!(!cacheable("SELECT * FROM read_csv('sales.csv')"));
+        assert!(!cacheable(""));
+        // 目录元数据
+        assert!(cacheable("DESCRIBE sales"));
+        assert!(!cacheable("DESCRIBE"));
+        assert!(!cacheable("SELECT table_name FROM information_schema.tables"));
+        assert!(!cacheable("SELECT * FROM duckdb_tables()"));
+        assert!(!cacheable("FROM pragma_table_info('sales')"));
+    }
+
+    #[test]
+    fn test_invalidate_and_evict() {
+        let cache = ResultCache::new(Arc::new(Mutex::new(Connection::open_in_memory().unwrap())));
+        cache.configure(true, None);
+        let result = QueryResult {
+            row_count: 1,
+            column_count: 1,
+            preview: "[]".to_string(),
+            cache: None,
+        };
+        let params = BTreeMap::new();
+
+        let Lookup::Miss(key) = cache.lookup("SELECT * FROM Sales", &params).unwrap() else {
+            panic!("expected a cache miss");
+        };
+        cache.insert(key, &result);
+        let Lookup::Hit(hit) = cache.lookup("select *\n from sales;", &params).unwrap() else {
+            panic!("expected a cache hit");
+        };
+        assert!(matches!(hit.cache, Some(CacheStatus::Hit { .. })));
+
+        // 其他表的修改不影响，读取的表被修改后失效
+        cache.invalidate(&["memory.main.orders"]);
+        assert!(matches!(cache.lookup("SELECT * FROM sales", &params).unwrap(), Lookup::Hit(_)));
+        cache.invalidate(&["memory.main.SALES"]);
+        assert_eq!(cache.stats().entries, 0);
+        assert!(matches!(cache.lookup("SELECT * FROM sales", &params).unwrap(), Lookup::Miss(_)));
+
+        // 超出内存上限时淘汰最久未使用的结果
+        for sql in ["SELECT 1", "SELECT 2"] {
+            let Lookup::Miss(key) = cache.lookup(sql, &params).unwrap() else {
+                panic!("expected a cache miss");
+            };
+            cache.insert(key, &result);
+        }
+        assert_eq!(cache.stats().entries, 2);
+        let one_entry = cache.stats().used_bytes / 2 + 8;
+        cache.configure(true, Some(one_entry));
+        assert_eq!(cache.stats().entries, 1);
+        assert!(matches!(cache.lookup("SELECT 2", &params).unwrap(), Lookup::Hit(_)));
+    }
+}
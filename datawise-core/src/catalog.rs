@@ -0,0 +1,871 @@
+//! 工作区目录
+//!
+//! 列出工作区中的数据库（包括附加的 SQLite/PostgreSQL/MySQL 数据库）、schema、
+//! 表和视图，以及表的列信息和数据来源，供 UI 构建表和列的目录树。
+//!
+//! 数据来源（导入的文件、SQLite 表或远程查询）在导入完成后由 [`Catalog::record_source`] 记录。
+//!
+//! 表管理操作（重命名、删除、复制、清空，把查询保存为视图或表）返回 [`TableChange`]，
+//! 其中的 [`CatalogChange`] 列出新建、删除和修改的表。导入和 SQL 语句引起的变化
+//! 通过对比执行前后的 [`CatalogSnapshot`] 得到。
+
+use crate::protocol::FileFmt;
+use crate::sql::{quote_ident, quote_table_name, tokenize, Token};
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use duckdb::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 不在目录中显示的内部数据库和 schema
+const INTERNAL_FILTER: &str = "t.table_catalog NOT IN ('system', 'temp') \
+     AND t.table_schema NOT IN ('information_schema', 'pg_catalog')";
+
+/// 排除附加的远程数据库（PostgreSQL/MySQL），快照只查询本地的表
+const LOCAL_FILTER: &str = "t.table_catalog NOT IN \
+     (SELECT database_name FROM duckdb_databases() WHERE type IN ('postgres', 'mysql'))";
+
+/// 修改目录或数据的语句中的关键字
+const MODIFYING_KEYWORDS: &[&str] = &[
+    "create", "drop", "alter", "attach", "detach", "insert", "update", "delete", "merge", "truncate", "copy",
+    "import",
+];
+
+/// 工作区中的数据库
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DatabaseInfo {
+    /// 数据库名（附加的数据库即别名）
+    pub name: String,
+    /// 数据库类型，如 `duckdb`、`sqlite`、`postgres`
+    pub kind: String,
+    /// 数据库文件路径（内存数据库和远程数据库为 `None`）
+    pub path: Option<String>,
+    /// 是否只读
+    pub read_only: bool,
+}
+
+/// 数据库中的 schema
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SchemaInfo {
+    /// 所属数据库
+    pub database: String,
+    /// schema 名
+    pub name: String,
+    /// 表和视图的数量
+    pub table_count: u64,
+}
+
+/// 表或视图
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TableInfo {
+    /// 所属数据库
+    pub database: String,
+    /// 所属 schema
+    pub schema: String,
+    /// 表名
+    pub name: String,
+    /// `BASE TABLE` 或 `VIEW`
+    pub table_type: String,
+    /// 行数（视图为 `None`；列表中为 DuckDB 的估计值，描述单张表时为精确值）
+    pub row_count: Option<u64>,
+    /// 列信息（按列顺序）
+    pub columns: Vec<ColumnInfo>,
+    /// 数据来源（不是由导入创建的表为 `None`）
+    pub source: Option<TableSource>,
+}
+
+impl TableInfo {
+    /// 加引号的 `"数据库"."schema"."表名"`，可以直接拼接到 SQL 中
+    pub fn quoted_name(&self) -> String {
+        [&self.database, &self.schema, &self.name].map(|s| quote_ident(s)).join(".")
+    }
+}
+
+/// 表中的列
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ColumnInfo {
+    /// 列名
+    pub name: String,
+    /// DuckDB 类型
+    pub data_type: String,
+    /// 是否允许 NULL
+    pub nullable: bool,
+}
+
+/// 表的数据来源
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TableSource {
+    /// 从文件导入
+    File {
+        /// 导入路径（文件、glob 模式或目录，包括额外路径）
+        paths: Vec<String>,
+        /// 文件格式
+        fmt: FileFmt,
+    },
+    /// 从 SQLite 数据库导入
+    Sqlite {
+        /// 数据库文件路径
+        path: String,
+        /// SQLite 中的表名
+        table: String,
+    },
+    /// 从 PostgreSQL/MySQL 查询结果导入
+    Database {
+        /// 连接配置名
+        profile: String,
+        /// 远程执行的 SQL
+        sql: String,
+    },
+    /// 由查询保存的视图或表
+    Query {
+        /// 查询 SQL
+        sql: String,
+    },
+}
+
+/// 目录变化，表名为 `数据库.schema.表名`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CatalogChange {
+    /// 新建的表和视图
+    pub created: Vec<String>,
+    /// 删除的表和视图
+    pub dropped: Vec<String>,
+    /// 结构或数据被修改的表和视图
+    pub altered: Vec<String>,
+}
+
+impl CatalogChange {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.dropped.is_empty() && self.altered.is_empty()
+    }
+}
+
+/// 目录快照：每张表的类型、列和估计行数，用于对比得到 [`CatalogChange`]
+pub struct CatalogSnapshot {
+    /// `数据库.schema.表名` -> 表的签名
+    tables: HashMap<String, TableSignature>,
+}
+
+#[derive(PartialEq)]
+struct TableSignature {
+    table_type: String,
+    columns: Vec<ColumnInfo>,
+    estimated_size: Option<i64>,
+}
+
+/// 表管理操作的结果，以 JSON 放在 `Finished.preview` 中
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TableChange {
+    /// 操作后的表或视图（删除时为 `None`）
+    pub table: Option<TableInfo>,
+    /// 受影响的行数：删除、清空的行数，或复制、物化写入的行数
+    pub rows_affected: u64,
+    /// 目录变化（预览时为空）
+    pub change: CatalogChange,
+    /// 引用了被删除表的视图（`数据库.schema.视图名`），删除后这些视图无法查询
+    #[serde(default)]
+    pub dependent_views: Vec<String>,
+}
+
+/// 工作区目录，记录导入表的数据来源
+pub struct Catalog {
+    conn: Arc<Mutex<Connection>>,
+    /// 数据来源（小写的 `数据库.schema.表名` -> 来源）
+    sources: DashMap<String, TableSource>,
+}
+
+impl Catalog {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self {
+            conn,
+            sources: DashMap::new(),
+        }
+    }
+
+    /// 清除被删除的表（`数据库.schema.表名`）的数据来源记录
+    pub fn forget_sources(&self, tables: &[String]) {
+        for name in tables {
+            self.sources.remove(&name.to_lowercase());
+        }
+    }
+
+    /// 清除表（按 DuckDB 的名称解析规则查找）的数据来源记录，表不存在时什么也不做
+    pub fn forget_source(&self, table: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        if let Some(table) = find_table(&conn, table)? {
+            self.sources.remove(&table.key());
+        }
+        Ok(())
+    }
+
+    /// 记录表的数据来源，覆盖之前的记录
+    pub fn record_source(&self, table: &str, source: TableSource) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let table = resolve_table(&conn, table)?;
+        self.sources.insert(table.key(), source);
+        Ok(())
+    }
+
+    /// 记录当前目录的快照
+    ///
+    /// 只包括本地的表（内存数据库、附加的 DuckDB/SQLite 文件），不查询远程数据库。
+    pub fn snapshot(&self) -> Result<CatalogSnapshot> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT t.table_catalog, t.table_schema, t.table_name, t.table_type, d.estimated_size \
+             FROM information_schema.tables t \
+             LEFT JOIN duckdb_tables() d \
+               ON d.database_name = t.table_catalog AND d.schema_name = t.table_schema \
+              AND d.table_name = t.table_name \
+             WHERE {} AND {}",
+            INTERNAL_FILTER, LOCAL_FILTER
+        ))?;
+        let mut rows = stmt.query([])?;
+
+        let mut columns = list_columns(&conn, None, None, None, true)?;
+        let mut tables = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let key: (String, String, String) = (row.get(0)?, row.get(1)?, row.get(2)?);
+            let signature = TableSignature {
+                table_type: row.get(3)?,
+                estimated_size: row.get(4)?,
+                columns: columns.remove(&key).unwrap_or_default(),
+            };
+            tables.insert(format!("{}.{}.{}", key.0, key.1, key.2), signature);
+        }
+        Ok(CatalogSnapshot { tables })
+    }
+
+    /// 对比 `before` 与当前目录，得到新建、删除和修改的表
+    ///
+    /// 表的类型、列或估计行数变化时算作修改；只改变数据、不改变行数的修改（如 `UPDATE`）
+    /// 无法从快照中看出，需要由调用方放在 `touched` 中（见 [`modified_tables`]）。
+    /// `touched` 中的表（如导入时覆盖的表）即使类型、列和行数都没有变化也算作修改。
+    pub fn changes_since(&self, before: &CatalogSnapshot, touched: &[String]) -> Result<CatalogChange> {
+        let after = self.snapshot()?;
+
+        let touched = {
+            let conn = self.conn.lock().unwrap();
+            touched
+                .iter()
+                .map(|name| Ok(find_table(&conn, name)?.map(|t| t.qualified_name())))
+                .filter_map(Result::transpose)
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let mut change = CatalogChange::default();
+        for (name, signature) in &after.tables {
+            match before.tables.get(name) {
+                None => change.created.push(name.clone()),
+                Some(previous) if previous != signature || touched.contains(name) => change.altered.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        for name in before.tables.keys() {
+            if !after.tables.contains_key(name) {
+                change.dropped.push(name.clone());
+            }
+        }
+
+        change.created.sort();
+        change.dropped.sort();
+        change.altered.sort();
+        Ok(change)
+    }
+
+    /// 列出数据库（不包括 DuckDB 内部的 `system`、`temp`）
+    pub fn list_databases(&self) -> Result<Vec<DatabaseInfo>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT database_name, type, path, readonly FROM duckdb_databases() \
+             WHERE NOT internal ORDER BY database_name",
+        )?;
+        let databases = stmt
+            .query_map([], |row| {
+                Ok(DatabaseInfo {
+                    name: row.get(0)?,
+                    kind: row.get(1)?,
+                    path: row.get(2)?,
+                    read_only: row.get(3)?,
+                })
+            })?
+            .collect::<duckdb::Result<Vec<_>>>()
+            .context("Failed to list databases")?;
+        Ok(databases)
+    }
+
+    /// 列出 schema，`database` 为 `None` 时列出所有数据库中的 schema
+    pub fn list_schemas(&self, database: Option<&str>) -> Result<Vec<SchemaInfo>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT s.database_name, s.schema_name, COUNT(t.table_name) \
+             FROM duckdb_schemas() s \
+             LEFT JOIN information_schema.tables t \
+               ON t.table_catalog = s.database_name AND t.table_schema = s.schema_name \
+             WHERE s.database_name NOT IN ('system', 'temp') \
+               AND s.schema_name NOT IN ('information_schema', 'pg_catalog') \
+               AND ($1 IS NULL OR s.database_name = $1) \
+             GROUP BY s.database_name, s.schema_name \
+             ORDER BY s.database_name, s.schema_name",
+        )?;
+        let schemas = stmt
+            .query_map([database], |row| {
+                Ok(SchemaInfo {
+                    database: row.get(0)?,
+                    name: row.get(1)?,
+                    table_count: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<duckdb::Result<Vec<_>>>()
+            .context("Failed to list schemas")?;
+        Ok(schemas)
+    }
+
+    /// 列出表和视图及其列，可按数据库和 schema 过滤
+    pub fn list_tables(&self, database: Option<&str>, schema: Option<&str>) -> Result<Vec<TableInfo>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT t.table_catalog, t.table_schema, t.table_name, t.table_type, d.estimated_size \
+             FROM information_schema.tables t \
+             LEFT JOIN duckdb_tables() d \
+               ON d.database_name = t.table_catalog AND d.schema_name = t.table_schema \
+              AND d.table_name = t.table_name \
+             WHERE {} AND ($1 IS NULL OR t.table_catalog = $1) AND ($2 IS NULL OR t.table_schema = $2) \
+             ORDER BY t.table_catalog, t.table_schema, t.table_name",
+            INTERNAL_FILTER
+        ))?;
+        let mut tables = stmt
+            .query_map([database, schema], |row| {
+                let database: String = row.get(0)?;
+                let schema: String = row.get(1)?;
+                let name: String = row.get(2)?;
+                let table_type: String = row.get(3)?;
+                let estimated_size: Option<i64> = row.get(4)?;
+                Ok(TableInfo {
+                    source: self.sources.get(&source_key(&database, &schema, &name)).map(|s| s.clone()),
+                    row_count: match table_type.as_str() {
+                        "VIEW" => None,
+                        _ => estimated_size.map(|n| n as u64),
+                    },
+                    database,
+                    schema,
+                    name,
+                    table_type,
+                    columns: Vec::new(),
+                })
+            })?
+            .collect::<duckdb::Result<Vec<_>>>()
+            .context("Failed to list tables")?;
+
+        let mut columns = list_columns(&conn, database, schema, None, false)?;
+        for table in &mut tables {
+            let key = (table.database.clone(), table.schema.clone(), table.name.clone());
+            table.columns = columns.remove(&key).unwrap_or_default();
+        }
+        Ok(tables)
+    }
+
+    /// 描述单张表或视图，行数为精确值
+    ///
+    /// `name` 可以是 `表名`、`schema.表名` 或 `数据库.schema.表名`，
+    /// 也可以是附加数据库中的 `别名.表名`。
+    pub fn describe_table(&self, name: &str) -> Result<TableInfo> {
+        let conn = self.conn.lock().unwrap();
+        let table = resolve_table(&conn, name)?;
+        self.describe(&conn, table)
+    }
+
+    /// 重命名表或视图，`new_name` 不带 schema，表留在原来的 schema 中
+    pub fn rename_table(&self, name: &str, new_name: &str) -> Result<TableChange> {
+        if new_name.contains('.') {
+            bail!("New table name must not include a schema or database: {}", new_name);
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let table = resolve_table(&conn, name)?;
+        conn.execute(
+            &format!("ALTER {} {} RENAME TO {}", table.kind_sql(), table.quoted(), quote_ident(new_name)),
+            [],
+        )
+        .with_context(|| format!("Failed to rename table: {}", table.qualified_name()))?;
+
+        let renamed = ResolvedTable {
+            name: new_name.to_string(),
+            ..table.clone()
+        };
+        if let Some((_, source)) = self.sources.remove(&table.key()) {
+            self.sources.insert(renamed.key(), source);
+        }
+
+        let change = CatalogChange {
+            created: vec![renamed.qualified_name()],
+            dropped: vec![table.qualified_name()],
+            ..Default::default()
+        };
+        Ok(TableChange {
+            table: Some(self.describe(&conn, renamed)?),
+            rows_affected: 0,
+            change,
+            dependent_views: Vec::new(),
+        })
+    }
+
+    /// 删除表或视图，`rows_affected` 是删除的表中的行数，`dependent_views` 是引用它的视图
+    ///
+    /// `dry_run` 为 true 时只统计，不删除；此时 `table` 是将被删除的表，目录变化为空。
+    pub fn drop_table(&self, name: &str, dry_run: bool) -> Result<TableChange> {
+        let conn = self.conn.lock().unwrap();
+        let table = resolve_table(&conn, name)?;
+        let rows = if table.is_view() { 0 } else { count_rows(&conn, &table)? };
+        let dependent_views = dependent_views(&conn, &table)?;
+        if dry_run {
+            return Ok(TableChange {
+                table: Some(self.describe(&conn, table)?),
+                rows_affected: rows,
+                change: CatalogChange::default(),
+                dependent_views,
+            });
+        }
+
+        conn.execute(&format!("DROP {} {}", table.kind_sql(), table.quoted()), [])
+            .with_context(|| format!("Failed to drop table: {}", table.qualified_name()))?;
+        self.sources.remove(&table.key());
+
+        Ok(TableChange {
+            table: None,
+            rows_affected: rows,
+            change: CatalogChange {
+                dropped: vec![table.qualified_name()],
+                ..Default::default()
+            },
+            dependent_views,
+        })
+    }
+
+    /// 把表（或视图的结果）复制为新表，数据来源随之复制
+    ///
+    /// `schema_only` 为 true 时只复制列，不复制数据。新表不保留原表的约束。
+    pub fn duplicate_table(&self, name: &str, new_name: &str, schema_only: bool) -> Result<TableChange> {
+        let conn = self.conn.lock().unwrap();
+        let table = resolve_table(&conn, name)?;
+
+        let limit = if schema_only { " LIMIT 0" } else { "" };
+        conn.execute(
+            &format!("CREATE TABLE {} AS SELECT * FROM {}{}", quote_table_name(new_name), table.quoted(), limit),
+            [],
+        )
+        .with_context(|| format!("Failed to duplicate table {} as {}", table.qualified_name(), new_name))?;
+
+        let copy = resolve_table(&conn, new_name)?;
+        if let Some(source) = self.sources.get(&table.key()).map(|s| s.clone()) {
+            self.sources.insert(copy.key(), source);
+        }
+
+        let change = CatalogChange {
+            created: vec![copy.qualified_name()],
+            ..Default::default()
+        };
+        let info = self.describe(&conn, copy)?;
+        Ok(TableChange {
+            rows_affected: info.row_count.unwrap_or(0),
+            table: Some(info),
+            change,
+            dependent_views: Vec::new(),
+        })
+    }
+
+    /// 清空表中的数据，保留表结构，`rows_affected` 是删除的行数
+    ///
+    /// `dry_run` 为 true 时只统计将被删除的行数，不修改表，目录变化为空。
+    pub fn truncate_table(&self, name: &str, dry_run: bool) -> Result<TableChange> {
+        let conn = self.conn.lock().unwrap();
+        let table = resolve_table(&conn, name)?;
+        if table.is_view() {
+            bail!("Cannot truncate a view: {}", table.qualified_name());
+        }
+        if dry_run {
+            let rows = count_rows(&conn, &table)?;
+            return Ok(TableChange {
+                table: Some(self.describe(&conn, table)?),
+                rows_affected: rows,
+                change: CatalogChange::default(),
+                dependent_views: Vec::new(),
+            });
+        }
+
+        let rows = conn
+            .execute(&format!("DELETE FROM {}", table.quoted()), [])
+            .with_context(|| format!("Failed to truncate table: {}", table.qualified_name()))?;
+
+        let change = CatalogChange {
+            altered: vec![table.qualified_name()],
+            ..Default::default()
+        };
+        Ok(TableChange {
+            table: Some(self.describe(&conn, table)?),
+            rows_affected: rows as u64,
+            change,
+            dependent_views: Vec::new(),
+        })
+    }
+
+    /// 把查询保存为视图，`materialize` 为 true 时保存为表
+    ///
+    /// 同名的表或视图已存在时，只有 `overwrite` 为 true 才替换（类型可以不同）。
+    pub fn save_query(&self, sql: &str, name: &str, materialize: bool, overwrite: bool) -> Result<TableChange> {
+        let sql = sql.trim().trim_end_matches(';');
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        let existing = find_table(&tx, name)?;
+        if let Some(existing) = &existing {
+            if !overwrite {
+                bail!("Table already exists: {}", existing.qualified_name());
+            }
+            tx.execute(&format!("DROP {} {}", existing.kind_sql(), existing.quoted()), [])?;
+        }
+
+        let kind = if materialize { "TABLE" } else { "VIEW" };
+        tx.execute(&format!("CREATE {} {} AS {}", kind, quote_table_name(name), sql), [])
+            .with_context(|| format!("Failed to save query as {}: {}", kind.to_lowercase(), name))?;
+
+        let saved = resolve_table(&tx, name)?;
+        let change = match existing {
+            Some(_) => CatalogChange {
+                altered: vec![saved.qualified_name()],
+                ..Default::default()
+            },
+            None => CatalogChange {
+                created: vec![saved.qualified_name()],
+                ..Default::default()
+            },
+        };
+        tx.commit().context("Failed to commit saved query")?;
+        self.sources.insert(saved.key(), TableSource::Query { sql: sql.to_string() });
+
+        let info = self.describe(&conn, saved)?;
+        Ok(TableChange {
+            rows_affected: info.row_count.unwrap_or(0),
+            table: Some(info),
+            change,
+            dependent_views: Vec::new(),
+        })
+    }
+
+    fn describe(&self, conn: &Connection, table: ResolvedTable) -> Result<TableInfo> {
+        let row_count = if table.is_view() {
+            None
+        } else {
+            Some(count_rows(conn, &table)?)
+        };
+
+        let columns = list_columns(conn, Some(&table.database), Some(&table.schema), Some(&table.name), false)?
+            .into_values()
+            .next()
+            .unwrap_or_default();
+
+        Ok(TableInfo {
+            source: self.sources.get(&table.key()).map(|s| s.clone()),
+            database: table.database,
+            schema: table.schema,
+            name: table.name,
+            table_type: table.table_type,
+            row_count,
+            columns,
+        })
+    }
+}
+
+/// 解析后的表或视图
+#[derive(Clone)]
+struct ResolvedTable {
+    database: String,
+    schema: String,
+    name: String,
+    table_type: String,
+}
+
+impl ResolvedTable {
+    fn is_view(&self) -> bool {
+        self.table_type == "VIEW"
+    }
+
+    /// `数据库.schema.表名`
+    fn qualified_name(&self) -> String {
+        format!("{}.{}.{}", self.database, self.schema, self.name)
+    }
+
+    /// 加引号的完整表名，用于拼接 SQL
+    fn quoted(&self) -> String {
+        [&self.database, &self.schema, &self.name].map(|s| quote_ident(s)).join(".")
+    }
+
+    /// 数据来源的键
+    fn key(&self) -> String {
+        source_key(&self.database, &self.schema, &self.name)
+    }
+
+    /// DDL 中的对象类型
+    fn kind_sql(&self) -> &'static str {
+        if self.is_view() {
+            "VIEW"
+        } else {
+            "TABLE"
+        }
+    }
+}
+
+/// 引用了表的视图（按视图 SQL 中出现的名称判断，是近似的）
+///
+/// DuckDB 不记录视图对表的依赖，删除表后视图仍然存在，但查询时报错。
+fn dependent_views(conn: &Connection, table: &ResolvedTable) -> Result<Vec<String>> {
+    let name = table.name.to_lowercase();
+    let mut stmt = conn.prepare(
+        "SELECT database_name, schema_name, view_name, sql FROM duckdb_views() \
+         WHERE NOT internal ORDER BY ALL",
+    )?;
+    let mut rows = stmt.query([])?;
+    let mut views = Vec::new();
+    while let Some(row) = rows.next()? {
+        let view = ResolvedTable {
+            database: row.get(0)?,
+            schema: row.get(1)?,
+            name: row.get(2)?,
+            table_type: "VIEW".to_string(),
+        };
+        let sql: String = row.get(3)?;
+        if references_name(&sql, &name) && view.key() != table.key() {
+            views.push(view.qualified_name());
+        }
+    }
+    Ok(views)
+}
+
+/// SQL 中是否出现名称 `name`（小写），字符串和注释中的不算
+fn references_name(sql: &str, name: &str) -> bool {
+    tokenize(sql)
+        .iter()
+        .any(|token| matches!(token, Token::Word(word) | Token::Quoted(word) if word == name))
+}
+
+fn count_rows(conn: &Connection, table: &ResolvedTable) -> Result<u64> {
+    let count: i64 = conn
+        .query_row(&format!("SELECT COUNT(*) FROM {}", table.quoted()), [], |row| row.get(0))
+        .with_context(|| format!("Failed to count rows of table: {}", table.qualified_name()))?;
+    Ok(count as u64)
+}
+
+/// SQL 是否可能改变目录（建表、删表、修改表结构、附加数据库或增删改数据）
+///
+/// 检查所有语句中的关键字（跳过字符串字面量、带引号的名称和注释），可以识别
+/// `WITH ... INSERT` 等写法；用于决定是否需要对比执行前后的目录快照。
+pub fn may_change_catalog(sql: &str) -> bool {
+    tokenize(sql)
+        .iter()
+        .any(|token| matches!(token, Token::Word(word) if MODIFYING_KEYWORDS.contains(&word.as_str())))
+}
+
+/// SQL 中 `INSERT INTO`、`UPDATE`、`DELETE FROM`、`MERGE INTO`、`TRUNCATE` 修改的表
+///
+/// 目录快照看不出不改变行数的数据修改，执行这些语句后把目标表算作修改。
+pub fn modified_tables(sql: &str) -> Vec<String> {
+    let tokens = tokenize(sql);
+    let word = |i: usize, expected: &str| matches!(tokens.get(i), Some(Token::Word(w)) if w == expected);
+
+    let mut tables = Vec::new();
+    for i in 0..tokens.len() {
+        let start = if word(i, "update") || word(i, "into") {
+            i + 1
+        } else if word(i, "delete") && word(i + 1, "from") {
+            i + 2
+        } else if word(i, "truncate") {
+            if word(i + 1, "table") { i + 2 } else { i + 1 }
+        } else {
+            continue;
+        };
+
+        if let Some(name) = name_at(&tokens, start) {
+            if !tables.contains(&name) {
+                tables.push(name);
+            }
+        }
+    }
+    tables
+}
+
+/// SQL 中 `CREATE OR REPLACE TABLE/VIEW` 替换的表
+///
+/// 被替换的表内容来自新的 SQL，原来的数据来源不再适用。
+pub fn replaced_tables(sql: &str) -> Vec<String> {
+    let tokens = tokenize(sql);
+    let word = |i: usize, expected: &str| matches!(tokens.get(i), Some(Token::Word(w)) if w == expected);
+
+    let mut tables = Vec::new();
+    for i in 0..tokens.len() {
+        if !(word(i, "create") && word(i + 1, "or") && word(i + 2, "replace")) {
+            continue;
+        }
+        let mut j = i + 3;
+        if word(j, "temp") || word(j, "temporary") {
+            j += 1;
+        }
+        if !(word(j, "table") || word(j, "view")) {
+            continue;
+        }
+        if let Some(name) = name_at(&tokens, j + 1) {
+            if !tables.contains(&name) {
+                tables.push(name);
+            }
+        }
+    }
+    tables
+}
+
+/// 从 `start` 开始的（可能带前缀的）名称，如 main.sales
+fn name_at(tokens: &[Token], start: usize) -> Option<String> {
+    let mut parts = Vec::new();
+    let mut j = start;
+    while let Some(Token::Word(part) | Token::Quoted(part)) = tokens.get(j) {
+        parts.push(part.as_str());
+        if tokens.get(j + 1) != Some(&Token::Dot) {
+            break;
+        }
+        j += 2;
+    }
+    (!parts.is_empty()).then(|| parts.join("."))
+}
+
+/// 查询列信息，按（数据库, schema, 表名）分组；`local_only` 时不查询远程数据库
+fn list_columns(
+    conn: &Connection,
+    database: Option<&str>,
+    schema: Option<&str>,
+    table: Option<&str>,
+    local_only: bool,
+) -> Result<HashMap<(String, String, String), Vec<ColumnInfo>>> {
+    let filter = if local_only {
+        format!("{} AND {}", INTERNAL_FILTER, LOCAL_FILTER)
+    } else {
+        INTERNAL_FILTER.to_string()
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT t.table_catalog, t.table_schema, t.table_name, c.column_name, c.data_type, c.is_nullable \
+         FROM information_schema.columns c \
+         JOIN information_schema.tables t \
+           ON t.table_catalog = c.table_catalog AND t.table_schema = c.table_schema \
+          AND t.table_name = c.table_name \
+         WHERE {} AND ($1 IS NULL OR t.table_catalog = $1) AND ($2 IS NULL OR t.table_schema = $2) \
+           AND ($3 IS NULL OR t.table_name = $3) \
+         ORDER BY t.table_catalog, t.table_schema, t.table_name, c.ordinal_position",
+        filter
+    ))?;
+    let mut rows = stmt.query([database, schema, table])?;
+
+    let mut columns: HashMap<_, Vec<ColumnInfo>> = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let key = (row.get(0)?, row.get(1)?, row.get(2)?);
+        let is_nullable: String = row.get(5)?;
+        columns.entry(key).or_default().push(ColumnInfo {
+            name: row.get(3)?,
+            data_type: row.get(4)?,
+            nullable: is_nullable == "YES",
+        });
+    }
+    Ok(columns)
+}
+
+/// 按 DuckDB 的名称解析规则找到表，不存在时报错
+fn resolve_table(conn: &Connection, name: &str) -> Result<ResolvedTable> {
+    find_table(conn, name)?.with_context(|| format!("Table not found: {}", name))
+}
+
+/// 按 DuckDB 的名称解析规则查找表或视图
+///
+/// 名称不区分大小写。两段式名称先按 `schema.表名` 在当前数据库中查找，
+/// 再按 `数据库.表名` 在该数据库的默认 schema 中查找。
+fn find_table(conn: &Connection, name: &str) -> Result<Option<ResolvedTable>> {
+    let parts: Vec<&str> = name.split('.').collect();
+    let candidates: Vec<(Option<&str>, Option<&str>, &str)> = match parts.as_slice() {
+        [table] => vec![(None, None, *table)],
+        [first, table] => vec![(None, Some(*first), *table), (Some(*first), Some("main"), *table)],
+        [database, schema, table] => vec![(Some(*database), Some(*schema), *table)],
+        _ => bail!("Invalid table name: {}", name),
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT t.table_catalog, t.table_schema, t.table_name, t.table_type \
+         FROM information_schema.tables t \
+         WHERE {} \
+           AND lower(t.table_catalog) = lower(coalesce($1, current_database())) \
+           AND lower(t.table_schema) = lower(coalesce($2, current_schema())) \
+           AND lower(t.table_name) = lower($3)",
+        INTERNAL_FILTER
+    ))?;
+    for (database, schema, table) in candidates {
+        let mut rows = stmt.query(duckdb::params![database, schema, table])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(ResolvedTable {
+                database: row.get(0)?,
+                schema: row.get(1)?,
+                name: row.get(2)?,
+                table_type: row.get(3)?,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+fn source_key(database: &str, schema: &str, name: &str) -> String {
+    format!("{}.{}.{}", database, schema, name).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_may_change_catalog() {
+        assert!(may_change_catalog("CREATE TABLE t AS SELECT 1"));
+        assert!(may_change_catalog("  -- 清理\n  drop view v"));
+        assert!(may_change_catalog("SELECT 1; INSERT INTO t VALUES (2)"));
+        assert!(!may_change_catalog("SELECT * FROM created_tables"));
+        assert!(!may_change_catalog("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(may_change_catalog("WITH x AS (SELECT 1) INSERT INTO t SELECT * FROM x"));
+        assert!(may_change_catalog("update t SET a = 1"));
+        // 字符串、带引号的名称和注释中的关键字不算
+        assert!(!may_change_catalog("SELECT 'a; DROP TABLE t', \"delete\" FROM t -- insert\n/* update */"));
+    }
+
+    #[test]
+    fn test_modified_tables() {
+        assert_eq!(
+            modified_tables(
+                "WITH x AS (SELECT 1) INSERT INTO main.\"Sales\" SELECT * FROM x; \
+                 UPDATE orders SET note = 'update logs'; DELETE FROM orders; TRUNCATE TABLE logs"
+            ),
+            ["main.sales", "orders", "logs"]
+        );
+        assert!(modified_tables("SELECT * FROM t").is_empty());
+    }
+
+    #[test]
+    fn test_replaced_tables() {
+        assert_eq!(
+            replaced_tables("CREATE OR REPLACE TABLE main.Orders AS SELECT 1; create or replace temp view v AS SELECT 2"),
+            ["main.orders", "v"]
+        );
+        assert!(replaced_tables("CREATE TABLE t AS SELECT 1").is_empty());
+        assert!(replaced_tables("INSERT OR REPLACE INTO t VALUES (1)").is_empty());
+    }
+
+    #[test]
+    fn test_references_name() {
+        assert!(references_name("SELECT * FROM main.Orders o WHERE o.total > 0", "orders"));
+        assert!(references_name("SELECT * FROM \"Orders\"", "orders"));
+        assert!(!references_name("SELECT * FROM orders_2024", "orders"));
+        assert!(!references_name("SELECT 'orders' AS label -- orders", "orders"));
+    }
+}
@@ -0,0 +1,251 @@
+//! CSV 方言
+//!
+//! 把 [`CsvDialect`] 转换为 DuckDB `read_csv` 的参数和 `COPY … (FORMAT CSV)` 的选项。
+//!
+//! DuckDB 只读写 UTF-8、以 `\n` 换行的 CSV，其余部分在文件层面处理：
+//! - 导入 GBK 文件前先转码为 UTF-8 临时文件
+//! - 导出时 DuckDB 先写入临时文件，再按需改写换行符、添加 BOM 并转码
+
+use crate::protocol::{CsvDialect, LineEnding, TextEncoding};
+use crate::sql::quote_literal;
+use anyhow::{bail, Context, Result};
+use encoding_rs::GBK;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// 未设置时导出使用的分隔符和引号（与 DuckDB 的默认值相同）
+const DEFAULT_DELIMITER: char = ',';
+const DEFAULT_QUOTE: char = '"';
+
+/// `read_csv` 的方言参数，如 `delim = ';'`、`header = false`
+///
+/// 只传入设置了的分隔符、引号和 NULL 字符串，其余由 DuckDB 自动识别，
+/// 例如只设置编码的 GBK 文件仍能识别出分号分隔。`header` 总是传入，与不指定方言时一致。
+pub fn reader_args(dialect: &CsvDialect) -> Result<Vec<String>> {
+    validate(dialect)?;
+
+    let mut args = vec![format!("header = {}", dialect.header)];
+    if let Some(delimiter) = dialect.delimiter {
+        args.push(format!("delim = {}", quote_literal(&delimiter.to_string())));
+    }
+    if let Some(quote) = dialect.quote {
+        args.push(format!("quote = {}", quote_literal(&quote.to_string())));
+    }
+    if let Some(null_string) = &dialect.null_string {
+        args.push(format!("nullstr = {}", quote_literal(null_string)));
+    }
+    if let Some(format) = &dialect.date_format {
+        args.push(format!("dateformat = {}", quote_literal(format)));
+    }
+    if let Some(format) = &dialect.timestamp_format {
+        args.push(format!("timestampformat = {}", quote_literal(format)));
+    }
+    Ok(args)
+}
+
+/// `COPY … TO` 的 CSV 选项
+pub fn copy_options(dialect: &CsvDialect) -> Result<String> {
+    validate(dialect)?;
+
+    let mut options = vec![
+        "FORMAT CSV".to_string(),
+        format!("DELIMITER {}", quote_literal(&delimiter(dialect).to_string())),
+        format!("HEADER {}", dialect.header),
+        format!("QUOTE {}", quote_literal(&quote(dialect).to_string())),
+        format!("NULLSTR {}", quote_literal(dialect.null_string.as_deref().unwrap_or_default())),
+    ];
+    if dialect.force_quote {
+        options.push("FORCE_QUOTE *".to_string());
+    }
+    if let Some(format) = &dialect.date_format {
+        options.push(format!("DATEFORMAT {}", quote_literal(format)));
+    }
+    if let Some(format) = &dialect.timestamp_format {
+        options.push(format!("TIMESTAMPFORMAT {}", quote_literal(format)));
+    }
+    Ok(options.join(", "))
+}
+
+/// DuckDB 写出的文件是否还需要改写（换行符、BOM、编码）
+pub fn needs_rewrite(dialect: &CsvDialect) -> bool {
+    dialect.line_ending != LineEnding::Lf || dialect.bom || dialect.encoding != TextEncoding::Utf8
+}
+
+/// 把 DuckDB 写出的 UTF-8、`\n` 换行的 CSV 按方言改写到 `target`
+///
+/// 只替换记录之间的换行符，引号内字段中的换行保持不变。
+pub fn rewrite_output(source: &Path, target: &Path, dialect: &CsvDialect) -> Result<()> {
+    let mut reader = BufReader::new(File::open(source).with_context(|| format!("Failed to open file: {:?}", source))?);
+    let mut writer = BufWriter::new(File::create(target).with_context(|| format!("Failed to create file: {:?}", target))?);
+
+    if dialect.bom {
+        writer.write_all(UTF8_BOM)?;
+    }
+
+    let quote = quote(dialect) as u8;
+    let mut in_quotes = false;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let has_newline = line.last() == Some(&b'\n');
+        if has_newline {
+            line.pop();
+        }
+        // 转义的引号成对出现，奇数个引号表示进入或离开引号内
+        if line.iter().filter(|&&b| b == quote).count() % 2 == 1 {
+            in_quotes = !in_quotes;
+        }
+
+        match dialect.encoding {
+            TextEncoding::Utf8 => writer.write_all(&line)?,
+            TextEncoding::Gbk => {
+                let text = std::str::from_utf8(&line).context("CSV output is not valid UTF-8")?;
+                let (encoded, _, unmappable) = GBK.encode(text);
+                if unmappable {
+                    bail!("CSV output contains characters that cannot be encoded as GBK: {}", text);
+                }
+                writer.write_all(&encoded)?;
+            }
+        }
+
+        if has_newline {
+            match dialect.line_ending {
+                LineEnding::CrLf if !in_quotes => writer.write_all(b"\r\n")?,
+                _ => writer.write_all(b"\n")?,
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// 把非 UTF-8 编码的 CSV 转码为 UTF-8 写入 `target`
+pub fn decode_to_utf8(source: &Path, target: &Path, encoding: TextEncoding) -> Result<()> {
+    let encoding = match encoding {
+        TextEncoding::Utf8 => bail!("File is already UTF-8: {:?}", source),
+        TextEncoding::Gbk => GBK,
+    };
+
+    let mut input = File::open(source).with_context(|| format!("Failed to open file: {:?}", source))?;
+    let mut output = BufWriter::new(File::create(target).with_context(|| format!("Failed to create file: {:?}", target))?);
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut text = String::new();
+    loop {
+        let n = input.read(&mut buf)?;
+        let last = n == 0;
+        text.clear();
+        text.reserve(decoder.max_utf8_buffer_length(n).unwrap_or(n * 3 + 4));
+        let (_, _, had_errors) = decoder.decode_to_string(&buf[..n], &mut text, last);
+        if had_errors {
+            bail!("File is not valid {}: {:?}", encoding.name(), source);
+        }
+        output.write_all(text.as_bytes())?;
+        if last {
+            break;
+        }
+    }
+
+    output.flush()?;
+    Ok(())
+}
+
+fn delimiter(dialect: &CsvDialect) -> char {
+    dialect.delimiter.unwrap_or(DEFAULT_DELIMITER)
+}
+
+fn quote(dialect: &CsvDialect) -> char {
+    dialect.quote.unwrap_or(DEFAULT_QUOTE)
+}
+
+fn validate(dialect: &CsvDialect) -> Result<()> {
+    let (delimiter, quote) = (delimiter(dialect), quote(dialect));
+    if !delimiter.is_ascii() || matches!(delimiter, '\r' | '\n') {
+        bail!("CSV delimiter must be an ASCII character other than a line break: {:?}", delimiter);
+    }
+    if !quote.is_ascii() || quote == delimiter {
+        bail!("CSV quote must be an ASCII character different from the delimiter: {:?}", quote);
+    }
+    if dialect.bom && dialect.encoding != TextEncoding::Utf8 {
+        bail!("A byte order mark can only be written for UTF-8 output");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_args_only_set_options() {
+        let dialect = CsvDialect {
+            encoding: TextEncoding::Gbk,
+            ..Default::default()
+        };
+        assert_eq!(reader_args(&dialect).unwrap(), ["header = true"]);
+
+        let dialect = CsvDialect {
+            delimiter: Some(';'),
+            header: false,
+            null_string: Some("NA".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(reader_args(&dialect).unwrap(), ["header = false", "delim = ';'", "nullstr = 'NA'"]);
+    }
+
+    #[test]
+    fn test_copy_options() {
+        let dialect = CsvDialect {
+            delimiter: Some(';'),
+            header: false,
+            force_quote: true,
+            null_string: Some("NULL".to_string()),
+            date_format: Some("%d/%m/%Y".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            copy_options(&dialect).unwrap(),
+            "FORMAT CSV, DELIMITER ';', HEADER false, QUOTE '\"', NULLSTR 'NULL', FORCE_QUOTE *, DATEFORMAT '%d/%m/%Y'"
+        );
+        assert!(!needs_rewrite(&dialect));
+
+        let invalid = CsvDialect {
+            bom: true,
+            encoding: TextEncoding::Gbk,
+            ..Default::default()
+        };
+        assert!(copy_options(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_rewrite_keeps_newlines_inside_quotes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("source.csv");
+        let target = dir.path().join("target.csv");
+        std::fs::write(&source, "id,note\n1,\"多行\n备注\"\n2,普通\n").unwrap();
+
+        let dialect = CsvDialect {
+            line_ending: LineEnding::CrLf,
+            encoding: TextEncoding::Gbk,
+            ..Default::default()
+        };
+        rewrite_output(&source, &target, &dialect).unwrap();
+
+        let bytes = std::fs::read(&target).unwrap();
+        let (text, _, _) = GBK.decode(&bytes);
+        assert_eq!(text, "id,note\r\n1,\"多行\n备注\"\r\n2,普通\r\n");
+
+        // 转回 UTF-8
+        let decoded = dir.path().join("decoded.csv");
+        decode_to_utf8(&target, &decoded, TextEncoding::Gbk).unwrap();
+        assert_eq!(std::fs::read_to_string(&decoded).unwrap(), text);
+    }
+}
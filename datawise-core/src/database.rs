@@ -0,0 +1,169 @@
+//! PostgreSQL / MySQL 数据源
+//!
+//! 通过 DuckDB 的 postgres、mysql 扩展按连接配置以只读方式附加远程数据库，
+//! 数据库别名即连接配置名。查询可以用 `postgres_query`/`mysql_query`
+//! 整体下推到远程服务器执行，只把结果传回本地。
+
+use crate::protocol::{ConnectionProfile, DatabaseKind};
+use crate::sql::{quote_ident, quote_literal};
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// 远程数据库中的表或视图
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct RemoteTable {
+    /// schema 名（MySQL 中即数据库名）
+    pub schema: String,
+    /// 表名
+    pub name: String,
+    /// `BASE TABLE` 或 `VIEW`
+    pub table_type: String,
+}
+
+impl RemoteTable {
+    /// 字段数（schema、表名、类型），即列出表时的 `Finished.column_count`
+    pub const FIELD_COUNT: usize = 3;
+}
+
+/// 列出 schema 时每项只有名称一个字段，即 `Finished.column_count`
+pub const SCHEMA_FIELD_COUNT: usize = 1;
+
+/// 附加连接配置对应的数据库（已附加时直接返回）
+pub fn attach(conn: &duckdb::Connection, profile: &ConnectionProfile) -> Result<()> {
+    let attached: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM duckdb_databases() WHERE database_name = ?",
+        [&profile.name],
+        |row| row.get(0),
+    )?;
+    if attached {
+        return Ok(());
+    }
+
+    let extension = match profile.kind {
+        DatabaseKind::Postgres => "postgres",
+        DatabaseKind::MySql => "mysql",
+    };
+    if conn.execute_batch(&format!("LOAD {}", extension)).is_err() {
+        conn.execute_batch(&format!("INSTALL {0}; LOAD {0}", extension))
+            .with_context(|| format!("Failed to load the DuckDB {} extension", extension))?;
+    }
+
+    conn.execute(&attach_sql(profile), [])
+        .with_context(|| format!("Failed to connect to {:?} database: {}", profile.kind, profile.name))?;
+    Ok(())
+}
+
+/// 以只读方式附加远程数据库的 `ATTACH` 语句
+fn attach_sql(profile: &ConnectionProfile) -> String {
+    let db_type = match profile.kind {
+        DatabaseKind::Postgres => "POSTGRES",
+        DatabaseKind::MySql => "MYSQL",
+    };
+    format!(
+        "ATTACH {} AS {} (TYPE {}, READ_ONLY)",
+        quote_literal(&profile.connection_string()),
+        quote_ident(&profile.name),
+        db_type
+    )
+}
+
+/// 列出 schema（跳过系统 schema）
+pub fn list_schemas(conn: &duckdb::Connection, profile: &ConnectionProfile) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT schema_name FROM information_schema.schemata \
+         WHERE catalog_name = ? \
+           AND schema_name NOT IN ('information_schema', 'pg_catalog', 'pg_toast', \
+                                   'mysql', 'performance_schema', 'sys') \
+         ORDER BY schema_name",
+    )?;
+    let schemas = stmt
+        .query_map([&profile.name], |row| row.get(0))?
+        .collect::<duckdb::Result<Vec<String>>>()
+        .context("Failed to list schemas")?;
+    Ok(schemas)
+}
+
+/// 列出表和视图，`schema` 为 `None` 时列出所有 schema 中的表
+pub fn list_tables(
+    conn: &duckdb::Connection,
+    profile: &ConnectionProfile,
+    schema: Option<&str>,
+) -> Result<Vec<RemoteTable>> {
+    let schemas = match schema {
+        Some(schema) => vec![schema.to_string()],
+        None => list_schemas(conn, profile)?,
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT table_schema, table_name, table_type FROM information_schema.tables \
+         WHERE table_catalog = ? AND table_schema = ? \
+         ORDER BY table_name",
+    )?;
+    let mut tables = Vec::new();
+    for schema in &schemas {
+        let rows = stmt
+            .query_map([&profile.name, schema], |row| {
+                Ok(RemoteTable {
+                    schema: row.get(0)?,
+                    name: row.get(1)?,
+                    table_type: row.get(2)?,
+                })
+            })?
+            .collect::<duckdb::Result<Vec<_>>>()
+            .with_context(|| format!("Failed to list tables in schema: {}", schema))?;
+        tables.extend(rows);
+    }
+    Ok(tables)
+}
+
+/// 把远程 SQL 包装为下推查询，可以作为 DuckDB 的表使用
+///
+/// 如 `postgres_query('warehouse', 'SELECT * FROM orders')`。
+pub fn pushdown_sql(profile: &ConnectionProfile, sql: &str) -> String {
+    let function = match profile.kind {
+        DatabaseKind::Postgres => "postgres_query",
+        DatabaseKind::MySql => "mysql_query",
+    };
+    // 去掉末尾的分号，以便嵌套到子查询中
+    let sql = sql.trim().trim_end_matches(';');
+    format!("{}({}, {})", function, quote_literal(&profile.name), quote_literal(sql))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(kind: DatabaseKind) -> ConnectionProfile {
+        ConnectionProfile {
+            name: "sales \"eu\"".to_string(),
+            kind,
+            host: "localhost".to_string(),
+            port: None,
+            user: "reader".to_string(),
+            password: Some("it's".to_string()),
+            database: "sales".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pushdown_sql() {
+        // 远程 SQL 整体作为字符串字面量传入，末尾的分号被去掉
+        assert_eq!(
+            pushdown_sql(&profile(DatabaseKind::Postgres), "  SELECT * FROM orders WHERE note = 'a;b';  "),
+            "postgres_query('sales \"eu\"', 'SELECT * FROM orders WHERE note = ''a;b''')"
+        );
+        assert_eq!(
+            pushdown_sql(&profile(DatabaseKind::MySql), "SELECT 1;;"),
+            "mysql_query('sales \"eu\"', 'SELECT 1')"
+        );
+    }
+
+    #[test]
+    fn test_attach_sql() {
+        assert_eq!(
+            attach_sql(&profile(DatabaseKind::Postgres)),
+            r#"ATTACH 'host=localhost port=5432 user=reader dbname=sales password=''it\''s''' AS "sales ""eu""" (TYPE POSTGRES, READ_ONLY)"#
+        );
+        assert!(attach_sql(&profile(DatabaseKind::MySql)).ends_with("(TYPE MYSQL, READ_ONLY)"));
+    }
+}
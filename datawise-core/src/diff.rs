@@ -0,0 +1,366 @@
+//! 表对比
+//!
+//! 按键列对比两张表（通常是上个月和这个月的数据），找出新增、删除和修改的行，
+//! 统计每列被修改的行数，并列出两张表的结构差异。
+//!
+//! 新增、删除和修改的行写入默认 schema 中的结果表（[`DiffTableNames`]），可以直接用 SQL 查询，
+//! 也会出现在目录中；摘要为 [`TableDiff`]。
+
+use crate::catalog::{ColumnInfo, TableInfo};
+use crate::profile::find_column;
+use crate::sql::{quote_ident, quote_literal, sanitize_table_name};
+use anyhow::{bail, Result};
+use duckdb::Connection;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// 对比结果摘要
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TableDiff {
+    /// 旧表
+    pub left: String,
+    /// 新表
+    pub right: String,
+    /// 键列
+    pub key_columns: Vec<String>,
+    /// 只在新表中的行数
+    pub added_rows: u64,
+    /// 只在旧表中的行数
+    pub removed_rows: u64,
+    /// 键相同但其他列不同的行数
+    pub changed_rows: u64,
+    /// 键相同且其他列都相同的行数
+    pub unchanged_rows: u64,
+    /// 两张表共有的非键列被修改的行数（按旧表的列顺序）
+    pub column_changes: Vec<ColumnChangeCount>,
+    /// 结构差异
+    pub schema: SchemaDiff,
+    /// 存放差异行的结果表
+    pub tables: DiffTableNames,
+}
+
+/// 列被修改的行数
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ColumnChangeCount {
+    pub column: String,
+    pub changed_rows: u64,
+}
+
+/// 两张表的结构差异，列名不区分大小写
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// 只在新表中的列
+    pub added_columns: Vec<ColumnInfo>,
+    /// 只在旧表中的列
+    pub removed_columns: Vec<ColumnInfo>,
+    /// 类型不同的列
+    pub type_changes: Vec<ColumnTypeChange>,
+}
+
+/// 列的类型变化
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ColumnTypeChange {
+    pub column: String,
+    pub left_type: String,
+    pub right_type: String,
+}
+
+/// 存放差异行的结果表名（在默认 schema 中），同一对表再次对比时被替换
+///
+/// - `added`、`removed`：新增/删除的行，列与新表/旧表相同
+/// - `changed`：键列、`changed_columns`（被修改的列名列表），以及共有列的 `<列名>_left`、`<列名>_right`；
+///   这些列名（不区分大小写）重复时对比报错
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiffTableNames {
+    pub added: String,
+    pub removed: String,
+    pub changed: String,
+}
+
+/// 表对比器
+pub struct TableDiffer {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl TableDiffer {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    /// 按键列对比旧表 `left` 和新表 `right`，键列在两张表中都须唯一
+    ///
+    /// 行数和各列被修改的行数在一个聚合查询中统计。
+    pub fn diff(&self, left: &TableInfo, right: &TableInfo, key_columns: &[String]) -> Result<TableDiff> {
+        if key_columns.is_empty() {
+            bail!("Table diff needs at least one key column");
+        }
+        let keys = key_columns
+            .iter()
+            .map(|name| {
+                find_column(left, name)?;
+                find_column(right, name)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let keys: Vec<&str> = keys.iter().map(|c| c.name.as_str()).collect();
+
+        let schema = schema_diff(left, right);
+        // 两张表共有的非键列
+        let compared: Vec<(&ColumnInfo, &ColumnInfo)> = left
+            .columns
+            .iter()
+            .filter(|c| !keys.iter().any(|k| k.eq_ignore_ascii_case(&c.name)))
+            .filter_map(|l| find_column(right, &l.name).ok().map(|r| (l, r)))
+            .collect();
+
+        let changed_names = changed_column_names(&keys, &compared)?;
+        let (l, r) = (left.quoted_name(), right.quoted_name());
+        let tables = diff_table_names(left, right);
+
+        let conn = self.conn.lock().unwrap();
+        for table in [left, right] {
+            ensure_unique_keys(&conn, table, &keys)?;
+        }
+
+        let matches = |a: &str, b: &str| {
+            keys.iter()
+                .map(|k| format!("{a}.{k} IS NOT DISTINCT FROM {b}.{k}", k = quote_ident(k)))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        };
+
+        // 新增和删除的行
+        let create_missing = |table: &str, from: &str, alias: &str, other: &str, other_alias: &str| {
+            conn.execute(
+                &format!(
+                    "CREATE OR REPLACE TABLE {} AS SELECT * FROM {from} AS {alias} \
+                     WHERE NOT EXISTS (SELECT 1 FROM {other} AS {other_alias} WHERE {})",
+                    quote_ident(table),
+                    matches("l", "r"),
+                ),
+                [],
+            )?;
+            // CREATE TABLE AS 不返回行数，从结果表统计
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", quote_ident(table)), [], |row| row.get::<_, i64>(0))
+                .map(|rows| rows as u64)
+        };
+        let added_rows = create_missing(&tables.added, &r, "r", &l, "l")?;
+        let removed_rows = create_missing(&tables.removed, &l, "l", &r, "r")?;
+
+        // 键相同的行中被修改的列
+        let distinct: Vec<String> = compared
+            .iter()
+            .map(|(lc, rc)| {
+                let (a, b) = (format!("l.{}", quote_ident(&lc.name)), format!("r.{}", quote_ident(&rc.name)));
+                if lc.data_type == rc.data_type {
+                    format!("{a} IS DISTINCT FROM {b}")
+                } else {
+                    // 类型不同时按文本比较
+                    format!("CAST({a} AS VARCHAR) IS DISTINCT FROM CAST({b} AS VARCHAR)")
+                }
+            })
+            .collect();
+        let changed_columns = if compared.is_empty() {
+            "CAST([] AS VARCHAR[])".to_string()
+        } else {
+            let names = compared
+                .iter()
+                .zip(&distinct)
+                .map(|((lc, _), d)| format!("CASE WHEN {} THEN {} END", d, quote_literal(&lc.name)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("list_filter([{names}], x -> x IS NOT NULL)")
+        };
+        let mut projection: Vec<String> = keys.iter().map(|k| format!("l.{}", quote_ident(k))).collect();
+        projection.push(format!("{changed_columns} AS changed_columns"));
+        for ((lc, rc), (left_name, right_name)) in compared.iter().zip(&changed_names) {
+            projection.push(format!("l.{} AS {}", quote_ident(&lc.name), quote_ident(left_name)));
+            projection.push(format!("r.{} AS {}", quote_ident(&rc.name), quote_ident(right_name)));
+        }
+        conn.execute(
+            &format!(
+                "CREATE OR REPLACE TABLE {} AS SELECT * FROM \
+                 (SELECT {} FROM {l} AS l JOIN {r} AS r ON {}) WHERE len(changed_columns) > 0",
+                quote_ident(&tables.changed),
+                projection.join(", "),
+                matches("l", "r"),
+            ),
+            [],
+        )?;
+
+        // 一次聚合统计键相同的行数、被修改的行数和各列被修改的行数
+        let any_changed = if distinct.is_empty() { "false".to_string() } else { distinct.join(" OR ") };
+        let aggregates = [
+            "COUNT(*)".to_string(),
+            format!("COUNT(*) FILTER (WHERE {any_changed})"),
+        ]
+        .into_iter()
+        .chain(distinct.iter().map(|d| format!("COALESCE(SUM(CASE WHEN {d} THEN 1 END), 0)")))
+        .collect::<Vec<_>>()
+        .join(", ");
+        let counts = conn.query_row(
+            &format!("SELECT {aggregates} FROM {l} AS l JOIN {r} AS r ON {}", matches("l", "r")),
+            [],
+            |row| {
+                (0..2 + distinct.len())
+                    .map(|i| row.get::<_, i64>(i).map(|n| n as u64))
+                    .collect::<duckdb::Result<Vec<_>>>()
+            },
+        )?;
+        let (matched_rows, changed_rows) = (counts[0], counts[1]);
+        let column_changes = compared
+            .iter()
+            .zip(&counts[2..])
+            .map(|((column, _), &changed_rows)| ColumnChangeCount {
+                column: column.name.clone(),
+                changed_rows,
+            })
+            .collect();
+
+        Ok(TableDiff {
+            left: left.name.clone(),
+            right: right.name.clone(),
+            key_columns: keys.iter().map(|k| k.to_string()).collect(),
+            added_rows,
+            removed_rows,
+            changed_rows,
+            unchanged_rows: matched_rows - changed_rows,
+            column_changes,
+            schema,
+            tables,
+        })
+    }
+}
+
+/// 键列不唯一时无法一一对应，报错
+fn ensure_unique_keys(conn: &Connection, table: &TableInfo, keys: &[&str]) -> Result<()> {
+    let keys = keys.iter().map(|k| quote_ident(k)).collect::<Vec<_>>().join(", ");
+    let duplicates: i64 = conn.query_row(
+        &format!(
+            "SELECT COUNT(*) FROM (SELECT 1 FROM {} GROUP BY {} HAVING COUNT(*) > 1)",
+            table.quoted_name(),
+            keys
+        ),
+        [],
+        |row| row.get(0),
+    )?;
+    if duplicates > 0 {
+        bail!("Key columns are not unique in {}: {} duplicate key(s)", table.name, duplicates);
+    }
+    Ok(())
+}
+
+/// 修改表中共有列的 `<列名>_left`、`<列名>_right` 列名
+///
+/// 与键列、`changed_columns` 或其他结果列名（不区分大小写）重复时报错，
+/// 例如键列为 `amount_left`、同时有非键列 `amount` 时。
+fn changed_column_names(keys: &[&str], compared: &[(&ColumnInfo, &ColumnInfo)]) -> Result<Vec<(String, String)>> {
+    let names: Vec<(String, String)> = compared
+        .iter()
+        .map(|(column, _)| (format!("{}_left", column.name), format!("{}_right", column.name)))
+        .collect();
+    let mut seen = vec!["changed_columns".to_string()];
+    let columns = names.iter().flat_map(|(l, r)| [l.as_str(), r.as_str()]);
+    for name in keys.iter().copied().chain(columns) {
+        let lower = name.to_lowercase();
+        if seen.contains(&lower) {
+            bail!("Diff result column name is ambiguous: {}; rename the column before comparing", name);
+        }
+        seen.push(lower);
+    }
+    Ok(names)
+}
+
+/// 按列名（不区分大小写）对比两张表的列
+fn schema_diff(left: &TableInfo, right: &TableInfo) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+    for column in &left.columns {
+        match find_column(right, &column.name) {
+            Ok(other) if other.data_type != column.data_type => diff.type_changes.push(ColumnTypeChange {
+                column: column.name.clone(),
+                left_type: column.data_type.clone(),
+                right_type: other.data_type.clone(),
+            }),
+            Ok(_) => {}
+            Err(_) => diff.removed_columns.push(column.clone()),
+        }
+    }
+    diff.added_columns = right
+        .columns
+        .iter()
+        .filter(|c| find_column(left, &c.name).is_err())
+        .cloned()
+        .collect();
+    diff
+}
+
+/// 结果表名：`diff_<旧表>_<新表>_added` 等
+fn diff_table_names(left: &TableInfo, right: &TableInfo) -> DiffTableNames {
+    let prefix = sanitize_table_name(&format!("diff_{}_{}", left.name, right.name))
+        .unwrap_or_else(|| "diff".to_string());
+    DiffTableNames {
+        added: format!("{prefix}_added"),
+        removed: format!("{prefix}_removed"),
+        changed: format!("{prefix}_changed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str, columns: &[(&str, &str)]) -> TableInfo {
+        TableInfo {
+            database: "memory".to_string(),
+            schema: "main".to_string(),
+            name: name.to_string(),
+            table_type: "BASE TABLE".to_string(),
+            row_count: None,
+            columns: columns
+                .iter()
+                .map(|(name, data_type)| ColumnInfo {
+                    name: name.to_string(),
+                    data_type: data_type.to_string(),
+                    nullable: true,
+                })
+                .collect(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let left = table("jan", &[("id", "INTEGER"), ("amount", "INTEGER"), ("note", "VARCHAR")]);
+        let right = table("feb", &[("ID", "INTEGER"), ("amount", "DOUBLE"), ("region", "VARCHAR")]);
+        let diff = schema_diff(&left, &right);
+
+        assert_eq!(diff.removed_columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["note"]);
+        assert_eq!(diff.added_columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["region"]);
+        assert_eq!(
+            diff.type_changes,
+            [ColumnTypeChange {
+                column: "amount".to_string(),
+                left_type: "INTEGER".to_string(),
+                right_type: "DOUBLE".to_string(),
+            }]
+        );
+        assert_eq!(diff_table_names(&left, &right).changed, "diff_jan_feb_changed");
+    }
+
+    #[test]
+    fn test_changed_column_names() {
+        let jan = table("jan", &[("id", "INTEGER"), ("amount", "INTEGER"), ("note", "VARCHAR")]);
+        let compared: Vec<_> = jan.columns[1..].iter().map(|c| (c, c)).collect();
+        assert_eq!(
+            changed_column_names(&["id"], &compared).unwrap(),
+            [
+                ("amount_left".to_string(), "amount_right".to_string()),
+                ("note_left".to_string(), "note_right".to_string()),
+            ]
+        );
+
+        // 键列与结果列重名
+        let err = changed_column_names(&["AMOUNT_LEFT"], &compared).unwrap_err();
+        assert!(err.to_string().contains("amount_left"));
+        assert!(changed_column_names(&["changed_columns"], &compared).is_err());
+    }
+}
@@ -0,0 +1,693 @@
+//! Excel 工作簿读写
+//!
+//! 基于 calamine 读取 xlsx/xlsm/xlsb/xls 工作簿：
+//! - 公式单元格取其缓存值
+//! - 带日期格式的数字识别为 DATE / TIMESTAMP
+//! - 表头中的合并单元格（仅 xlsx）会展开到其覆盖的每一列
+//!
+//! 每列的类型根据该列所有数据单元格推断，类型不一致的列退化为 VARCHAR。
+//!
+//! 基于 rust_xlsxwriter 把 Arrow 查询结果写入 xlsx 工作表：数字、布尔值和
+//! 日期/时间写为对应类型的单元格，表头加粗并冻结，列宽自动调整。
+
+use crate::protocol::ImportOptions;
+use crate::sql::{quote_ident, quote_table_name};
+use anyhow::{bail, Context, Result};
+use calamine::{open_workbook_auto, Data, Dimensions, Range, Reader, Sheets};
+use arrow::array::{Array, ArrayRef, AsArray, Float64Array};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type, Int64Type, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use duckdb::types::Value;
+use rust_xlsxwriter::{Format, Workbook, Worksheet};
+use std::path::Path;
+
+/// 列出工作簿中的工作表（按工作簿中的顺序）
+pub fn list_sheets(path: &Path) -> Result<Vec<String>> {
+    let workbook = open_workbook_auto(path)
+        .with_context(|| format!("Failed to open workbook: {:?}", path))?;
+    Ok(workbook.sheet_names())
+}
+
+/// 按导入选项读取工作表，返回 `(工作表名, 表格数据)`
+///
+/// - `options.all_sheets`：读取所有工作表
+/// - `options.sheet`：读取指定工作表（默认第一个）
+/// - `options.cell_range`：只读取指定区域，区域的第一行作为表头
+pub fn read_sheets(path: &Path, options: &ImportOptions) -> Result<Vec<(String, SheetTable)>> {
+    let mut workbook = open_workbook_auto(path)
+        .with_context(|| format!("Failed to open workbook: {:?}", path))?;
+    let names = workbook.sheet_names();
+    if names.is_empty() {
+        bail!("Workbook has no sheets: {:?}", path);
+    }
+
+    let selected = if options.all_sheets {
+        names.clone()
+    } else if let Some(sheet) = &options.sheet {
+        if !names.contains(sheet) {
+            bail!("Sheet not found: {} (available: {})", sheet, names.join(", "));
+        }
+        vec![sheet.clone()]
+    } else {
+        vec![names[0].clone()]
+    };
+
+    let cell_range = options.cell_range.as_deref().map(parse_cell_range).transpose()?;
+
+    if let Sheets::Xlsx(xlsx) = &mut workbook {
+        xlsx.load_merged_regions().context("Failed to read merged cells")?;
+    }
+
+    let mut tables = Vec::new();
+    for name in selected {
+        let range = workbook
+            .worksheet_range(&name)
+            .with_context(|| format!("Failed to read sheet: {}", name))?;
+        let merged = match &workbook {
+            Sheets::Xlsx(xlsx) => xlsx
+                .merged_regions_by_sheet(&name)
+                .into_iter()
+                .map(|(_, _, dims)| *dims)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let table = SheetTable::from_range(&range, cell_range, &merged)
+            .with_context(|| format!("Failed to read sheet: {}", name))?;
+        tables.push((name, table));
+    }
+    Ok(tables)
+}
+
+/// 从工作表中读取的表格数据
+#[derive(Debug)]
+pub struct SheetTable {
+    /// 列名（已去重）
+    pub columns: Vec<String>,
+    /// 推断出的列类型
+    pub types: Vec<ColumnType>,
+    /// 数据行（不含表头）
+    rows: Vec<Vec<Data>>,
+}
+
+impl SheetTable {
+    /// 从单元格区域构造表格
+    ///
+    /// `cell_range` 为绝对位置 `((行, 列), (行, 列))`，为空时使用整个工作表。
+    fn from_range(
+        range: &Range<Data>,
+        cell_range: Option<((u32, u32), (u32, u32))>,
+        merged: &[Dimensions],
+    ) -> Result<Self> {
+        let selected = match cell_range {
+            Some((start, end)) => range.range(start, end),
+            None => range.clone(),
+        };
+        let (Some(start), (height, width)) = (selected.start(), selected.get_size()) else {
+            bail!("Sheet is empty");
+        };
+        if height == 0 || width == 0 {
+            bail!("Sheet is empty");
+        }
+
+        // 表头：空单元格若位于合并区域内，取合并区域左上角的值
+        let mut columns = Vec::with_capacity(width);
+        for col in 0..width {
+            let mut name = cell_text(selected.get((0, col)).unwrap_or(&Data::Empty)).trim().to_string();
+            if name.is_empty() {
+                let pos = (start.0, start.1 + col as u32);
+                if let Some(region) = merged.iter().find(|d| d.contains(pos.0, pos.1)) {
+                    name = cell_text(range.get_value(region.start).unwrap_or(&Data::Empty)).trim().to_string();
+                }
+            }
+            columns.push(name);
+        }
+        let columns = unique_column_names(columns);
+
+        // 数据行，跳过整行为空的行
+        let rows: Vec<Vec<Data>> = selected
+            .rows()
+            .skip(1)
+            .filter(|row| row.iter().any(|c| !matches!(c, Data::Empty)))
+            .map(|row| row.to_vec())
+            .collect();
+
+        let mut types = vec![ColumnType::Empty; width];
+        for row in &rows {
+            for (ty, cell) in types.iter_mut().zip(row) {
+                *ty = ty.merge(ColumnType::of(cell));
+            }
+        }
+
+        Ok(Self { columns, types, rows })
+    }
+
+    /// 数据行数
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// 建表语句，列名加双引号以支持空格等字符
+    pub fn create_sql(&self, table_name: &str) -> String {
+        let columns = self
+            .columns
+            .iter()
+            .zip(&self.types)
+            .map(|(name, ty)| format!("{} {}", quote_ident(name), ty.sql_type()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("CREATE TABLE {} ({})", quote_table_name(table_name), columns)
+    }
+
+    /// 第 `row` 行的值，日期/时间以字符串追加，由 Appender 转换为列类型
+    pub fn row_values(&self, row: usize) -> Vec<Value> {
+        self.types
+            .iter()
+            .enumerate()
+            .map(|(col, ty)| ty.value(self.rows[row].get(col).unwrap_or(&Data::Empty)))
+            .collect()
+    }
+}
+
+/// 推断出的列类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// 所有单元格都为空
+    Empty,
+    /// 布尔值
+    Boolean,
+    /// 整数
+    BigInt,
+    /// 浮点数
+    Double,
+    /// 日期（时间部分为 0）
+    Date,
+    /// 日期时间
+    Timestamp,
+    /// 文本，或类型不一致的列
+    Varchar,
+}
+
+impl ColumnType {
+    /// 单个单元格的类型
+    fn of(cell: &Data) -> Self {
+        match cell {
+            Data::Empty | Data::Error(_) => ColumnType::Empty,
+            Data::Bool(_) => ColumnType::Boolean,
+            Data::Int(_) => ColumnType::BigInt,
+            // Excel 中的数字都以浮点数存储，整数值按 BIGINT 处理
+            Data::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => ColumnType::BigInt,
+            Data::Float(_) => ColumnType::Double,
+            Data::DateTime(dt) if dt.is_datetime() => match dt.as_datetime() {
+                Some(ts) if ts.time() == chrono::NaiveTime::MIN => ColumnType::Date,
+                Some(_) => ColumnType::Timestamp,
+                None => ColumnType::Varchar,
+            },
+            Data::DateTimeIso(s) if s.contains('T') => ColumnType::Timestamp,
+            Data::DateTimeIso(s) if s.len() == 10 => ColumnType::Date,
+            _ => ColumnType::Varchar,
+        }
+    }
+
+    /// 合并两个类型，不兼容时退化为 VARCHAR
+    fn merge(self, other: Self) -> Self {
+        use ColumnType::*;
+        match (self, other) {
+            (Empty, t) | (t, Empty) => t,
+            (a, b) if a == b => a,
+            (BigInt, Double) | (Double, BigInt) => Double,
+            (Date, Timestamp) | (Timestamp, Date) => Timestamp,
+            _ => Varchar,
+        }
+    }
+
+    /// 对应的 DuckDB 类型
+    pub fn sql_type(self) -> &'static str {
+        match self {
+            ColumnType::Empty | ColumnType::Varchar => "VARCHAR",
+            ColumnType::Boolean => "BOOLEAN",
+            ColumnType::BigInt => "BIGINT",
+            ColumnType::Double => "DOUBLE",
+            ColumnType::Date => "DATE",
+            ColumnType::Timestamp => "TIMESTAMP",
+        }
+    }
+
+    /// 把单元格转换为该类型的参数值
+    fn value(self, cell: &Data) -> Value {
+        if ColumnType::of(cell) == ColumnType::Empty {
+            return Value::Null;
+        }
+        match (self, cell) {
+            (ColumnType::Boolean, Data::Bool(b)) => Value::Boolean(*b),
+            (ColumnType::BigInt, Data::Int(i)) => Value::BigInt(*i),
+            (ColumnType::BigInt, Data::Float(f)) => Value::BigInt(*f as i64),
+            (ColumnType::Double, Data::Int(i)) => Value::Double(*i as f64),
+            (ColumnType::Double, Data::Float(f)) => Value::Double(*f),
+            _ => Value::Text(cell_text(cell)),
+        }
+    }
+}
+
+/// 单元格的文本形式，日期按 ISO 8601 格式输出
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty | Data::Error(_) => String::new(),
+        Data::String(s) => s.clone(),
+        Data::DateTime(dt) if dt.is_duration() => match dt.as_duration() {
+            Some(d) => {
+                let secs = d.num_seconds();
+                format!("{:02}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+            }
+            None => dt.to_string(),
+        },
+        Data::DateTime(dt) => match dt.as_datetime() {
+            Some(ts) if ts.time() == chrono::NaiveTime::MIN => ts.format("%Y-%m-%d").to_string(),
+            Some(ts) => ts.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+            None => dt.to_string(),
+        },
+        Data::DateTimeIso(s) => s.replace('T', " "),
+        other => other.to_string(),
+    }
+}
+
+/// 补全空列名并为重复列名添加后缀：`["a", "", "a"]` → `["a", "column2", "a_2"]`
+fn unique_column_names(names: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::with_capacity(names.len());
+    for (idx, name) in names.into_iter().enumerate() {
+        let base = if name.is_empty() { format!("column{}", idx + 1) } else { name };
+        let mut candidate = base.clone();
+        let mut suffix = 2;
+        while result.iter().any(|n| n.eq_ignore_ascii_case(&candidate)) {
+            candidate = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        result.push(candidate);
+    }
+    result
+}
+
+/// 解析 A1 风格的单元格区域，如 `B2:F100`，返回从 0 开始的 `((行, 列), (行, 列))`
+pub fn parse_cell_range(range: &str) -> Result<((u32, u32), (u32, u32))> {
+    let (start, end) = range
+        .split_once(':')
+        .with_context(|| format!("Invalid cell range: {}", range))?;
+    let start = parse_cell_ref(start).with_context(|| format!("Invalid cell range: {}", range))?;
+    let end = parse_cell_ref(end).with_context(|| format!("Invalid cell range: {}", range))?;
+    if start.0 > end.0 || start.1 > end.1 {
+        bail!("Invalid cell range: {}", range);
+    }
+    Ok((start, end))
+}
+
+/// 解析单元格引用，如 `AB12` → `(11, 27)`，允许 `$` 绝对引用标记
+fn parse_cell_ref(cell: &str) -> Option<(u32, u32)> {
+    let cell = cell.trim().replace('$', "").to_ascii_uppercase();
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+
+    let mut col: u32 = 0;
+    for c in letters.chars() {
+        col = col.checked_mul(26)?.checked_add(c as u32 - 'A' as u32 + 1)?;
+    }
+    let row: u32 = digits.parse().ok()?;
+    if row == 0 {
+        return None;
+    }
+    Some((row - 1, col - 1))
+}
+
+/// 把工作表名转换为可用作表名后缀的标识符：`Q1 Sales` → `q1_sales`
+pub fn sanitize_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            result.extend(c.to_lowercase());
+        } else if !result.ends_with('_') {
+            result.push('_');
+        }
+    }
+    let result = result.trim_matches('_');
+    if result.is_empty() {
+        "sheet".to_string()
+    } else {
+        result.to_string()
+    }
+}
+
+/// Excel 工作表的最大行数（含表头）
+pub const MAX_SHEET_ROWS: usize = 1_048_576;
+
+/// Excel 工作表的最大列数
+const MAX_SHEET_COLUMNS: usize = 16_384;
+
+/// Excel 工作表名的最大长度
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// 1970-01-01 对应的 Excel 序列日期
+const UNIX_EPOCH_SERIAL: f64 = 25_569.0;
+
+/// xlsx 工作簿写入器
+pub struct WorkbookWriter {
+    workbook: Workbook,
+    /// 已使用的工作表名，用于去重
+    used_names: Vec<String>,
+    formats: CellFormats,
+    /// 每个工作表最多写入的数据行数（不含表头）
+    rows_per_sheet: usize,
+    /// 超出行数限制时是否拆分到多个工作表
+    split: bool,
+}
+
+impl WorkbookWriter {
+    /// 创建写入器
+    ///
+    /// `split` 为 true 时，超过 Excel 行数限制的结果依次写入
+    /// `<name> (2)`、`<name> (3)` 等工作表；否则返回错误。
+    pub fn new(split: bool) -> Self {
+        Self {
+            workbook: Workbook::new(),
+            used_names: Vec::new(),
+            formats: CellFormats::new(),
+            rows_per_sheet: MAX_SHEET_ROWS - 1,
+            split,
+        }
+    }
+
+    /// 把查询结果写入新的工作表，返回写入的数据行数
+    ///
+    /// 每写完一个 RecordBatch 调用一次 `on_rows`，参数为该批次的行数。
+    pub fn write_sheet(
+        &mut self,
+        name: &str,
+        schema: &Schema,
+        batches: &[RecordBatch],
+        on_rows: &mut dyn FnMut(u64),
+    ) -> Result<usize> {
+        let total_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        if total_rows > self.rows_per_sheet && !self.split {
+            bail!(
+                "Sheet '{}' has {} rows, exceeding Excel's limit of {} rows per sheet; \
+                 enable sheet splitting or export to CSV/Parquet instead",
+                name,
+                total_rows,
+                self.rows_per_sheet
+            );
+        }
+        if schema.fields().len() > MAX_SHEET_COLUMNS {
+            bail!(
+                "Sheet '{}' has {} columns, exceeding Excel's limit of {} columns",
+                name,
+                schema.fields().len(),
+                MAX_SHEET_COLUMNS
+            );
+        }
+
+        let mut worksheet = self.new_worksheet(name, schema)?;
+        let mut sheet_row = 0;
+
+        for batch in batches {
+            let columns = batch
+                .columns()
+                .iter()
+                .map(prepare_column)
+                .collect::<Result<Vec<_>>>()?;
+
+            for row in 0..batch.num_rows() {
+                if sheet_row == self.rows_per_sheet {
+                    self.finish_worksheet(worksheet);
+                    worksheet = self.new_worksheet(name, schema)?;
+                    sheet_row = 0;
+                }
+                sheet_row += 1;
+
+                for (col, (kind, array)) in columns.iter().enumerate() {
+                    if array.is_null(row) {
+                        continue;
+                    }
+                    write_cell(&mut worksheet, sheet_row as u32, col as u16, *kind, array, row, &self.formats)?;
+                }
+            }
+            on_rows(batch.num_rows() as u64);
+        }
+
+        self.finish_worksheet(worksheet);
+        Ok(total_rows)
+    }
+
+    /// 保存工作簿
+    pub fn save(mut self, path: &Path) -> Result<()> {
+        self.workbook
+            .save(path)
+            .with_context(|| format!("Failed to save workbook: {:?}", path))
+    }
+
+    /// 创建带加粗表头、冻结首行的工作表
+    fn new_worksheet(&mut self, name: &str, schema: &Schema) -> Result<Worksheet> {
+        let mut worksheet = Worksheet::new();
+        worksheet.set_name(unique_sheet_name(name, &mut self.used_names))?;
+        for (col, field) in schema.fields().iter().enumerate() {
+            worksheet.write_string_with_format(0, col as u16, field.name(), &self.formats.header)?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        Ok(worksheet)
+    }
+
+    fn finish_worksheet(&mut self, mut worksheet: Worksheet) {
+        worksheet.autofit();
+        self.workbook.push_worksheet(worksheet);
+    }
+}
+
+/// 表头和日期单元格的格式
+struct CellFormats {
+    header: Format,
+    date: Format,
+    datetime: Format,
+}
+
+impl CellFormats {
+    fn new() -> Self {
+        Self {
+            header: Format::new().set_bold(),
+            date: Format::new().set_num_format("yyyy-mm-dd"),
+            datetime: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+        }
+    }
+}
+
+/// 单元格类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+    /// 数字，数组已转换为 Float64
+    Number,
+    /// 布尔值
+    Boolean,
+    /// 日期，数组已转换为 Excel 序列日期
+    Date,
+    /// 日期时间，数组已转换为 Excel 序列日期
+    DateTime,
+    /// 文本，数组已转换为 Utf8
+    Text,
+}
+
+/// 把 Arrow 列转换为便于写入的形式
+fn prepare_column(array: &ArrayRef) -> Result<(CellKind, ArrayRef)> {
+    let prepared = match array.data_type() {
+        DataType::Boolean => (CellKind::Boolean, array.clone()),
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64
+        | DataType::Float16
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => (CellKind::Number, cast(array, &DataType::Float64)?),
+        DataType::Date32 => (CellKind::Date, to_serial(array, 1.0)?),
+        DataType::Date64 => (CellKind::Date, to_serial(array, 86_400_000.0)?),
+        DataType::Timestamp(unit, _) => {
+            let units_per_day = match unit {
+                TimeUnit::Second => 86_400.0,
+                TimeUnit::Millisecond => 86_400_000.0,
+                TimeUnit::Microsecond => 86_400_000_000.0,
+                TimeUnit::Nanosecond => 86_400_000_000_000.0,
+            };
+            (CellKind::DateTime, to_serial(array, units_per_day)?)
+        }
+        DataType::Utf8 => (CellKind::Text, array.clone()),
+        DataType::LargeUtf8 | DataType::Utf8View => (CellKind::Text, cast(array, &DataType::Utf8)?),
+        // 其他类型（列表、结构体、区间等）按显示格式写为文本
+        _ => {
+            let formatter = ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default())?;
+            let values: arrow::array::StringArray = (0..array.len())
+                .map(|i| (!array.is_null(i)).then(|| formatter.value(i).to_string()))
+                .collect();
+            (CellKind::Text, std::sync::Arc::new(values) as ArrayRef)
+        }
+    };
+    Ok(prepared)
+}
+
+/// 把从 1970-01-01 起的时间值（单位为 `1 / units_per_day` 天）转换为 Excel 序列日期
+fn to_serial(array: &ArrayRef, units_per_day: f64) -> Result<ArrayRef> {
+    let values = cast(array, &DataType::Int64)?;
+    let serials: Float64Array = values
+        .as_primitive::<Int64Type>()
+        .iter()
+        .map(|v| v.map(|v| v as f64 / units_per_day + UNIX_EPOCH_SERIAL))
+        .collect();
+    Ok(std::sync::Arc::new(serials))
+}
+
+fn write_cell(
+    worksheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    kind: CellKind,
+    array: &ArrayRef,
+    index: usize,
+    formats: &CellFormats,
+) -> Result<()> {
+    match kind {
+        CellKind::Number => {
+            let value = array.as_primitive::<Float64Type>().value(index);
+            // Excel 不支持 NaN 和无穷大
+            if value.is_finite() {
+                worksheet.write_number(row, col, value)?;
+            } else {
+                worksheet.write_string(row, col, value.to_string())?;
+            }
+        }
+        CellKind::Boolean => {
+            worksheet.write_boolean(row, col, array.as_boolean().value(index))?;
+        }
+        CellKind::Date => {
+            let value = array.as_primitive::<Float64Type>().value(index);
+            worksheet.write_number_with_format(row, col, value, &formats.date)?;
+        }
+        CellKind::DateTime => {
+            let value = array.as_primitive::<Float64Type>().value(index);
+            worksheet.write_number_with_format(row, col, value, &formats.datetime)?;
+        }
+        CellKind::Text => {
+            worksheet.write_string(row, col, array.as_string::<i32>().value(index))?;
+        }
+    }
+    Ok(())
+}
+
+/// 生成合法且不重复的工作表名
+///
+/// 去掉 Excel 不允许的字符 `[]:*?/\`，截断到 31 个字符；
+/// 与已有名称重复（不区分大小写）时添加 ` (2)`、` (3)` 等后缀。
+pub fn unique_sheet_name(name: &str, used_names: &mut Vec<String>) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('\'');
+    let base = if cleaned.is_empty() {
+        format!("Sheet{}", used_names.len() + 1)
+    } else {
+        cleaned.to_string()
+    };
+
+    let mut candidate: String = base.chars().take(MAX_SHEET_NAME_LEN).collect();
+    let mut suffix = 2;
+    while used_names.iter().any(|n| n.to_lowercase() == candidate.to_lowercase()) {
+        let tail = format!(" ({})", suffix);
+        let head: String = base.chars().take(MAX_SHEET_NAME_LEN - tail.chars().count()).collect();
+        candidate = format!("{}{}", head, tail);
+        suffix += 1;
+    }
+    used_names.push(candidate.clone());
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cell_range() {
+        assert_eq!(parse_cell_range("A1:C10").unwrap(), ((0, 0), (9, 2)));
+        assert_eq!(parse_cell_range("b2:$AA$3").unwrap(), ((1, 1), (2, 26)));
+        assert!(parse_cell_range("A1").is_err());
+        assert!(parse_cell_range("C3:A1").is_err());
+        assert!(parse_cell_range("A0:B2").is_err());
+        assert!(parse_cell_range("1A:B2").is_err());
+    }
+
+    #[test]
+    fn test_unique_column_names() {
+        let names = vec!["id".to_string(), String::new(), "ID".to_string(), "id".to_string()];
+        assert_eq!(unique_column_names(names), ["id", "column2", "ID_2", "id_3"]);
+    }
+
+    #[test]
+    fn test_column_type_merge() {
+        use ColumnType::*;
+        assert_eq!(Empty.merge(BigInt), BigInt);
+        assert_eq!(BigInt.merge(Double), Double);
+        assert_eq!(Date.merge(Timestamp), Timestamp);
+        assert_eq!(Boolean.merge(BigInt), Varchar);
+        assert_eq!(ColumnType::of(&Data::Float(3.0)), BigInt);
+        assert_eq!(ColumnType::of(&Data::Float(3.5)), Double);
+    }
+
+    #[test]
+    fn test_unique_sheet_name() {
+        let mut used = Vec::new();
+        assert_eq!(unique_sheet_name("Orders", &mut used), "Orders");
+        assert_eq!(unique_sheet_name("orders", &mut used), "orders (2)");
+        assert_eq!(unique_sheet_name("a/b:c", &mut used), "a_b_c");
+        assert_eq!(unique_sheet_name("", &mut used), "Sheet4");
+        let long = "x".repeat(40);
+        assert_eq!(unique_sheet_name(&long, &mut used), "x".repeat(31));
+        assert_eq!(unique_sheet_name(&long, &mut used), format!("{} (2)", "x".repeat(27)));
+    }
+
+    #[test]
+    fn test_write_sheet_splits_or_fails_on_row_limit() {
+        use arrow::array::Int64Array;
+        use arrow::datatypes::Field;
+        use std::sync::Arc;
+
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5]))],
+        )
+        .unwrap();
+
+        let mut writer = WorkbookWriter::new(false);
+        writer.rows_per_sheet = 2;
+        let result = writer.write_sheet("data", &schema, std::slice::from_ref(&batch), &mut |_| {});
+        assert!(result.is_err(), "Exceeding the row limit should fail without splitting");
+
+        let mut writer = WorkbookWriter::new(true);
+        writer.rows_per_sheet = 2;
+        let mut reported = 0;
+        let rows = writer.write_sheet("data", &schema, &[batch], &mut |n| reported += n).unwrap();
+        assert_eq!(rows, 5);
+        assert_eq!(reported, 5);
+        assert_eq!(writer.used_names, ["data", "data (2)", "data (3)"]);
+    }
+
+    #[test]
+    fn test_sanitize_name() {
+        assert_eq!(sanitize_name("Q1 Sales"), "q1_sales");
+        assert_eq!(sanitize_name("  --  "), "sheet");
+        assert_eq!(sanitize_name("Data (2024)"), "data_2024");
+    }
+}
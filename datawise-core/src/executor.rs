@@ -8,6 +8,10 @@ use arrow::array::{
 };
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
+use crate::exporter::{ExportConfig, ExportProgress, Exporter, ProgressCallback};
+use crate::progress::Database;
+use crate::protocol::ExportSummary;
+use crate::sql::{quote_path, quote_table_name};
 use duckdb::Connection;
 use std::sync::{Arc, Mutex};
 
@@ -16,20 +20,22 @@ use std::sync::{Arc, Mutex};
 /// 使用 Arc<Mutex<Connection>> 以支持跨线程共享
 pub struct Executor {
     conn: Arc<Mutex<Connection>>,
+    database: Arc<Database>,
 }
 
 impl Executor {
     /// 创建新的执行器
     ///
-    /// 初始化一个内存中的 DuckDB 连接。
+    /// 初始化一个内存中的 DuckDB 数据库和连接。
     pub fn new() -> Result<Self> {
-        let conn = Connection::open_in_memory()
-            .context("Failed to open DuckDB in-memory database")?;
+        let database = Database::open_in_memory()?;
+        let conn = database.connect()?;
 
         tracing::info!("DuckDB executor initialized");
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
+            database: Arc::new(database),
         })
     }
 
@@ -38,6 +44,11 @@ impl Executor {
         Arc::clone(&self.conn)
     }
 
+    /// 获取数据库句柄（用于打开可跟踪进度的连接）
+    pub fn database(&self) -> Arc<Database> {
+        Arc::clone(&self.database)
+    }
+
     /// 导入 CSV 文件
     pub fn import_csv(
         &self,
@@ -46,11 +57,10 @@ impl Executor {
         _progress: Option<Box<dyn Fn(u64, u64) + Send + Sync>>,
     ) -> anyhow::Result<()> {
         let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
-
         let sql = format!(
-            "CREATE TABLE {} AS SELECT * FROM read_csv_auto('{}')",
-            table_name, path_str
+            "CREATE TABLE {} AS SELECT * FROM read_csv_auto({})",
+            quote_table_name(table_name),
+            quote_path(path)
         );
 
         conn.execute(&sql, []).context("Failed to import CSV")?;
@@ -66,11 +76,10 @@ impl Executor {
         _progress: Option<Box<dyn Fn(u64, u64) + Send + Sync>>,
     ) -> anyhow::Result<()> {
         let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
-
         let sql = format!(
-            "CREATE TABLE {} AS SELECT * FROM read_parquet('{}')",
-            table_name, path_str
+            "CREATE TABLE {} AS SELECT * FROM read_parquet({})",
+            quote_table_name(table_name),
+            quote_path(path)
         );
 
         conn.execute(&sql, []).context("Failed to import Parquet")?;
@@ -79,43 +88,33 @@ impl Executor {
     }
 
     /// 导出到 CSV
+    ///
+    /// 进度回调参数为（已导出行数，总行数）。已存在的文件会被覆盖。
     pub fn export_csv(
         &self,
         path: &std::path::Path,
         source: &str,
-        _progress: Option<Box<dyn Fn(u64, u64) + Send + Sync>>,
-    ) -> anyhow::Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
-
-        let sql = format!(
-            "COPY {} TO '{}' (FORMAT CSV, HEADER TRUE)",
-            source, path_str
-        );
-
-        conn.execute(&sql, []).context("Failed to export CSV")?;
-        tracing::info!("Data exported to CSV: {:?}", path);
-        Ok(())
+        progress: Option<Box<dyn Fn(u64, u64) + Send + Sync>>,
+    ) -> anyhow::Result<ExportSummary> {
+        self.exporter()
+            .export_csv(path, overwrite_config(source), progress.map(rows_progress))
     }
 
     /// 导出到 Parquet
+    ///
+    /// 进度回调参数为（已导出行数，总行数）。已存在的文件会被覆盖。
     pub fn export_parquet(
         &self,
         path: &std::path::Path,
         source: &str,
-        _progress: Option<Box<dyn Fn(u64, u64) + Send + Sync>>,
-    ) -> anyhow::Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
-
-        let sql = format!(
-            "COPY {} TO '{}' (FORMAT PARQUET)",
-            source, path_str
-        );
+        progress: Option<Box<dyn Fn(u64, u64) + Send + Sync>>,
+    ) -> anyhow::Result<ExportSummary> {
+        self.exporter()
+            .export_parquet(path, overwrite_config(source), progress.map(rows_progress))
+    }
 
-        conn.execute(&sql, []).context("Failed to export Parquet")?;
-        tracing::info!("Data exported to Parquet: {:?}", path);
-        Ok(())
+    fn exporter(&self) -> Exporter {
+        Exporter::new(self.conn_arc()).with_database(self.database())
     }
 
     /// 执行 SQL 查询
@@ -328,3 +327,19 @@ impl Default for Executor {
     }
 }
 
+/// 导出整张表并覆盖已有文件（保持这两个方法原有的行为）
+fn overwrite_config(source: &str) -> ExportConfig {
+    ExportConfig {
+        overwrite: true,
+        ..ExportConfig::new_table(source.to_string())
+    }
+}
+
+/// 把按行数的进度回调适配为导出进度回调
+fn rows_progress(progress: Box<dyn Fn(u64, u64) + Send + Sync>) -> ProgressCallback {
+    Box::new(move |p: &ExportProgress| match (p.rows_written, p.total_rows) {
+        (Some(rows_written), Some(total_rows)) => progress(rows_written, total_rows),
+        // 行数未知时按百分比报告
+        _ => progress((p.fraction * 100.0) as u64, 100),
+    })
+}
@@ -1,12 +1,45 @@
 //! 文件导出模块
 //!
-//! 支持导出到 CSV、Parquet 格式，带进度报告
+//! 支持导出到 CSV、Parquet、Excel、Arrow IPC 格式，带进度报告
+//!
+//! CSV 的方言（分隔符、换行符、编码等）由 [`crate::csv`] 处理。
+//!
+//! 进度按已导出的行数和已写入的字节数报告，导出完成后返回 [`ExportSummary`]。
+//!
+//! 所有导出都先写入目标所在目录的临时文件，成功后原子地重命名为目标路径，
+//! 出错或取消时删除临时文件，目标路径上不会留下写了一半的文件。
+//! 目标已存在且未设置 `overwrite` 时返回 [`ExportError::TargetExists`]。
 
-use anyhow::{Context, Result};
-use std::path::Path;
+use crate::csv;
+use crate::excel::WorkbookWriter;
+use crate::progress::{needs_session, Database, PROGRESS_INTERVAL};
+use crate::protocol::{ExportOptions, ExportSummary, ParquetCompression, ParquetOptions};
+use crate::sql::{quote_ident, quote_literal, quote_path, quote_table_name};
+use anyhow::{bail, Context, Result};
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use arrow::record_batch::RecordBatch;
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
+use tempfile::{TempDir, TempPath};
 use tracing::info;
 
+/// 导出错误
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    /// 目标文件或目录已存在，且未设置覆盖
+    #[error("Export target already exists: {0:?}")]
+    TargetExists(PathBuf),
+    /// 导出被取消
+    #[error("Export cancelled")]
+    Cancelled,
+}
+
 /// 导出器配置
 #[derive(Debug, Clone)]
 pub struct ExportConfig {
@@ -14,6 +47,12 @@ pub struct ExportConfig {
     pub source: String,
     /// 是否是 SQL 查询（true）还是表名（false）
     pub is_query: bool,
+    /// 导出选项
+    pub options: ExportOptions,
+    /// 是否覆盖已存在的目标文件（默认 false）
+    pub overwrite: bool,
+    /// 取消标记，置为 true 后导出尽快停止并返回 [`ExportError::Cancelled`]
+    pub cancel: Option<Arc<AtomicBool>>,
 }
 
 impl ExportConfig {
@@ -21,6 +60,9 @@ impl ExportConfig {
         Self {
             source: table_name,
             is_query: false,
+            options: ExportOptions::default(),
+            overwrite: false,
+            cancel: None,
         }
     }
 
@@ -28,73 +70,654 @@ impl ExportConfig {
         Self {
             source: sql,
             is_query: true,
+            options: ExportOptions::default(),
+            overwrite: false,
+            cancel: None,
+        }
+    }
+
+    /// 导出是否已被取消
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|flag| flag.load(Ordering::SeqCst))
+    }
+
+    /// 已取消时返回 [`ExportError::Cancelled`]
+    fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(ExportError::Cancelled.into());
         }
+        Ok(())
     }
+
+    /// 读取导出数据的查询
+    fn select_sql(&self) -> String {
+        if self.is_query {
+            self.source.clone()
+        } else {
+            format!("SELECT * FROM {}", quote_table_name(&self.source))
+        }
+    }
+}
+
+/// 导出进度
+///
+/// 用 DuckDB 的 `COPY` 导出时，执行期间只知道完成比例，行数在导出完成后才知道。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportProgress {
+    /// 完成比例（0.0-1.0）
+    pub fraction: f64,
+    /// 已导出的行数（未知时为 `None`）
+    pub rows_written: Option<u64>,
+    /// 总行数（未知时为 `None`）
+    pub total_rows: Option<u64>,
+    /// 已写入输出文件的字节数
+    pub bytes_written: u64,
 }
 
 /// 导出进度回调
-pub type ProgressCallback = Box<dyn Fn(u64, u64) + Send + Sync>;
+pub type ProgressCallback = Box<dyn Fn(&ExportProgress) + Send + Sync>;
+
+/// Excel 工作簿中的一个工作表
+#[derive(Debug, Clone)]
+pub struct ExcelSheet {
+    /// 工作表名
+    pub name: String,
+    /// 写入该工作表的表或查询
+    pub config: ExportConfig,
+}
 
 /// 文件导出器
 pub struct Exporter {
     conn: Arc<std::sync::Mutex<duckdb::Connection>>,
+    database: Option<Arc<Database>>,
 }
 
 impl Exporter {
     pub fn new(conn: Arc<std::sync::Mutex<duckdb::Connection>>) -> Self {
-        Self { conn }
+        Self { conn, database: None }
+    }
+
+    /// 使用数据库句柄跟踪 `COPY` 的进度
+    ///
+    /// `database` 须与 `conn` 属于同一个数据库。未设置时，
+    /// CSV、Parquet 导出只在完成后报告一次进度。
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
     }
 
     /// 导出到 CSV
+    ///
+    /// 按 `config.options.csv` 设置分隔符、表头、引号、NULL 字符串和日期格式；
+    /// 需要 CRLF 换行、BOM 或 GBK 编码时，先导出到同目录的临时文件再改写。
     pub fn export_csv(
         &self,
         path: &Path,
         config: ExportConfig,
-        _progress: Option<ProgressCallback>,
-    ) -> Result<()> {
+        progress: Option<ProgressCallback>,
+    ) -> Result<ExportSummary> {
         info!("Exporting to CSV: {:?}", path);
+        let dialect = &config.options.csv;
+        let options = csv::copy_options(dialect)?;
+        let output = AtomicOutput::file(path, config.overwrite)?;
+        let summary = if csv::needs_rewrite(dialect) {
+            // DuckDB 写出的中间文件，改写后丢弃
+            let duckdb_output = AtomicOutput::file(path, true)?;
+            let summary = self
+                .copy_to(duckdb_output.path(), &config, &options, progress)
+                .context("Failed to export CSV")?;
+            csv::rewrite_output(duckdb_output.path(), output.path(), dialect).context("Failed to export CSV")?;
+            ExportSummary {
+                bytes_written: output_size(output.path()),
+                ..summary
+            }
+        } else {
+            self.copy_to(output.path(), &config, &options, progress)
+                .context("Failed to export CSV")?
+        };
+        output.commit()?;
+        info!("CSV export completed ({} rows)", summary.rows_exported);
+        Ok(summary)
+    }
+
+    /// 导出到 Parquet
+    ///
+    /// 按 `config.options.parquet` 设置压缩编码、行组大小和键值元数据；
+    /// 设置了分区列时 `path` 是输出目录。
+    pub fn export_parquet(
+        &self,
+        path: &Path,
+        config: ExportConfig,
+        progress: Option<ProgressCallback>,
+    ) -> Result<ExportSummary> {
+        info!("Exporting to Parquet: {:?}", path);
+        let options = parquet_copy_options(&config.options.parquet)?;
+        let output = if config.options.parquet.partition_by.is_empty() {
+            AtomicOutput::file(path, config.overwrite)?
+        } else {
+            AtomicOutput::dir(path, config.overwrite)?
+        };
+        let summary = self
+            .copy_to(output.path(), &config, &options, progress)
+            .context("Failed to export Parquet")?;
+        output.commit()?;
+        info!("Parquet export completed ({} rows)", summary.rows_exported);
+        Ok(summary)
+    }
 
+    /// 用 DuckDB 的 `COPY` 导出
+    ///
+    /// 查询只执行一次：执行期间按 DuckDB 的查询进度报告完成比例，并读取输出文件的当前大小；
+    /// 导出的行数取自 `COPY` 的返回值，列数取自 `DESCRIBE`（只规划不执行）。
+    /// 取消时中断 DuckDB 的查询。
+    fn copy_to(
+        &self,
+        path: &Path,
+        config: &ExportConfig,
+        options: &str,
+        progress: Option<ProgressCallback>,
+    ) -> Result<ExportSummary> {
+        let started = Instant::now();
         let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
-        let source = &config.source;
 
-        // 使用 DuckDB 的 SQL 接口导出 CSV
-        let sql = if config.is_query {
-            format!("COPY ({}) TO '{}' (FORMAT CSV, HEADER TRUE)", source, path_str)
+        let select = config.select_sql();
+        let column_count = count_columns(&conn, &select)?;
+        let mut reporter = ProgressReporter::new(progress.as_deref(), None);
+        config.check_cancelled()?;
+
+        let sql = format!("COPY ({}) TO {} ({})", select, quote_path(path), options);
+        let database = match &self.database {
+            Some(database) if !needs_session(&conn, &select)? => Some(database),
+            _ => None,
+        };
+        let result = match database {
+            Some(database) => database.execute_with_progress(&sql, config.cancel.as_deref(), &mut |fraction| {
+                reporter.report_fraction(fraction, output_size(path))
+            }),
+            None => conn.execute(&sql, []).map(|rows| rows as u64).map_err(Into::into),
+        };
+        config.check_cancelled()?;
+        let rows_exported = result?;
+
+        let bytes_written = output_size(path);
+        reporter.finish(rows_exported, bytes_written);
+
+        Ok(ExportSummary {
+            rows_exported,
+            column_count,
+            bytes_written,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// 导出到 Excel（单个工作表）
+    ///
+    /// 工作表以表名命名，查询结果写入 `Sheet1`。超过 Excel 行数限制时返回错误。
+    pub fn export_excel(
+        &self,
+        path: &Path,
+        config: ExportConfig,
+        progress: Option<ProgressCallback>,
+    ) -> Result<ExportSummary> {
+        let name = if config.is_query {
+            "Sheet1".to_string()
         } else {
-            format!("COPY {} TO '{}' (FORMAT CSV, HEADER TRUE)", source, path_str)
+            config.source.clone()
         };
+        let overwrite = config.overwrite;
+        self.export_excel_sheets(path, &[ExcelSheet { name, config }], false, overwrite, progress)
+    }
 
-        conn.execute(&sql, []).context("Failed to export CSV")?;
+    /// 把多个表或查询分别写入同一个 Excel 工作簿的多个工作表
+    ///
+    /// `split_large_sheets` 为 true 时，超过 Excel 行数限制（1,048,576 行）的结果
+    /// 自动拆分到多个工作表；否则返回错误。工作簿在最后一次性保存，
+    /// 因此写入过程中只报告行数，字节数在保存后报告。
+    /// 返回的列数是所有工作表的列数之和。
+    ///
+    /// 任一工作表的 `config.cancel` 被置位时，导出在工作表之间停止。
+    pub fn export_excel_sheets(
+        &self,
+        path: &Path,
+        sheets: &[ExcelSheet],
+        split_large_sheets: bool,
+        overwrite: bool,
+        progress: Option<ProgressCallback>,
+    ) -> Result<ExportSummary> {
+        if sheets.is_empty() {
+            bail!("No sheets to export");
+        }
 
-        info!("CSV export completed");
-        Ok(())
+        info!("Exporting {} sheet(s) to Excel: {:?}", sheets.len(), path);
+        let started = Instant::now();
+        let output = AtomicOutput::file(path, overwrite)?;
+        let check_cancelled = || sheets.iter().try_for_each(|sheet| sheet.config.check_cancelled());
+
+        let conn = self.conn.lock().unwrap();
+
+        // 先执行所有查询，以便得到总行数
+        let mut results = Vec::with_capacity(sheets.len());
+        for sheet in sheets {
+            check_cancelled()?;
+            let mut stmt = conn
+                .prepare(&sheet.config.select_sql())
+                .with_context(|| format!("Failed to prepare query for sheet: {}", sheet.name))?;
+            let arrow = stmt
+                .query_arrow([])
+                .with_context(|| format!("Failed to query data for sheet: {}", sheet.name))?;
+            let schema = arrow.get_schema();
+            let batches: Vec<_> = arrow.collect();
+            results.push((schema, batches));
+        }
+        drop(conn);
+
+        let total_rows: u64 = results
+            .iter()
+            .flat_map(|(_, batches)| batches.iter())
+            .map(|b| b.num_rows() as u64)
+            .sum();
+        let column_count = results.iter().map(|(schema, _)| schema.fields().len()).sum();
+
+        let mut reporter = ProgressReporter::new(progress.as_deref(), Some(total_rows));
+        let mut written = 0;
+        let mut on_rows = |rows: u64| {
+            written += rows;
+            reporter.report(written, 0);
+        };
+
+        let mut writer = WorkbookWriter::new(split_large_sheets);
+        for (sheet, (schema, batches)) in sheets.iter().zip(&results) {
+            check_cancelled()?;
+            writer.write_sheet(&sheet.name, schema, batches, &mut on_rows)?;
+        }
+        check_cancelled()?;
+        writer.save(output.path())?;
+
+        let bytes_written = output_size(output.path());
+        output.commit()?;
+        reporter.finish(total_rows, bytes_written);
+
+        info!("Excel export completed ({} rows)", total_rows);
+        Ok(ExportSummary {
+            rows_exported: total_rows,
+            column_count,
+            bytes_written,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
     }
 
-    /// 导出到 Parquet
-    pub fn export_parquet(
+    /// 导出到 Arrow IPC
+    ///
+    /// 扩展名为 `.arrows` 时写为 IPC 流，否则写为 IPC 文件（Feather v2）。
+    /// 直接写出 DuckDB 返回的 RecordBatch，保留嵌套类型和字典编码（ENUM 列）。
+    pub fn export_arrow(
         &self,
         path: &Path,
         config: ExportConfig,
-        _progress: Option<ProgressCallback>,
-    ) -> Result<()> {
-        info!("Exporting to Parquet: {:?}", path);
+        progress: Option<ProgressCallback>,
+    ) -> Result<ExportSummary> {
+        info!("Exporting to Arrow IPC: {:?}", path);
+        let started = Instant::now();
+        let output_file = AtomicOutput::file(path, config.overwrite)?;
 
         let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
-        let source = &config.source;
+        let mut stmt = conn.prepare(&config.select_sql()).context("Failed to prepare export query")?;
+        stmt.execute([]).context("Failed to query export data")?;
+        // 结果已经物化，行数不需要再执行一次查询
+        let total_rows = stmt.row_count() as u64;
+        let schema = stmt.schema();
+        let batches = std::iter::from_fn(|| stmt.step()).map(|array| RecordBatch::from(&array));
+        let mut reporter = ProgressReporter::new(progress.as_deref(), Some(total_rows));
 
-        // 使用 DuckDB 的 SQL 接口导出 Parquet
-        let sql = if config.is_query {
-            format!("COPY ({}) TO '{}' (FORMAT PARQUET)", source, path_str)
+        let bytes = Rc::new(Cell::new(0));
+        let output = BufWriter::new(CountingWriter {
+            inner: File::create(output_file.path())
+                .with_context(|| format!("Failed to create file: {:?}", output_file.path()))?,
+            count: Rc::clone(&bytes),
+        });
+        let is_stream = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("arrows"));
+
+        let mut rows = 0;
+        if is_stream {
+            let mut writer = StreamWriter::try_new(output, &schema)?;
+            for batch in batches {
+                config.check_cancelled()?;
+                writer.write(&batch)?;
+                rows += batch.num_rows() as u64;
+                reporter.report(rows, bytes.get());
+            }
+            writer.finish()?;
         } else {
-            format!("COPY {} TO '{}' (FORMAT PARQUET)", source, path_str)
-        };
+            let mut writer = FileWriter::try_new(output, &schema)?;
+            for batch in batches {
+                config.check_cancelled()?;
+                writer.write(&batch)?;
+                rows += batch.num_rows() as u64;
+                reporter.report(rows, bytes.get());
+            }
+            writer.finish()?;
+        }
 
-        conn.execute(&sql, []).context("Failed to export Parquet")?;
+        let bytes_written = output_size(output_file.path());
+        output_file.commit()?;
+        reporter.finish(rows, bytes_written);
 
-        info!("Parquet export completed");
+        info!("Arrow IPC export completed ({} rows)", rows);
+        Ok(ExportSummary {
+            rows_exported: rows,
+            column_count: schema.fields().len(),
+            bytes_written,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+/// 查询结果的列数（`DESCRIBE` 只规划查询，不执行）
+fn count_columns(conn: &duckdb::Connection, select: &str) -> Result<usize> {
+    let columns: i64 = conn
+        .query_row(&format!("SELECT COUNT(*) FROM (DESCRIBE {})", select), [], |row| row.get(0))
+        .context("Failed to describe export query")?;
+    Ok(columns as usize)
+}
+
+/// Parquet 的 `COPY` 选项
+fn parquet_copy_options(options: &ParquetOptions) -> Result<String> {
+    let mut copy_options = vec!["FORMAT PARQUET".to_string()];
+
+    match options.compression {
+        ParquetCompression::Snappy => copy_options.push("COMPRESSION snappy".to_string()),
+        ParquetCompression::Gzip => copy_options.push("COMPRESSION gzip".to_string()),
+        ParquetCompression::Uncompressed => copy_options.push("COMPRESSION uncompressed".to_string()),
+        ParquetCompression::Zstd { level } => {
+            copy_options.push("COMPRESSION zstd".to_string());
+            if let Some(level) = level {
+                if !(1..=22).contains(&level) {
+                    bail!("Zstd compression level must be between 1 and 22, got {}", level);
+                }
+                copy_options.push(format!("COMPRESSION_LEVEL {}", level));
+            }
+        }
+    }
+
+    if let Some(rows) = options.row_group_size {
+        if rows == 0 {
+            bail!("Row group size must be greater than 0");
+        }
+        copy_options.push(format!("ROW_GROUP_SIZE {}", rows));
+    }
+
+    if !options.partition_by.is_empty() {
+        let columns: Vec<String> = options.partition_by.iter().map(|c| quote_ident(c)).collect();
+        copy_options.push(format!("PARTITION_BY ({})", columns.join(", ")));
+    }
+
+    if !options.metadata.is_empty() {
+        let entries: Vec<String> = options
+            .metadata
+            .iter()
+            .map(|(key, value)| format!("{}: {}", quote_ident(key), quote_literal(value)))
+            .collect();
+        copy_options.push(format!("KV_METADATA {{{}}}", entries.join(", ")));
+    }
+
+    Ok(copy_options.join(", "))
+}
+
+/// 输出的当前大小（尚未创建时为 0），分区导出时为目录下所有文件的总大小
+fn output_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| output_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// 导出的临时输出
+///
+/// 数据写入目标所在目录中的临时文件（或目录），`commit` 时重命名为目标路径。
+/// 未提交就 drop（出错或取消）时删除临时输出。
+struct AtomicOutput {
+    target: PathBuf,
+    temp: TempOutput,
+    overwrite: bool,
+}
+
+enum TempOutput {
+    File(TempPath),
+    /// 分区导出：DuckDB 在临时目录中创建 `path` 目录
+    Dir { _dir: TempDir, path: PathBuf },
+}
+
+impl AtomicOutput {
+    /// 临时文件，文件名以目标文件名结尾，以保留扩展名（DuckDB 据此推断压缩格式）
+    fn file(target: &Path, overwrite: bool) -> Result<Self> {
+        let (dir, file_name) = Self::check_target(target, overwrite)?;
+        let suffix = format!(".{}", file_name);
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(".").suffix(&suffix);
+        // 临时文件默认只有所有者可读写，重命名后应与普通新建的文件一致
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            builder.permissions(std::fs::Permissions::from_mode(0o644));
+        }
+        let temp = builder
+            .tempfile_in(dir)
+            .with_context(|| format!("Failed to create temp file in: {:?}", dir))?
+            .into_temp_path();
+        Ok(Self {
+            target: target.to_path_buf(),
+            temp: TempOutput::File(temp),
+            overwrite,
+        })
+    }
+
+    /// 临时目录（用于分区导出）
+    fn dir(target: &Path, overwrite: bool) -> Result<Self> {
+        let (dir, file_name) = Self::check_target(target, overwrite)?;
+        let temp_dir = tempfile::Builder::new()
+            .prefix(".")
+            .tempdir_in(dir)
+            .with_context(|| format!("Failed to create temp directory in: {:?}", dir))?;
+        let path = temp_dir.path().join(file_name);
+        Ok(Self {
+            target: target.to_path_buf(),
+            temp: TempOutput::Dir { _dir: temp_dir, path },
+            overwrite,
+        })
+    }
+
+    /// 检查目标是否已存在，返回 (所在目录, 文件名)
+    fn check_target(target: &Path, overwrite: bool) -> Result<(&Path, String)> {
+        if !overwrite && target.exists() {
+            return Err(ExportError::TargetExists(target.to_path_buf()).into());
+        }
+        let file_name = target
+            .file_name()
+            .with_context(|| format!("Export path has no file name: {:?}", target))?
+            .to_string_lossy()
+            .to_string();
+        let dir = target.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        Ok((dir, file_name))
+    }
+
+    fn path(&self) -> &Path {
+        match &self.temp {
+            TempOutput::File(temp) => temp,
+            TempOutput::Dir { path, .. } => path,
+        }
+    }
+
+    /// 把临时输出重命名为目标路径
+    fn commit(self) -> Result<()> {
+        let target = self.target;
+        match self.temp {
+            TempOutput::File(temp) if self.overwrite => {
+                temp.persist(&target)
+                    .with_context(|| format!("Failed to write file: {:?}", target))?;
+            }
+            // 不覆盖时原子地检查并重命名，避免覆盖导出期间出现的同名文件
+            TempOutput::File(temp) => match temp.persist_noclobber(&target) {
+                Ok(()) => {}
+                Err(e) if e.error.kind() == io::ErrorKind::AlreadyExists => {
+                    return Err(ExportError::TargetExists(target).into());
+                }
+                Err(e) => return Err(e.error).with_context(|| format!("Failed to write file: {:?}", target)),
+            },
+            TempOutput::Dir { _dir, path } => {
+                if !target.exists() {
+                    return std::fs::rename(&path, &target)
+                        .with_context(|| format!("Failed to write directory: {:?}", target));
+                }
+                if !self.overwrite {
+                    return Err(ExportError::TargetExists(target).into());
+                }
+                if !is_partition_tree(&target)? {
+                    bail!("Refusing to replace {:?}: it is not a partitioned export directory", target);
+                }
+
+                // 先把旧目录移到同级的备份目录，新目录就位后才删除备份；失败时恢复旧目录
+                let parent = target.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+                let backup_dir = tempfile::Builder::new()
+                    .prefix(".")
+                    .tempdir_in(parent)
+                    .with_context(|| format!("Failed to create backup directory in: {:?}", parent))?;
+                let backup = backup_dir.path().join("previous");
+                std::fs::rename(&target, &backup)
+                    .with_context(|| format!("Failed to move existing output aside: {:?}", target))?;
+                if let Err(e) = std::fs::rename(&path, &target) {
+                    if let Err(restore) = std::fs::rename(&backup, &target) {
+                        tracing::error!("Failed to restore previous output {:?}: {}", target, restore);
+                        // 保留备份目录，避免丢失旧数据
+                        let kept = backup_dir.keep();
+                        return Err(e).with_context(|| {
+                            format!("Failed to write directory: {:?} (previous output kept in {:?})", target, kept)
+                        });
+                    }
+                    return Err(e).with_context(|| format!("Failed to write directory: {:?}", target));
+                }
+                // backup_dir 析构时删除旧目录
+            }
+        }
         Ok(())
     }
 }
 
+/// 目录是否是分区导出写出的 Hive 风格目录树（或空目录）
+///
+/// 顶层只能有 `列=值` 子目录；子目录中是数据文件或下一级 `列=值` 子目录。
+fn is_partition_tree(dir: &Path) -> Result<bool> {
+    fn check(dir: &Path, top: bool) -> io::Result<bool> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let valid = if file_type.is_dir() {
+                name.contains('=') && check(&entry.path(), false)?
+            } else {
+                !top && file_type.is_file()
+            };
+            if !valid {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    if !dir.is_dir() {
+        return Ok(false);
+    }
+    check(dir, true).with_context(|| format!("Failed to inspect existing output: {:?}", dir))
+}
+
+/// 统计写入字节数的 Writer
+struct CountingWriter<W> {
+    inner: W,
+    count: Rc<Cell<u64>>,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 导出进度报告
+///
+/// 只在百分比变化且距上次回调超过 [`PROGRESS_INTERVAL`] 时回调；`finish` 总是回调。
+struct ProgressReporter<'a> {
+    callback: Option<&'a (dyn Fn(&ExportProgress) + Send + Sync)>,
+    total_rows: Option<u64>,
+    last_pct: u64,
+    last_report: Option<Instant>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn new(callback: Option<&'a (dyn Fn(&ExportProgress) + Send + Sync)>, total_rows: Option<u64>) -> Self {
+        Self {
+            callback,
+            total_rows,
+            last_pct: 0,
+            last_report: None,
+        }
+    }
+
+    /// 按已导出的行数报告（总行数已知时）
+    fn report(&mut self, rows_written: u64, bytes_written: u64) {
+        let Some(total_rows) = self.total_rows.filter(|&total| total > 0) else {
+            return;
+        };
+        let rows_written = rows_written.min(total_rows);
+        self.emit(rows_written as f64 / total_rows as f64, Some(rows_written), bytes_written);
+    }
+
+    /// 按完成比例报告（总行数未知时）
+    fn report_fraction(&mut self, fraction: f64, bytes_written: u64) {
+        self.emit(fraction.clamp(0.0, 1.0), None, bytes_written);
+    }
+
+    fn emit(&mut self, fraction: f64, rows_written: Option<u64>, bytes_written: u64) {
+        let pct = (fraction * 100.0) as u64;
+        if pct <= self.last_pct {
+            return;
+        }
+        let now = Instant::now();
+        if self.last_report.is_some_and(|last| now.duration_since(last) < PROGRESS_INTERVAL) {
+            return;
+        }
+        self.last_pct = pct;
+        self.last_report = Some(now);
+        if let Some(cb) = self.callback {
+            cb(&ExportProgress {
+                fraction,
+                rows_written,
+                total_rows: self.total_rows,
+                bytes_written,
+            });
+        }
+    }
+
+    fn finish(&self, rows_written: u64, bytes_written: u64) {
+        if let Some(cb) = self.callback {
+            cb(&ExportProgress {
+                fraction: 1.0,
+                rows_written: Some(rows_written),
+                total_rows: Some(rows_written),
+                bytes_written,
+            });
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! 查询历史
+//!
+//! 记录每次执行的 SQL（开始时间、耗时、结果行数、错误），所有前端共用，
+//! 可以列出、搜索、重新执行和置顶。
+//!
+//! 打开历史文件（JSON）后，变化写回该文件（先写临时文件再重命名）；未打开时只保存在内存中。
+//! 记录查询时只通知后台线程写回，不在执行查询的路径上写文件；打开、置顶和释放时立即写回。
+//! 历史文件损坏时改名为 `<文件名>.corrupt` 保留，从空历史开始。
+//! 未置顶的记录最多保留 [`MAX_ENTRIES`] 条，超出时删除最早的。
+
+use crate::persist;
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+/// 保留的未置顶记录数
+pub const MAX_ENTRIES: usize = 1000;
+
+/// 一次查询
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// 记录 ID，递增
+    pub id: u64,
+    /// 执行的 SQL
+    pub sql: String,
+    /// 开始时间（UTC，RFC 3339）
+    pub executed_at: String,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+    /// 结果行数（失败时为 `None`）
+    pub row_count: Option<u64>,
+    /// 错误信息（成功时为 `None`）
+    pub error: Option<String>,
+    /// 是否置顶
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// 查询历史
+#[derive(Default)]
+pub struct QueryHistory {
+    shared: Arc<Shared>,
+    /// 通知后台线程写回，打开历史文件时启动
+    writer: Mutex<Option<Sender<()>>>,
+}
+
+/// 与后台写回线程共用的状态
+#[derive(Default)]
+struct Shared {
+    store: Mutex<HistoryStore>,
+    /// 已写入文件的版本；写文件时一直持有，保证后取的快照后写入
+    written: Mutex<u64>,
+}
+
+#[derive(Default)]
+struct HistoryStore {
+    /// 按执行顺序
+    entries: Vec<HistoryEntry>,
+    /// 打开的历史文件，变化时写回
+    path: Option<PathBuf>,
+    /// 每次变化加 1
+    version: u64,
+}
+
+impl Shared {
+    /// 把当前记录写回历史文件（已是最新时不写）
+    ///
+    /// 只在复制记录时持有 `store` 的锁，序列化和写文件时不阻塞记录查询。
+    fn save(&self) -> Result<()> {
+        let mut written = self.written.lock().unwrap();
+        let (path, entries, version) = {
+            let store = self.store.lock().unwrap();
+            match &store.path {
+                Some(path) if store.version != *written => (path.clone(), store.entries.clone(), store.version),
+                _ => return Ok(()),
+            }
+        };
+        let text = serde_json::to_string_pretty(&entries)?;
+        persist::write_atomic(&path, text)
+            .with_context(|| format!("Failed to write history file: {}", path.display()))?;
+        *written = version;
+        Ok(())
+    }
+}
+
+impl HistoryStore {
+    fn next_id(&self) -> u64 {
+        self.entries.iter().map(|e| e.id).max().unwrap_or(0) + 1
+    }
+
+    /// 删除超出上限的最早的未置顶记录
+    fn trim(&mut self) {
+        let unpinned = self.entries.iter().filter(|e| !e.pinned).count();
+        let mut excess = unpinned.saturating_sub(MAX_ENTRIES);
+        self.entries.retain(|e| {
+            if excess > 0 && !e.pinned {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+impl QueryHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 打开历史文件：文件存在时读取其中的记录，并合并当前内存中的记录；之后的变化写回该文件
+    ///
+    /// 文件无法解析时改名保留，按空历史打开。
+    pub fn open(&self, path: &Path) -> Result<usize> {
+        let mut entries: Vec<HistoryEntry> = if path.exists() {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read history file: {}", path.display()))?;
+            match serde_json::from_str(&text) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    let moved = persist::quarantine(path)
+                        .with_context(|| format!("Failed to move aside invalid history file: {}", path.display()))?;
+                    warn!("Invalid history file {} ({}), moved to {}", path.display(), e, moved.display());
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        let count = {
+            let mut store = self.shared.store.lock().unwrap();
+            // 打开前在内存中记录的查询排在文件中的记录之后，重新编号
+            let first_id = entries.iter().map(|e| e.id).max().unwrap_or(0) + 1;
+            for (id, mut entry) in (first_id..).zip(store.entries.drain(..)) {
+                entry.id = id;
+                entries.push(entry);
+            }
+            store.entries = entries;
+            store.path = Some(path.to_path_buf());
+            store.trim();
+            store.version += 1;
+            store.entries.len()
+        };
+        self.shared.save()?;
+
+        let mut writer = self.writer.lock().unwrap();
+        if writer.is_none() {
+            let (tx, rx) = mpsc::channel();
+            let shared = Arc::clone(&self.shared);
+            std::thread::spawn(move || {
+                while rx.recv().is_ok() {
+                    // 合并积压的通知，只写一次
+                    while rx.try_recv().is_ok() {}
+                    if let Err(e) = shared.save() {
+                        warn!("Failed to save query history: {:#}", e);
+                    }
+                }
+            });
+            *writer = Some(tx);
+        }
+        Ok(count)
+    }
+
+    /// 立即把未写回的记录写入历史文件
+    pub fn flush(&self) -> Result<()> {
+        self.shared.save()
+    }
+
+    /// 记录一次查询
+    ///
+    /// 打开了历史文件时由后台线程写回，写入失败只记录日志。
+    pub fn record(
+        &self,
+        sql: &str,
+        started: SystemTime,
+        duration: Duration,
+        result: Result<usize, &anyhow::Error>,
+    ) -> HistoryEntry {
+        let mut store = self.shared.store.lock().unwrap();
+        let entry = HistoryEntry {
+            id: store.next_id(),
+            sql: sql.to_string(),
+            executed_at: DateTime::<Utc>::from(started).to_rfc3339_opts(SecondsFormat::Millis, true),
+            duration_ms: duration.as_millis() as u64,
+            row_count: result.as_ref().ok().map(|&rows| rows as u64),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            pinned: false,
+        };
+        store.entries.push(entry.clone());
+        store.trim();
+        store.version += 1;
+        drop(store);
+
+        if let Some(writer) = &*self.writer.lock().unwrap() {
+            let _ = writer.send(());
+        }
+        entry
+    }
+
+    /// 按 ID 查找记录
+    pub fn get(&self, id: u64) -> Result<HistoryEntry> {
+        let store = self.shared.store.lock().unwrap();
+        store
+            .entries
+            .iter()
+            .find(|e| e.id == id)
+            .cloned()
+            .with_context(|| format!("History entry not found: {}", id))
+    }
+
+    /// 列出记录，最新的在前
+    ///
+    /// `text` 不为空时只返回 SQL 中包含该文本（不区分大小写）的记录；
+    /// `pinned_only` 时只返回置顶的记录；`limit` 为 0 时不限制条数。
+    pub fn list(&self, text: Option<&str>, pinned_only: bool, limit: usize) -> Vec<HistoryEntry> {
+        let text = text.map(str::to_lowercase).filter(|t| !t.is_empty());
+        let store = self.shared.store.lock().unwrap();
+        let entries = store
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| !pinned_only || e.pinned)
+            .filter(|e| text.as_ref().is_none_or(|t| e.sql.to_lowercase().contains(t.as_str())))
+            .cloned();
+        match limit {
+            0 => entries.collect(),
+            limit => entries.take(limit).collect(),
+        }
+    }
+
+    /// 置顶或取消置顶，置顶的记录不会因超出上限被删除
+    pub fn pin(&self, id: u64, pinned: bool) -> Result<HistoryEntry> {
+        let entry = {
+            let mut store = self.shared.store.lock().unwrap();
+            let entry = store
+                .entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .with_context(|| format!("History entry not found: {}", id))?;
+            entry.pinned = pinned;
+            let entry = entry.clone();
+            store.trim();
+            store.version += 1;
+            entry
+        };
+        self.shared.save()?;
+        Ok(entry)
+    }
+}
+
+impl Drop for QueryHistory {
+    /// 写回后台线程还没写入的记录
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            warn!("Failed to save query history: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_keeps_pinned_entries() {
+        let history = QueryHistory::new();
+        let first = history.record("SELECT 'pinned'", SystemTime::now(), Duration::ZERO, Ok(1));
+        history.pin(first.id, true).unwrap();
+        for i in 0..MAX_ENTRIES + 1 {
+            history.record(&format!("SELECT {}", i), SystemTime::now(), Duration::ZERO, Ok(1));
+        }
+
+        // 最早的未置顶记录（SELECT 0）被删除，置顶的记录保留
+        let entries = history.list(None, false, 0);
+        assert_eq!(entries.len(), MAX_ENTRIES + 1);
+        assert_eq!(entries[MAX_ENTRIES].sql, "SELECT 'pinned'");
+        assert_eq!(entries[MAX_ENTRIES - 1].sql, "SELECT 1");
+        assert_eq!(history.list(None, true, 0).len(), 1);
+    }
+
+    #[test]
+    fn test_open_quarantines_corrupt_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("history.json");
+        std::fs::write(&path, "[{\"id\": 1, \"sql\": ").unwrap();
+
+        let history = QueryHistory::new();
+        history.record("SELECT 1", SystemTime::now(), Duration::ZERO, Ok(1));
+        assert_eq!(history.open(&path).unwrap(), 1);
+
+        // 损坏的文件改名保留，新文件只包含内存中的记录
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("history.json.corrupt")).unwrap(),
+            "[{\"id\": 1, \"sql\": "
+        );
+        let saved: Vec<HistoryEntry> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].sql, "SELECT 1");
+    }
+
+    #[test]
+    fn test_record_is_written_in_background() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("history.json");
+        let read = || serde_json::from_str::<Vec<HistoryEntry>>(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        let history = QueryHistory::new();
+        history.open(&path).unwrap();
+        for i in 0..3 {
+            history.record(&format!("SELECT {}", i), SystemTime::now(), Duration::ZERO, Ok(1));
+        }
+        history.flush().unwrap();
+        assert_eq!(read().len(), 3);
+
+        // 释放时写回最后的记录
+        history.record("SELECT 3", SystemTime::now(), Duration::ZERO, Ok(1));
+        drop(history);
+        assert_eq!(read().last().unwrap().sql, "SELECT 3");
+    }
+}
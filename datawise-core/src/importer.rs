@@ -1,12 +1,62 @@
 //! 文件导入模块
 //!
 //! 支持 CSV、Parquet、JSON 格式的导入，带进度报告
+//!
+//! 除单个文件外，还支持 glob 模式、目录和显式文件列表，
+//! 多个文件会被合并导入到同一张表中。
+//!
+//! 压缩文件（`.gz`、`.zst`、`.bz2`）和 zip 压缩包也可以直接导入：
+//! 所有文件都是 gzip（或都是 zstd）压缩的 CSV、JSON 时直接交给 DuckDB，并显式指定
+//! `compression`（DuckDB 不认识 `.gzip`、`.zstd` 扩展名）；其余的先流式解压到临时目录再导入，
+//! 解压时每读一块就按已读取的压缩字节数报告进度。
+//!
+//! CSV 可以指定方言（分隔符、表头、NULL 字符串、日期格式等，见 [`crate::csv`]），
+//! GBK 编码的文件先转码为 UTF-8 临时文件。开启容错导入（[`ImportOptions::rejects`]）时，
+//! 无法解析的行被跳过，连同行号和原因写入 `<表名>_rejects` 表或 CSV 文件。
+//!
+//! Excel 工作簿由 [`crate::excel`] 读取后逐行插入，可选择工作表和单元格区域。
+//!
+//! Arrow IPC 文件/流直接以 RecordBatch 交给 DuckDB，保留嵌套类型，
+//! 字典编码的字符串列导入为 ENUM（导出时仍为字典编码）。
+//!
+//! SQLite 数据库可以整表导入（由 [`crate::sqlite`] 读取），也可以附加后直接查询。
+//!
+//! PostgreSQL/MySQL 的查询结果通过 [`crate::database`] 下推执行后以 RecordBatch 导入。
 
-use anyhow::{Context, Result};
-use std::path::Path;
+use crate::csv;
+use crate::database::{self, RemoteTable};
+use crate::excel;
+use crate::progress::{needs_session, Database, PROGRESS_INTERVAL};
+use crate::protocol::{Compression, ConnectionProfile, FileFmt, ImportOptions, RejectsOutput, TextEncoding};
+use crate::sql::{quote_ident, quote_literal, quote_path, quote_table_name, sanitize_table_name};
+use crate::sqlite::{self, SqliteTable};
+use anyhow::{bail, Context, Result};
+use arrow::array::AsArray;
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::record_batch::RecordBatch;
+use duckdb::vtab::arrow::{arrow_recordbatch_to_query_params, ArrowVTab};
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
+use tempfile::TempDir;
 use tracing::info;
 
+/// 文件导入的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOutcome {
+    /// 创建或覆盖的表（Excel 工作簿每个工作表一张表，其他格式只有 `config.table_name`）
+    pub tables: Vec<String>,
+    /// 容错导入（仅 CSV）时被拒绝的行数，否则为 `None`
+    pub rejected_rows: Option<u64>,
+}
+
 /// 导入器配置
 #[derive(Debug, Clone)]
 pub struct ImportConfig {
@@ -14,6 +64,8 @@ pub struct ImportConfig {
     pub table_name: String,
     /// 是否覆盖现有表
     pub overwrite: bool,
+    /// 导入选项
+    pub options: ImportOptions,
 }
 
 impl ImportConfig {
@@ -21,21 +73,49 @@ impl ImportConfig {
         Self {
             table_name,
             overwrite: false,
+            options: ImportOptions::default(),
         }
     }
 }
 
+/// 容错导入时 DuckDB 记录错误行的临时表
+const REJECTS_TABLE: &str = "datawise_reject_errors";
+const REJECTS_SCAN: &str = "datawise_reject_scans";
+
 /// 导入进度回调
 pub type ProgressCallback = Box<dyn Fn(u64, u64) + Send + Sync>;
 
+/// zip 压缩包中的成员文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveMember {
+    /// 成员在压缩包中的路径
+    pub name: String,
+    /// 解压后大小（字节）
+    pub size: u64,
+    /// 压缩后大小（字节）
+    pub compressed_size: u64,
+    /// 根据扩展名推断的文件格式
+    pub fmt: Option<FileFmt>,
+}
+
 /// 文件导入器
 pub struct Importer {
     conn: Arc<std::sync::Mutex<duckdb::Connection>>,
+    database: Option<Arc<Database>>,
 }
 
 impl Importer {
     pub fn new(conn: Arc<std::sync::Mutex<duckdb::Connection>>) -> Self {
-        Self { conn }
+        Self { conn, database: None }
+    }
+
+    /// 使用数据库句柄跟踪 DuckDB 读取文件的进度
+    ///
+    /// `database` 须与 `conn` 属于同一个数据库。未设置时，
+    /// DuckDB 直接读取的文件只在导入完成后报告一次进度。
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
     }
 
 
@@ -47,112 +127,1219 @@ impl Importer {
         config: ImportConfig,
         progress: Option<ProgressCallback>,
     ) -> Result<()> {
-        info!("Importing CSV from: {:?}", path);
+        self.import_files(&[path.to_path_buf()], FileFmt::Csv, config, None, progress).map(|_| ())
+    }
+
+    /// 导入 Parquet 文件
+    pub fn import_parquet(
+        &self,
+        path: &Path,
+        config: ImportConfig,
+        progress: Option<ProgressCallback>,
+    ) -> Result<()> {
+        self.import_files(&[path.to_path_buf()], FileFmt::Parquet, config, None, progress).map(|_| ())
+    }
 
-        let file_size = std::fs::metadata(path)?.len();
-        let table_name = &config.table_name;
+    /// 导入 JSON 文件
+    pub fn import_json(
+        &self,
+        path: &Path,
+        config: ImportConfig,
+        progress: Option<ProgressCallback>,
+    ) -> Result<()> {
+        self.import_files(&[path.to_path_buf()], FileFmt::Json, config, None, progress).map(|_| ())
+    }
+
+    /// 将一个或多个同格式文件导入到同一张表
+    ///
+    /// `paths` 通常由 [`resolve_paths`] 展开得到。多个文件时可通过
+    /// `config.options` 添加 `filename` 列、提取 Hive 分区列或按列名合并 schema。
+    ///
+    /// Excel 只能导入一个工作簿，每个工作表导入为一张表（见 [`Importer::import_excel`]）。
+    /// `cancel` 被置位时中断 DuckDB 读取文件的语句（在共享连接上执行时只在语句前后检查）。
+    pub fn import_files(
+        &self,
+        paths: &[PathBuf],
+        fmt: FileFmt,
+        config: ImportConfig,
+        cancel: Option<&AtomicBool>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<ImportOutcome> {
+        if paths.is_empty() {
+            bail!("No files to import");
+        }
+        if config.options.rejects.is_some() && fmt != FileFmt::Csv {
+            bail!("Rejected-row capture is only supported for CSV imports");
+        }
+
+        if fmt == FileFmt::Excel {
+            let [path] = paths else {
+                bail!("Excel import supports a single workbook, got {} files", paths.len());
+            };
+            let tables = self.import_excel(path, config, progress)?;
+            return Ok(ImportOutcome { tables, rejected_rows: None });
+        }
+
+        info!("Importing {} {:?} file(s) into: {}", paths.len(), fmt, config.table_name);
+
+        // 进度按磁盘上（压缩后）的字节数计算
+        let mut total_size = 0;
+        for path in paths {
+            total_size += std::fs::metadata(path)
+                .with_context(|| format!("Failed to read file metadata: {:?}", path))?
+                .len();
+        }
+        let mut tracker = ProgressTracker::new(progress.as_deref(), total_size);
+
+        // 解压 DuckDB 无法直接读取的文件
+        let mut staged = StagedFiles {
+            compression: native_compression(paths, fmt, &config.options),
+            ..Default::default()
+        };
+        let cancelled = || cancel.is_some_and(|flag| flag.load(Ordering::SeqCst));
+        for path in paths {
+            if cancelled() {
+                bail!("Import cancelled");
+            }
+            let origin = path.to_string_lossy().to_string();
+            stage_file(path, &origin, fmt, &config.options, &mut staged, &mut tracker)?;
+        }
+
+        // DuckDB 只读取 UTF-8 的 CSV，其他编码先转码
+        let encoding = csv_encoding(fmt, &config.options);
+        if encoding != TextEncoding::Utf8 {
+            staged.decode_csv(encoding)?;
+        }
+
+        if fmt == FileFmt::Arrow {
+            self.import_arrow(&staged.files, &config, &mut tracker)?;
+            return Ok(ImportOutcome { tables: vec![config.table_name], rejected_rows: None });
+        }
+
+        let table_name = &quote_table_name(&config.table_name);
 
-        // 使用 DuckDB 的 SQL 接口导入 CSV
         let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
+        if cancelled() {
+            bail!("Import cancelled");
+        }
 
         // 删除现有表（如果需要）
         if config.overwrite {
             let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", table_name), []);
         }
 
-        // 使用 DuckDB 的 read_csv 函数，指定 header=true 以识别列名
+        // 上次导入残留的错误行临时表会让 DuckDB 报错
+        if config.options.rejects.is_some() {
+            drop_reject_tables(&conn)?;
+        }
+
         let sql = format!(
-            "CREATE TABLE {} AS SELECT * FROM read_csv_auto('{}', header = true)",
-            table_name, path_str
+            "CREATE TABLE {} AS SELECT * FROM {}",
+            table_name,
+            reader_sql(&staged.files, fmt, &config.options, staged.compression)?
         );
 
-        conn.execute(&sql, []).context("Failed to import CSV")?;
+        // 解压已计入的字节之外，剩余进度按 DuckDB 的读取进度折算。
+        // 错误行记录在执行语句的连接的临时表中，容错导入只能在共享连接上执行，不报告读取进度
+        let staged_bytes = tracker.processed();
+        let remaining = total_size - staged_bytes;
+        let database = match &self.database {
+            Some(database) if config.options.rejects.is_none() && !needs_session(&conn, &sql)? => Some(database),
+            _ => None,
+        };
+        match database {
+            Some(database) => database.execute_with_progress(&sql, cancel, &mut |fraction| {
+                tracker.update(staged_bytes + (remaining as f64 * fraction) as u64)
+            }),
+            None => conn.execute(&sql, []).map(|rows| rows as u64).map_err(Into::into),
+        }
+        .with_context(|| format!("Failed to import {}", fmt_label(fmt)))?;
+        if cancelled() {
+            bail!("Import cancelled");
+        }
+
+        // filename 列指向原始文件，而不是临时解压出的文件
+        if config.options.filename_column {
+            for (staged_path, origin) in &staged.origins {
+                conn.execute(
+                    &format!(
+                        "UPDATE {} SET filename = {} WHERE filename = {}",
+                        table_name,
+                        quote_literal(origin),
+                        quote_path(staged_path)
+                    ),
+                    [],
+                )
+                .context("Failed to update filename column")?;
+            }
+        }
+
+        let rejected = match &config.options.rejects {
+            Some(output) => {
+                let rejected = write_rejects(&conn, &config.table_name, output, &staged.origins);
+                drop_reject_tables(&conn)?;
+                Some(rejected?)
+            }
+            None => None,
+        };
 
         // 报告进度
-        if let Some(cb) = progress {
-            cb(file_size, file_size);
+        tracker.finish();
+
+        info!("{} import completed", fmt_label(fmt));
+        Ok(ImportOutcome {
+            tables: vec![config.table_name],
+            rejected_rows: rejected,
+        })
+    }
+
+    /// 导入 Arrow IPC 文件或流，所有文件的 schema 需一致
+    ///
+    /// 通过 `arrow` 表函数把 RecordBatch 直接交给 DuckDB，不经过文本转换。
+    fn import_arrow(
+        &self,
+        paths: &[PathBuf],
+        config: &ImportConfig,
+        tracker: &mut ProgressTracker,
+    ) -> Result<()> {
+        let table_name = &quote_table_name(&config.table_name);
+
+        let mut conn = self.conn.lock().unwrap();
+        register_arrow_function(&conn)?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        if config.overwrite {
+            tx.execute(&format!("DROP TABLE IF EXISTS {}", table_name), [])?;
+        }
+
+        let mut created = false;
+        let mut dictionaries = DictionaryColumns::default();
+        for path in paths {
+            let file_size = std::fs::metadata(path)?.len();
+            let position = Rc::new(Cell::new(0));
+            let reader = CountingReader {
+                inner: BufReader::new(
+                    File::open(path).with_context(|| format!("Failed to open file: {:?}", path))?,
+                ),
+                count: Rc::clone(&position),
+            };
+            let (schema, batches) = read_arrow(reader, path)?;
+
+            let mut reported = 0;
+            let mut empty = true;
+            for batch in batches {
+                let batch = batch.with_context(|| format!("Failed to read Arrow batch: {:?}", path))?;
+                dictionaries.collect(&batch)?;
+                insert_arrow_batch(&tx, table_name, batch, created)?;
+                created = true;
+                empty = false;
+
+                // 进度按文件中的读取位置计算
+                let pos = position.get().min(file_size);
+                tracker.advance(pos.saturating_sub(reported));
+                reported = reported.max(pos);
+            }
+            // 没有数据的文件也按其 schema 建表
+            if empty && !created {
+                insert_arrow_batch(&tx, table_name, RecordBatch::new_empty(schema), false)?;
+                created = true;
+            }
+            tracker.advance(file_size.saturating_sub(reported));
         }
 
-        info!("CSV import completed");
+        // 字典编码列转换为 ENUM，字典值的顺序保持不变
+        for (column, values) in &dictionaries.columns {
+            let values = values
+                .iter()
+                .map(|v| quote_literal(v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tx.execute(
+                &format!(
+                    "ALTER TABLE {} ALTER COLUMN {} TYPE ENUM({})",
+                    table_name,
+                    quote_ident(column),
+                    values
+                ),
+                [],
+            )
+            .with_context(|| format!("Failed to convert dictionary column to ENUM: {}", column))?;
+        }
+
+        tx.commit().context("Failed to commit Arrow import")?;
+        tracker.finish();
+
+        info!("Arrow IPC import completed");
         Ok(())
     }
 
-    /// 导入 Parquet 文件
-    pub fn import_parquet(
+    /// 导入 Excel 工作簿，返回创建的表名
+    ///
+    /// 默认导入第一个工作表；`config.options.sheet` 指定工作表，
+    /// `config.options.cell_range` 指定区域（区域的第一行作为表头）。
+    /// `config.options.all_sheets` 时每个工作表导入为 `<表名>_<工作表名>`。
+    pub fn import_excel(
         &self,
         path: &Path,
         config: ImportConfig,
         progress: Option<ProgressCallback>,
-    ) -> Result<()> {
-        info!("Importing Parquet from: {:?}", path);
+    ) -> Result<Vec<String>> {
+        info!("Importing Excel workbook: {:?}", path);
 
-        let file_size = std::fs::metadata(path)?.len();
-        let table_name = &config.table_name;
+        let total_size = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read file metadata: {:?}", path))?
+            .len();
+        let mut tracker = ProgressTracker::new(progress.as_deref(), total_size);
 
-        // 使用 DuckDB 的 SQL 接口导入 Parquet
-        let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
+        let sheets = excel::read_sheets(path, &config.options)?;
+        let total_rows: usize = sheets.iter().map(|(_, table)| table.row_count()).sum();
 
-        // 删除现有表（如果需要）
-        if config.overwrite {
-            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", table_name), []);
-        }
+        let mut conn = self.conn.lock().unwrap();
+        // 所有工作表在同一事务中导入，失败时不留下部分数据
+        let tx = conn.transaction().context("Failed to start transaction")?;
 
-        // 使用 DuckDB 的 read_parquet 函数
-        let sql = format!(
-            "CREATE TABLE {} AS SELECT * FROM read_parquet('{}')",
-            table_name, path_str
-        );
+        let mut tables = Vec::new();
+        let mut rows_done = 0;
+        let mut reported = 0;
+        for (sheet, table) in &sheets {
+            let table_name = if config.options.all_sheets {
+                format!("{}_{}", config.table_name, excel::sanitize_name(sheet))
+            } else {
+                config.table_name.clone()
+            };
 
-        conn.execute(&sql, []).context("Failed to import Parquet")?;
+            if config.overwrite {
+                tx.execute(&format!("DROP TABLE IF EXISTS {}", quote_table_name(&table_name)), [])?;
+            }
+            tx.execute(&table.create_sql(&table_name), [])
+                .with_context(|| format!("Failed to create table for sheet: {}", sheet))?;
 
-        // 报告进度
-        if let Some(cb) = progress {
-            cb(file_size, file_size);
+            // 日期和时间戳以文本追加，由 Appender 转换为列类型
+            let appender = match table_name.split_once('.') {
+                Some((schema, name)) => tx.appender_to_db(name, schema),
+                None => tx.appender(&table_name),
+            };
+            let mut appender = appender
+                .with_context(|| format!("Failed to create appender for sheet: {}", sheet))?;
+            for row in 0..table.row_count() {
+                appender
+                    .append_row(duckdb::appender_params_from_iter(table.row_values(row)))
+                    .with_context(|| format!("Failed to insert row {} of sheet: {}", row + 2, sheet))?;
+
+                // 按已插入行数折算进度
+                rows_done += 1;
+                let processed = total_size * rows_done as u64 / total_rows as u64;
+                tracker.advance(processed - reported);
+                reported = processed;
+            }
+            appender
+                .flush()
+                .with_context(|| format!("Failed to import sheet: {}", sheet))?;
+
+            info!("Imported sheet '{}' into {} ({} rows)", sheet, table_name, table.row_count());
+            tables.push(table_name);
         }
 
-        info!("Parquet import completed");
-        Ok(())
+        tx.commit().context("Failed to commit Excel import")?;
+        tracker.finish();
+
+        info!("Excel import completed");
+        Ok(tables)
     }
 
-    /// 导入 JSON 文件
-    pub fn import_json(
+    /// 列出 Excel 工作簿中的工作表
+    pub fn list_sheets(&self, path: &Path) -> Result<Vec<String>> {
+        excel::list_sheets(path)
+    }
+
+    /// 列出 SQLite 数据库中的表及其行数和导入后的列类型
+    pub fn list_sqlite_tables(&self, path: &Path) -> Result<Vec<SqliteTable>> {
+        let source = sqlite::open(path)?;
+        sqlite::list_tables(&source)
+    }
+
+    /// 把 SQLite 数据库中的表导入到工作区
+    ///
+    /// `tables` 为空时导入所有表，表名与 SQLite 中的表名相同。
+    /// 列类型由声明类型和实际存储的值共同决定（见 [`crate::sqlite`]）。
+    /// 进度按已复制的行数报告，所有表在同一事务中导入；`cancel` 被置位时
+    /// 在两张表之间或每批行之后中止，回滚已导入的表。
+    pub fn import_sqlite(
         &self,
         path: &Path,
-        config: ImportConfig,
+        tables: &[String],
+        overwrite: bool,
+        cancel: Option<&AtomicBool>,
         progress: Option<ProgressCallback>,
-    ) -> Result<()> {
-        info!("Importing JSON from: {:?}", path);
+    ) -> Result<Vec<SqliteTable>> {
+        info!("Importing SQLite database: {:?}", path);
 
-        let file_size = std::fs::metadata(path)?.len();
-        let table_name = &config.table_name;
+        let source = sqlite::open(path)?;
+        let available = sqlite::list_tables(&source)?;
+        let selected = if tables.is_empty() {
+            available
+        } else {
+            tables
+                .iter()
+                .map(|name| {
+                    available
+                        .iter()
+                        .find(|t| &t.name == name)
+                        .cloned()
+                        .with_context(|| format!("Table not found in SQLite database: {}", name))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
 
-        // 使用 DuckDB 的 SQL 接口导入 JSON
-        let conn = self.conn.lock().unwrap();
-        let path_str = path.to_string_lossy();
+        let total_rows = selected.iter().map(|t| t.row_count).sum();
+        let mut tracker = ProgressTracker::new(progress.as_deref(), total_rows);
 
-        // 删除现有表（如果需要）
-        if config.overwrite {
-            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", table_name), []);
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        for table in &selected {
+            if cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+                bail!("SQLite import cancelled");
+            }
+            if overwrite {
+                tx.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&table.name)), [])?;
+            }
+            let rows = sqlite::copy_table(&source, table, &tx, &table.name, cancel, &mut || tracker.advance(1))?;
+            info!("Imported SQLite table '{}' ({} rows)", table.name, rows);
         }
 
-        // 使用 DuckDB 的 read_json 函数
-        // JSON 文件可以是数组或对象行格式
+        tx.commit().context("Failed to commit SQLite import")?;
+        tracker.finish();
+
+        info!("SQLite import completed");
+        Ok(selected)
+    }
+
+    /// 以只读方式附加 SQLite 数据库，之后可以用 `<alias>.<表名>` 直接查询
+    ///
+    /// 依赖 DuckDB 的 sqlite 扩展，本地没有时会尝试安装。返回数据库中的表。
+    pub fn attach_sqlite(&self, path: &Path, alias: &str) -> Result<Vec<SqliteTable>> {
+        info!("Attaching SQLite database {:?} as {}", path, alias);
+
+        let tables = self.list_sqlite_tables(path)?;
+
+        let conn = self.conn.lock().unwrap();
+        if conn.execute_batch("LOAD sqlite").is_err() {
+            conn.execute_batch("INSTALL sqlite; LOAD sqlite")
+                .context("Failed to load the DuckDB sqlite extension")?;
+        }
+        conn.execute(
+            &format!(
+                "ATTACH {} AS {} (TYPE SQLITE, READ_ONLY)",
+                quote_path(path),
+                quote_ident(alias)
+            ),
+            [],
+        )
+        .with_context(|| format!("Failed to attach SQLite database: {:?}", path))?;
+
+        Ok(tables)
+    }
+
+    /// 按连接配置以只读方式附加 PostgreSQL/MySQL 数据库（已附加时不做任何事）
+    ///
+    /// 之后可以用 `<配置名>.<schema>.<表名>` 直接查询远程表。
+    pub fn attach_database(&self, profile: &ConnectionProfile) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        database::attach(&conn, profile)
+    }
+
+    /// 列出 PostgreSQL/MySQL 数据库中的 schema
+    pub fn list_database_schemas(&self, profile: &ConnectionProfile) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        database::attach(&conn, profile)?;
+        database::list_schemas(&conn, profile)
+    }
+
+    /// 列出 PostgreSQL/MySQL 数据库中的表和视图
+    pub fn list_database_tables(
+        &self,
+        profile: &ConnectionProfile,
+        schema: Option<&str>,
+    ) -> Result<Vec<RemoteTable>> {
+        let conn = self.conn.lock().unwrap();
+        database::attach(&conn, profile)?;
+        database::list_tables(&conn, profile, schema)
+    }
+
+    /// 在 PostgreSQL/MySQL 服务器上执行查询，把结果导入到 `config.table_name`
+    ///
+    /// 查询整体下推到远程服务器执行，结果由 `CREATE TABLE AS` 直接写入本地表，不在内存中缓存。
+    /// 进度按 DuckDB 的查询进度（0-100）报告，远程扫描无法估计进度时只在完成时报告。
+    /// `cancel` 被置位时中断查询。返回导入的行数。
+    pub fn import_database_query(
+        &self,
+        profile: &ConnectionProfile,
+        sql: &str,
+        config: ImportConfig,
+        cancel: Option<&AtomicBool>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<u64> {
+        info!("Importing {:?} query from {} into: {}", profile.kind, profile.name, config.table_name);
+
+        let conn = self.conn.lock().unwrap();
+        // 附加的数据库在同一数据库实例的所有连接中可见
+        database::attach(&conn, profile)?;
+
+        // 覆盖时用 CREATE OR REPLACE，查询失败时保留原表
         let sql = format!(
-            "CREATE TABLE {} AS SELECT * FROM read_json_auto('{}')",
-            table_name, path_str
+            "CREATE {}TABLE {} AS SELECT * FROM {}",
+            if config.overwrite { "OR REPLACE " } else { "" },
+            quote_table_name(&config.table_name),
+            database::pushdown_sql(profile, sql)
         );
+        let mut tracker = ProgressTracker::new(progress.as_deref(), 100);
+        let database = match &self.database {
+            Some(database) if !needs_session(&conn, &sql)? => Some(database),
+            _ => None,
+        };
+        let rows = match database {
+            Some(database) => {
+                database.execute_with_progress(&sql, cancel, &mut |fraction| tracker.update((fraction * 100.0) as u64))
+            }
+            None => conn.execute(&sql, []).map(|rows| rows as u64).map_err(Into::into),
+        };
+        if cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+            bail!("Database import cancelled");
+        }
+        let rows = rows.with_context(|| format!("Failed to import query from {}", profile.name))?;
+        tracker.finish();
 
-        conn.execute(&sql, []).context("Failed to import JSON")?;
+        info!("Database import completed ({} rows)", rows);
+        Ok(rows)
+    }
 
-        // 报告进度
-        if let Some(cb) = progress {
-            cb(file_size, file_size);
+    /// 列出 zip 压缩包中的成员文件（不包含目录）
+    pub fn list_archive_members(&self, path: &Path) -> Result<Vec<ArchiveMember>> {
+        let file = File::open(path).with_context(|| format!("Failed to open archive: {:?}", path))?;
+        let mut archive = zip::ZipArchive::new(file).context("Failed to read zip archive")?;
+
+        let mut members = Vec::new();
+        for index in 0..archive.len() {
+            let entry = archive.by_index(index)?;
+            if entry.is_dir() {
+                continue;
+            }
+            members.push(ArchiveMember {
+                name: entry.name().to_string(),
+                size: entry.size(),
+                compressed_size: entry.compressed_size(),
+                fmt: FileFmt::from_path(entry.name()),
+            });
         }
+        Ok(members)
+    }
+}
+
+/// 准备好交给 DuckDB 读取的文件
+#[derive(Default)]
+struct StagedFiles {
+    /// 交给 DuckDB 读取的文件
+    files: Vec<PathBuf>,
+    /// 由 DuckDB 直接解压读取的压缩格式，这种格式的文件不解压到临时目录
+    compression: Option<Compression>,
+    /// 解压得到的临时文件 → 原始来源（如 `bundle.zip/data.csv`）
+    origins: Vec<(PathBuf, String)>,
+    /// 解压用的临时目录，导入结束后自动删除
+    temp_dir: Option<TempDir>,
+    /// 已分配的临时文件数
+    temp_count: usize,
+}
+
+impl StagedFiles {
+    /// 在临时目录中为解压文件分配路径，保留原文件名以便 DuckDB 识别扩展名
+    fn temp_path(&mut self, file_name: &str) -> Result<PathBuf> {
+        if self.temp_dir.is_none() {
+            self.temp_dir = Some(TempDir::new().context("Failed to create temp directory")?);
+        }
+        // 每个文件单独一个子目录，避免不同成员同名冲突
+        let dir = self
+            .temp_dir
+            .as_ref()
+            .unwrap()
+            .path()
+            .join(self.temp_count.to_string());
+        self.temp_count += 1;
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir.join(file_name))
+    }
+}
+
+impl StagedFiles {
+    /// 把所有待读取的 CSV 转码为 UTF-8 临时文件
+    fn decode_csv(&mut self, encoding: TextEncoding) -> Result<()> {
+        let files = std::mem::take(&mut self.files);
+        for file in files {
+            let file_name = file
+                .file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "data.csv".to_string());
+            let target = self.temp_path(&file_name)?;
+            csv::decode_to_utf8(&file, &target, encoding)?;
 
-        info!("JSON import completed");
+            // filename 列指向原始来源
+            let origin = match self.origins.iter().position(|(staged, _)| staged == &file) {
+                Some(index) => self.origins.remove(index).1,
+                None => file.to_string_lossy().to_string(),
+            };
+            self.origins.push((target.clone(), origin));
+            self.files.push(target);
+        }
         Ok(())
     }
 }
 
+/// 导入 CSV 时指定的文本编码（其他格式或未指定方言时为 UTF-8）
+fn csv_encoding(fmt: FileFmt, options: &ImportOptions) -> TextEncoding {
+    match &options.csv {
+        Some(dialect) if fmt == FileFmt::Csv => dialect.encoding,
+        _ => TextEncoding::Utf8,
+    }
+}
+
+/// DuckDB 可以直接读取的压缩格式
+///
+/// DuckDB 能直接读取 gzip/zstd 压缩的 CSV 和 JSON（需要转码的 CSV 除外），
+/// 但一次读取只能指定一种压缩格式，因此只有所有文件的压缩格式相同时才直接读取。
+fn native_compression(paths: &[PathBuf], fmt: FileFmt, options: &ImportOptions) -> Option<Compression> {
+    if !matches!(fmt, FileFmt::Csv | FileFmt::Json) || csv_encoding(fmt, options) != TextEncoding::Utf8 {
+        return None;
+    }
+    let first = Compression::from_path(&paths.first()?.to_string_lossy());
+    let same = paths
+        .iter()
+        .all(|path| Compression::from_path(&path.to_string_lossy()) == first);
+    match first {
+        Some(Compression::Gzip | Compression::Zstd) if same => first,
+        _ => None,
+    }
+}
+
+/// 按需解压单个文件，并把结果加入 `staged`
+fn stage_file(
+    path: &Path,
+    origin: &str,
+    fmt: FileFmt,
+    options: &ImportOptions,
+    staged: &mut StagedFiles,
+    tracker: &mut ProgressTracker,
+) -> Result<()> {
+    let compression = Compression::from_path(&path.to_string_lossy());
+
+    match compression {
+        None => {
+            staged.files.push(path.to_path_buf());
+        }
+        // 由 DuckDB 直接解压读取（见 native_compression）
+        Some(compression) if staged.compression == Some(compression) => {
+            staged.files.push(path.to_path_buf());
+        }
+        Some(compression @ (Compression::Gzip | Compression::Zstd | Compression::Bzip2)) => {
+            let file_name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("data.{}", fmt.extension()));
+            let target = staged.temp_path(&file_name)?;
+
+            let read = Rc::new(Cell::new(0));
+            let input = CountingReader {
+                inner: File::open(path).with_context(|| format!("Failed to open file: {:?}", path))?,
+                count: Rc::clone(&read),
+            };
+            let mut decoder: Box<dyn Read> = match compression {
+                Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(input)),
+                Compression::Zstd => Box::new(zstd::Decoder::new(input)?),
+                _ => Box::new(bzip2::read::MultiBzDecoder::new(input)),
+            };
+
+            let mut output = File::create(&target)?;
+            let mut buf = vec![0u8; 64 * 1024];
+            let mut reported = 0;
+            loop {
+                let n = decoder
+                    .read(&mut buf)
+                    .with_context(|| format!("Failed to decompress file: {:?}", path))?;
+                if n == 0 {
+                    break;
+                }
+                output.write_all(&buf[..n])?;
+                tracker.advance(read.get() - reported);
+                reported = read.get();
+            }
+
+            staged.origins.push((target.clone(), origin.to_string()));
+            staged.files.push(target);
+        }
+        Some(Compression::Zip) => {
+            let file = File::open(path).with_context(|| format!("Failed to open archive: {:?}", path))?;
+            let mut archive = zip::ZipArchive::new(file).context("Failed to read zip archive")?;
+
+            // 选出要导入的成员
+            let mut indices = Vec::new();
+            if options.archive_members.is_empty() {
+                for index in 0..archive.len() {
+                    let entry = archive.by_index(index)?;
+                    let hidden = Path::new(entry.name())
+                        .components()
+                        .any(|c| is_hidden(&c.as_os_str().to_string_lossy()));
+                    if !entry.is_dir() && !hidden && FileFmt::from_path(entry.name()) == Some(fmt) {
+                        indices.push(index);
+                    }
+                }
+                if indices.is_empty() {
+                    bail!("No {} files found in archive: {:?}", fmt_label(fmt), path);
+                }
+            } else {
+                for name in &options.archive_members {
+                    match archive.index_for_name(name) {
+                        Some(index) => indices.push(index),
+                        None => bail!("Archive member not found: {} (in {:?})", name, path),
+                    }
+                }
+            }
+
+            for index in indices {
+                let mut entry = archive.by_index(index)?;
+                let name = entry.name().to_string();
+                let file_name = Path::new(&name)
+                    .file_name()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| format!("member.{}", fmt.extension()));
+                let target = staged.temp_path(&file_name)?;
+
+                // 按压缩比把解压字节数折算为压缩字节数
+                let ratio = if entry.size() > 0 {
+                    entry.compressed_size() as f64 / entry.size() as f64
+                } else {
+                    0.0
+                };
+                let mut output = File::create(&target)?;
+                let mut buf = vec![0u8; 64 * 1024];
+                loop {
+                    let n = entry
+                        .read(&mut buf)
+                        .with_context(|| format!("Failed to extract archive member: {}", name))?;
+                    if n == 0 {
+                        break;
+                    }
+                    output.write_all(&buf[..n])?;
+                    tracker.advance((n as f64 * ratio) as u64);
+                }
+                drop(output);
+
+                // 成员本身也可能是压缩文件（如 data.csv.gz）
+                let member_origin = format!("{}/{}", origin, name);
+                let before = staged.files.len();
+                stage_file(&target, &member_origin, fmt, options, staged, &mut ProgressTracker::new(None, 0))?;
+                if staged.files.len() > before && staged.files.last() == Some(&target) {
+                    staged.origins.push((target, member_origin));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Arrow RecordBatch 迭代器
+type ArrowBatches<'a> = Box<dyn Iterator<Item = std::result::Result<RecordBatch, arrow::error::ArrowError>> + 'a>;
+
+/// 按文件头打开 Arrow IPC 文件（以 `ARROW1` 开头）或流
+fn read_arrow<'a, R: Read + Seek + 'a>(mut reader: R, path: &Path) -> Result<(SchemaRef, ArrowBatches<'a>)> {
+    let mut magic = [0u8; 6];
+    let is_file = reader.read_exact(&mut magic).is_ok() && &magic == b"ARROW1";
+    reader.seek(SeekFrom::Start(0))?;
+
+    if is_file {
+        let reader = FileReader::try_new(reader, None)
+            .with_context(|| format!("Failed to read Arrow IPC file: {:?}", path))?;
+        Ok((reader.schema(), Box::new(reader)))
+    } else {
+        let reader = StreamReader::try_new(reader, None)
+            .with_context(|| format!("Failed to read Arrow IPC stream: {:?}", path))?;
+        Ok((reader.schema(), Box::new(reader)))
+    }
+}
+
+/// 注册 `arrow` 表函数（每个连接只需一次）
+fn register_arrow_function(conn: &duckdb::Connection) -> Result<()> {
+    let registered: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM duckdb_functions() WHERE function_name = 'arrow'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !registered {
+        conn.register_table_function::<ArrowVTab>("arrow")
+            .context("Failed to register arrow table function")?;
+    }
+    Ok(())
+}
+
+/// 把一个 RecordBatch 写入表，`append` 为 false 时按其 schema 建表
+fn insert_arrow_batch(conn: &duckdb::Connection, table_name: &str, batch: RecordBatch, append: bool) -> Result<()> {
+    let sql = if append {
+        format!("INSERT INTO {} SELECT * FROM arrow(?, ?)", table_name)
+    } else {
+        format!("CREATE TABLE {} AS SELECT * FROM arrow(?, ?)", table_name)
+    };
+    conn.execute(&sql, arrow_recordbatch_to_query_params(decode_dictionaries(batch)?))
+        .context("Failed to import Arrow batch")?;
+    Ok(())
+}
+
+/// 把顶层字典编码列解码为普通列（`arrow` 表函数不支持字典类型），之后再转换为 ENUM
+fn decode_dictionaries(batch: RecordBatch) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    if !schema.fields().iter().any(|f| matches!(f.data_type(), DataType::Dictionary(..))) {
+        return Ok(batch);
+    }
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        match field.data_type() {
+            DataType::Dictionary(_, value_type) => {
+                fields.push(field.as_ref().clone().with_data_type(value_type.as_ref().clone()));
+                columns.push(arrow::compute::cast(column, value_type)?);
+            }
+            _ => {
+                fields.push(field.as_ref().clone());
+                columns.push(column.clone());
+            }
+        }
+    }
+    Ok(RecordBatch::try_new(
+        std::sync::Arc::new(arrow::datatypes::Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )?)
+}
+
+/// 顶层字典编码字符串列的取值（按首次出现的顺序）
+#[derive(Default)]
+struct DictionaryColumns {
+    columns: Vec<(String, Vec<String>)>,
+    seen: Vec<HashSet<String>>,
+}
+
+impl DictionaryColumns {
+    fn collect(&mut self, batch: &RecordBatch) -> Result<()> {
+        for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+            let DataType::Dictionary(_, value_type) = field.data_type() else {
+                continue;
+            };
+            if !matches!(value_type.as_ref(), DataType::Utf8 | DataType::LargeUtf8) {
+                continue;
+            }
+
+            let idx = match self.columns.iter().position(|(name, _)| name == field.name()) {
+                Some(idx) => idx,
+                None => {
+                    self.columns.push((field.name().clone(), Vec::new()));
+                    self.seen.push(HashSet::new());
+                    self.columns.len() - 1
+                }
+            };
+            let values = arrow::compute::cast(column.as_any_dictionary().values(), &DataType::Utf8)?;
+            for value in values.as_string::<i32>().iter().flatten() {
+                if self.seen[idx].insert(value.to_string()) {
+                    self.columns[idx].1.push(value.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 统计读取位置的 Reader（也用于统计已读取的字节数）
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.count.set(position);
+        Ok(position)
+    }
+}
+
+/// 导入进度跟踪
+///
+/// 只在百分比变化且距上次回调超过 [`PROGRESS_INTERVAL`] 时回调，避免事件过多；
+/// `finish` 总是回调。
+struct ProgressTracker<'a> {
+    callback: Option<&'a (dyn Fn(u64, u64) + Send + Sync)>,
+    total: u64,
+    processed: u64,
+    last_pct: u64,
+    last_report: Option<Instant>,
+}
+
+impl<'a> ProgressTracker<'a> {
+    fn new(callback: Option<&'a (dyn Fn(u64, u64) + Send + Sync)>, total: u64) -> Self {
+        Self {
+            callback,
+            total,
+            processed: 0,
+            last_pct: 0,
+            last_report: None,
+        }
+    }
+
+    fn processed(&self) -> u64 {
+        self.processed
+    }
+
+    fn advance(&mut self, bytes: u64) {
+        self.update(self.processed + bytes);
+    }
+
+    /// 设置已处理的总量（不会倒退）
+    fn update(&mut self, processed: u64) {
+        self.update_at(processed, Instant::now());
+    }
+
+    /// 在 `now` 时刻设置已处理的总量；百分比增加且距上次回调超过 `PROGRESS_INTERVAL` 时才回调
+    fn update_at(&mut self, processed: u64, now: Instant) {
+        self.processed = processed.clamp(self.processed, self.total);
+        if self.total == 0 {
+            return;
+        }
+        let pct = self.processed * 100 / self.total;
+        if pct <= self.last_pct {
+            return;
+        }
+        if self.last_report.is_some_and(|last| now.duration_since(last) < PROGRESS_INTERVAL) {
+            return;
+        }
+        self.last_pct = pct;
+        self.last_report = Some(now);
+        if let Some(cb) = self.callback {
+            cb(self.processed, self.total);
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(cb) = self.callback {
+            cb(self.total, self.total);
+        }
+    }
+}
+
+/// 构造 DuckDB 读取函数调用，如 `read_csv_auto(['a.csv', 'b.csv'], header = true)`
+fn reader_sql(
+    paths: &[PathBuf],
+    fmt: FileFmt,
+    options: &ImportOptions,
+    compression: Option<Compression>,
+) -> Result<String> {
+    let files = paths
+        .iter()
+        .map(|p| quote_path(p))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut args = Vec::new();
+    if fmt == FileFmt::Csv {
+        match &options.csv {
+            Some(dialect) => args.extend(csv::reader_args(dialect)?),
+            // 指定 header=true 以识别列名
+            None => args.push("header = true".to_string()),
+        }
+    }
+    match compression {
+        Some(Compression::Gzip) => args.push("compression = 'gzip'".to_string()),
+        Some(Compression::Zstd) => args.push("compression = 'zstd'".to_string()),
+        _ => {}
+    }
+    if options.filename_column {
+        args.push("filename = true".to_string());
+    }
+    // 显式指定，避免 DuckDB 对目录结构自动推断出额外的分区列
+    args.push(format!("hive_partitioning = {}", options.hive_partitioning));
+    if options.union_by_name {
+        args.push("union_by_name = true".to_string());
+    }
+    if options.rejects.is_some() {
+        // 记录错误行时 DuckDB 自动跳过它们
+        args.push("store_rejects = true".to_string());
+        args.push(format!("rejects_table = {}", quote_literal(REJECTS_TABLE)));
+        args.push(format!("rejects_scan = {}", quote_literal(REJECTS_SCAN)));
+    }
+
+    let func = match fmt {
+        FileFmt::Csv => "read_csv_auto",
+        FileFmt::Parquet => "read_parquet",
+        // JSON 文件可以是数组或对象行格式
+        FileFmt::Json => "read_json_auto",
+        FileFmt::Excel => unreachable!("Excel workbooks are read by the excel module"),
+        FileFmt::Arrow => unreachable!("Arrow IPC files are imported batch by batch"),
+    };
+
+    Ok(format!("{}([{}], {})", func, files, args.join(", ")))
+}
+
+/// 把 DuckDB 记录的错误行写入 `<表名>_rejects` 表或 CSV 文件，返回被拒绝的行数
+///
+/// 一行可能有多个错误（每个错误一条记录），被拒绝的行数按行号去重。
+/// `origins` 把解压或转码得到的临时文件还原为原始来源。
+fn write_rejects(
+    conn: &duckdb::Connection,
+    table_name: &str,
+    output: &RejectsOutput,
+    origins: &[(PathBuf, String)],
+) -> Result<u64> {
+    let errors = format!("temp.main.{}", quote_ident(REJECTS_TABLE));
+    let scans = format!("temp.main.{}", quote_ident(REJECTS_SCAN));
+
+    let file = if origins.is_empty() {
+        "s.file_path".to_string()
+    } else {
+        let cases = origins
+            .iter()
+            .map(|(staged, origin)| format!("WHEN {} THEN {}", quote_path(staged), quote_literal(origin)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("CASE s.file_path {} ELSE s.file_path END", cases)
+    };
+    let select = format!(
+        "SELECT {file} AS file, CAST(e.line AS BIGINT) AS line, e.column_name, \
+         CAST(e.error_type AS VARCHAR) AS error_type, e.error_message AS reason, e.csv_line AS content \
+         FROM {errors} e JOIN {scans} s ON e.scan_id = s.scan_id AND e.file_id = s.file_id \
+         ORDER BY file, e.line"
+    );
+
+    match output {
+        RejectsOutput::Table => {
+            let rejects_table = quote_table_name(&format!("{}_rejects", table_name));
+            conn.execute(&format!("CREATE OR REPLACE TABLE {} AS {}", rejects_table, select), [])
+                .context("Failed to create rejects table")?;
+        }
+        RejectsOutput::File { path } => {
+            conn.execute(
+                &format!("COPY ({}) TO {} (FORMAT CSV, HEADER true)", select, quote_literal(path)),
+                [],
+            )
+            .with_context(|| format!("Failed to write rejects file: {}", path))?;
+        }
+    }
+
+    let rejected: i64 = conn.query_row(
+        &format!("SELECT COUNT(DISTINCT (scan_id, file_id, line)) FROM {}", errors),
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(rejected as u64)
+}
+
+/// 删除 DuckDB 记录错误行的临时表
+fn drop_reject_tables(conn: &duckdb::Connection) -> Result<()> {
+    for table in [REJECTS_TABLE, REJECTS_SCAN] {
+        conn.execute(&format!("DROP TABLE IF EXISTS temp.main.{}", quote_ident(table)), [])?;
+    }
+    Ok(())
+}
+
+fn fmt_label(fmt: FileFmt) -> &'static str {
+    match fmt {
+        FileFmt::Csv => "CSV",
+        FileFmt::Parquet => "Parquet",
+        FileFmt::Json => "JSON",
+        FileFmt::Excel => "Excel",
+        FileFmt::Arrow => "Arrow IPC",
+    }
+}
+
+/// 判断路径中是否包含 glob 通配符
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// 将导入路径展开为具体的文件列表
+///
+/// - glob 模式：按字典序返回所有匹配的文件
+/// - 目录：递归收集扩展名与 `fmt` 匹配的文件（包括压缩后的 `data.csv.gz` 等，
+///   忽略以 `.` 或 `_` 开头的文件，如 `_SUCCESS`）
+/// - 普通路径：原样返回
+pub fn resolve_paths(path: &str, fmt: FileFmt) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    if is_glob(path) {
+        for entry in glob::glob(path).with_context(|| format!("Invalid glob pattern: {}", path))? {
+            let entry = entry?;
+            if entry.is_file() {
+                files.push(entry);
+            }
+        }
+        if files.is_empty() {
+            bail!("No files match pattern: {}", path);
+        }
+    } else {
+        let path = Path::new(path);
+        if path.is_dir() {
+            collect_dir(path, fmt, &mut files)?;
+            if files.is_empty() {
+                bail!("No {} files found in directory: {:?}", fmt_label(fmt), path);
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// 以 `.` 或 `_` 开头的文件（如 `_SUCCESS`、`.DS_Store`、`__MACOSX`）不参与导入
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.') || name.starts_with('_')
+}
+
+fn collect_dir(dir: &Path, fmt: FileFmt, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read directory: {:?}", dir))? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .map(|n| is_hidden(&n.to_string_lossy()))
+            .unwrap_or(false);
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_dir(&path, fmt, files)?;
+        } else if FileFmt::from_path(&path.to_string_lossy()) == Some(fmt) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 根据导入路径推断默认表名
+///
+/// 取路径中最后一个有意义的部分：`data/users.csv` → `users`，
+/// `sales_2024_*.csv` → `sales_2024`，`lake/events/**/*.parquet` → `events`，
+/// `data.csv.gz` → `data`。名称经 [`sanitize_table_name`] 处理：`2024-sales.csv` → `t_2024_sales`。
+pub fn default_table_name(path: &str) -> String {
+    for component in Path::new(path).components().rev() {
+        let name = component.as_os_str().to_string_lossy();
+        // 去掉通配符及其之后的部分
+        let name = match name.find(['*', '?', '[']) {
+            Some(idx) => &name[..idx],
+            None => &name[..],
+        };
+        // 去掉压缩后缀和扩展名
+        let name = match Compression::from_path(name) {
+            Some(_) => Path::new(name).file_stem().and_then(|s| s.to_str()).unwrap_or_default(),
+            None => name,
+        };
+        let stem = Path::new(name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if stem.contains(['/', '\\', ':']) {
+            continue;
+        }
+        if let Some(name) = sanitize_table_name(&stem) {
+            return name;
+        }
+    }
+    "imported_data".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[test]
+    fn test_progress_tracker_throttling() {
+        let reports = Mutex::new(Vec::new());
+        let callback = |processed, total| reports.lock().unwrap().push((processed, total));
+        let mut tracker = ProgressTracker::new(Some(&callback), 1000);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        tracker.update_at(100, at(0));
+        // 间隔不足 PROGRESS_INTERVAL，跳过
+        tracker.update_at(200, at(50));
+        tracker.update_at(300, at(500));
+        // 百分比没有增加，跳过
+        tracker.update_at(305, at(1000));
+        // 进度不会倒退
+        tracker.update_at(250, at(1500));
+        assert_eq!(tracker.processed(), 305);
+        tracker.update_at(2000, at(2000));
+        assert_eq!(tracker.processed(), 1000);
+        tracker.finish();
+
+        assert_eq!(*reports.lock().unwrap(), [(100, 1000), (300, 1000), (1000, 1000), (1000, 1000)]);
+    }
+
+    #[test]
+    fn test_progress_tracker_without_total() {
+        let reports = Mutex::new(Vec::new());
+        let callback = |processed, total| reports.lock().unwrap().push((processed, total));
+        let mut tracker = ProgressTracker::new(Some(&callback), 0);
+        tracker.advance(10);
+        assert_eq!(tracker.processed(), 0);
+        tracker.finish();
+        assert_eq!(*reports.lock().unwrap(), [(0, 0)]);
+    }
+
+    #[test]
+    fn test_native_compression() {
+        let paths = |names: &[&str]| names.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let options = ImportOptions::default();
+        assert_eq!(
+            native_compression(&paths(&["a.csv.gz", "b.csv.gzip"]), FileFmt::Csv, &options),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(native_compression(&paths(&["a.json.zst"]), FileFmt::Json, &options), Some(Compression::Zstd));
+        // 压缩格式不同、未压缩，或 DuckDB 不能直接读取
+        assert_eq!(native_compression(&paths(&["a.csv.gz", "b.csv"]), FileFmt::Csv, &options), None);
+        assert_eq!(native_compression(&paths(&["a.csv.bz2"]), FileFmt::Csv, &options), None);
+        assert_eq!(native_compression(&paths(&["a.parquet.gz"]), FileFmt::Parquet, &options), None);
+    }
+
+    #[test]
+    fn test_stage_file_reports_progress_per_chunk() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data.csv.gz");
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&path).unwrap(), flate2::Compression::fast());
+        // 伪随机数，压缩后仍有 1 MB 以上
+        let mut x: u64 = 1;
+        for _ in 0..200_000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            writeln!(encoder, "{}", x).unwrap();
+        }
+        encoder.finish().unwrap();
+        let total = std::fs::metadata(&path).unwrap().len();
+
+        let reports = Mutex::new(Vec::new());
+        let callback = |processed, total| reports.lock().unwrap().push((processed, total));
+        let mut tracker = ProgressTracker::new(Some(&callback), total);
+        let mut staged = StagedFiles::default();
+        stage_file(&path, "data.csv.gz", FileFmt::Csv, &ImportOptions::default(), &mut staged, &mut tracker).unwrap();
+
+        // 解压过程中就报告进度，解压完成时已读完全部压缩字节
+        assert_eq!(tracker.processed(), total);
+        let first = reports.lock().unwrap().first().copied();
+        assert!(first.is_some_and(|(processed, _)| processed < total), "{:?}", first);
+        assert_eq!(staged.origins[0].1, "data.csv.gz");
+    }
+}
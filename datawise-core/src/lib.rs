@@ -42,19 +42,56 @@
 //! }
 //! ```
 
+pub mod catalog;
 pub mod executor;
 pub mod protocol;
 pub mod importer;
 pub mod exporter;
-
-pub use protocol::{Command, CmdType, EventKind, FileFmt, UiEvent};
-pub use importer::{Importer, ImportConfig};
-pub use exporter::{Exporter, ExportConfig};
+pub mod excel;
+pub mod csv;
+pub mod database;
+pub mod progress;
+pub mod sqlite;
+pub mod sql;
+pub mod profile;
+pub mod quality;
+pub mod diff;
+pub mod history;
+pub mod persist;
+pub mod snippets;
+pub mod workbook;
+pub mod cache;
+
+pub use protocol::{
+    Binning, CacheAction, CacheStatus, CellKind, CellResult, Command, CmdType, Compression, ConnectionProfile, CsvDialect, DatabaseAction, DatabaseKind,
+    EventKind, ExportOptions, ExportSummary, FileFmt, HistoryAction, ImportOptions, LineEnding,
+    ParquetCompression, ParquetOptions, RejectsOutput, Rule, RuleResult, RulesAction, SheetExport, Snippet, SnippetAction,
+    SqliteAction, TableAction, TextEncoding, TimeBucket, UiEvent, ValidationReport, Workbook, WorkbookAction, WorkbookCell,
+};
+pub use importer::{ArchiveMember, Importer, ImportConfig, ImportOutcome};
+pub use exporter::{ExcelSheet, Exporter, ExportConfig, ExportError, ExportProgress};
+pub use sqlite::{SqliteColumn, SqliteTable};
+pub use database::RemoteTable;
+pub use profile::{
+    BinEdges, ColumnProfile, Histogram, HistogramSeries, LengthStats, Profiler, TableProfile, ValueCount,
+};
+pub use diff::{ColumnChangeCount, ColumnTypeChange, DiffTableNames, SchemaDiff, TableDiff, TableDiffer};
+pub use history::{HistoryEntry, QueryHistory};
+pub use snippets::{SnippetLibrary, Snippets};
+pub use cache::{CacheStats, ResultCache};
+pub use quality::{RuleSet, Validator};
+pub use catalog::{
+    Catalog, CatalogChange, ColumnInfo, DatabaseInfo, SchemaInfo, TableChange, TableInfo, TableSource,
+};
 
 use anyhow::Result;
+use cache::Lookup;
 use executor::Executor;
+use sql::quote_table_name;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::broadcast;
 use dashmap::DashMap;
 
@@ -68,10 +105,35 @@ pub struct DataWise {
     importer: Arc<Importer>,
     /// 文件导出器
     exporter: Arc<Exporter>,
+    /// 工作区目录
+    catalog: Arc<Catalog>,
+    /// 列画像计算器
+    profiler: Arc<Profiler>,
+    /// 数据质量规则
+    validator: Arc<Validator>,
+    /// 表对比器
+    differ: Arc<TableDiffer>,
+    /// 查询历史
+    history: Arc<QueryHistory>,
+    /// 查询片段库
+    snippets: Arc<Snippets>,
+    /// 查询结果缓存
+    cache: Arc<ResultCache>,
     /// 任务取消标记映射（task_id -> cancel_flag）
     task_cancels: Arc<DashMap<u64, Arc<AtomicBool>>>,
 }
 
+/// SQL 执行结果摘要
+#[derive(Clone)]
+struct QueryResult {
+    row_count: usize,
+    column_count: usize,
+    /// 前 10 行的 JSON
+    preview: String,
+    /// 是否来自缓存（不缓存时为 `None`）
+    cache: Option<CacheStatus>,
+}
+
 impl DataWise {
     /// 创建新的 DataWise 实例
     ///
@@ -79,8 +141,15 @@ impl DataWise {
     pub fn new() -> Result<Self> {
         let (tx, _) = broadcast::channel(100);
         let executor = Arc::new(Executor::new()?);
-        let importer = Arc::new(Importer::new(executor.conn_arc()));
-        let exporter = Arc::new(Exporter::new(executor.conn_arc()));
+        let importer = Arc::new(Importer::new(executor.conn_arc()).with_database(executor.database()));
+        let exporter = Arc::new(Exporter::new(executor.conn_arc()).with_database(executor.database()));
+        let catalog = Arc::new(Catalog::new(executor.conn_arc()));
+        let profiler = Arc::new(Profiler::new(executor.conn_arc()));
+        let validator = Arc::new(Validator::new(executor.conn_arc(), Arc::clone(&catalog)));
+        let differ = Arc::new(TableDiffer::new(executor.conn_arc()));
+        let history = Arc::new(QueryHistory::new());
+        let snippets = Arc::new(Snippets::new());
+        let cache = Arc::new(ResultCache::new(executor.conn_arc()));
         let task_cancels = Arc::new(DashMap::new());
 
         tracing::info!("DataWise initialized with DuckDB executor");
@@ -90,6 +159,13 @@ impl DataWise {
             executor,
             importer,
             exporter,
+            catalog,
+            profiler,
+            validator,
+            differ,
+            history,
+            snippets,
+            cache,
             task_cancels,
         })
     }
@@ -117,15 +193,73 @@ impl DataWise {
         let result = match cmd.cmd_type {
             CmdType::ExecuteSql { sql } => {
                 tracing::info!("Executing SQL: {}", sql);
-                self.execute_sql(cmd.task_id, &sql).await
+                self.execute_sql(cmd.task_id, &sql, &BTreeMap::new()).await
             }
-            CmdType::ImportFile { path, fmt, table_name, overwrite } => {
+            CmdType::ImportFile { path, fmt, table_name, overwrite, options } => {
                 tracing::info!("Importing file: {} ({:?}), overwrite: {}", path, fmt, overwrite);
-                self.import_file(cmd.task_id, &path, fmt, table_name, overwrite).await
+                self.import_file(cmd.task_id, &path, fmt, table_name, overwrite, options).await
+            }
+            CmdType::ExportFile { source, path, fmt, overwrite, options } => {
+                tracing::info!("Exporting to: {} ({:?}), overwrite: {}", path, fmt, overwrite);
+                self.export_file(cmd.task_id, &source, &path, fmt, overwrite, options).await
+            }
+            CmdType::ExportWorkbook { path, sheets, split_large_sheets, overwrite } => {
+                tracing::info!("Exporting {} sheet(s) to: {}, overwrite: {}", sheets.len(), path, overwrite);
+                self.export_workbook(cmd.task_id, &path, sheets, split_large_sheets, overwrite).await
+            }
+            CmdType::OpenSqlite { path, action } => {
+                tracing::info!("Opening SQLite database: {} ({:?})", path, action);
+                self.open_sqlite(cmd.task_id, &path, action).await
+            }
+            CmdType::Database { profile, action } => {
+                tracing::info!("Database command on {}: {:?}", profile.name, action);
+                self.database_command(cmd.task_id, &profile, action).await
+            }
+            cmd_type @ (CmdType::ListDatabases
+            | CmdType::ListSchemas { .. }
+            | CmdType::ListTables { .. }
+            | CmdType::DescribeTable { .. }) => {
+                self.browse_catalog(cmd.task_id, cmd_type).await
+            }
+            CmdType::ProfileTable { table, columns, sample } => {
+                tracing::info!("Profiling table: {} (columns: {:?}, sample: {:?})", table, columns, sample);
+                self.profile_table(cmd.task_id, &table, &columns, sample).await
+            }
+            CmdType::Histogram { table, column, binning, group_by } => {
+                tracing::info!("Computing histogram of {}.{} ({:?})", table, column, binning);
+                self.histogram(cmd.task_id, &table, &column, binning, group_by.as_deref()).await
+            }
+            CmdType::ManageTable { action } => {
+                tracing::info!("Managing table: {:?}", action);
+                self.manage_table(cmd.task_id, action).await
+            }
+            CmdType::DiffTables { left, right, key_columns } => {
+                tracing::info!("Comparing tables: {} -> {} (keys: {:?})", left, right, key_columns);
+                self.diff_tables(cmd.task_id, &left, &right, &key_columns).await
+            }
+            CmdType::Rules { action } => {
+                tracing::info!("Rules command: {:?}", action);
+                self.rules_command(cmd.task_id, action).await
+            }
+            CmdType::Validate { table, rules } => {
+                tracing::info!("Validating table: {} ({} rule(s))", table, rules.len());
+                self.validate(cmd.task_id, &table, rules).await
             }
-            CmdType::ExportFile { source, path, fmt } => {
-                tracing::info!("Exporting to: {} ({:?})", path, fmt);
-                self.export_file(cmd.task_id, &source, &path, fmt).await
+            CmdType::History { action } => {
+                tracing::info!("History command: {:?}", action);
+                self.history_command(cmd.task_id, action).await
+            }
+            CmdType::Snippets { action } => {
+                tracing::info!("Snippets command: {:?}", action);
+                self.snippets_command(cmd.task_id, action).await
+            }
+            CmdType::Workbook { path, action } => {
+                tracing::info!("Workbook command: {} {:?}", path, action);
+                self.workbook_command(cmd.task_id, &path, action).await
+            }
+            CmdType::Cache { action } => {
+                tracing::info!("Cache command: {:?}", action);
+                self.cache_command(cmd.task_id, action).await
             }
             CmdType::Cancel { task_id } => {
                 tracing::info!("Cancelling task: {}", task_id);
@@ -148,31 +282,102 @@ impl DataWise {
         result
     }
 
-    /// 执行 SQL 查询
-    async fn execute_sql(&self, task_id: u64, sql: &str) -> Result<()> {
-        // 直接执行 SQL（DuckDB 操作通常很快，不需要 spawn_blocking）
-        let batches = self.executor.execute(sql)?;
-
-        // 计算结果统计
-        let row_count = batches.iter().map(|b| b.num_rows()).sum();
-        let column_count = batches.first().map(|b| b.num_columns()).unwrap_or(0);
-
-        // 生成预览数据（前 10 行）
-        let preview = self.generate_preview(&batches)?;
+    /// 执行 SQL 查询，`params` 是代入 SQL 的参数值（作为结果缓存键的一部分）
+    async fn execute_sql(
+        &self,
+        task_id: u64,
+        sql: &str,
+        params: &BTreeMap<String, serde_json::Value>,
+    ) -> Result<()> {
+        let result = self.run_sql(task_id, sql, params).await?;
 
         // 发送完成事件
         let _ = self.tx.send(UiEvent {
             task_id,
             kind: EventKind::Finished {
-                row_count,
-                column_count,
-                preview,
+                row_count: result.row_count,
+                column_count: result.column_count,
+                preview: result.preview,
+                rejected_rows: None,
+                cache: result.cache,
             },
         });
 
         Ok(())
     }
 
+    /// 执行 SQL 查询并记录到查询历史，返回结果摘要
+    async fn run_sql(
+        &self,
+        task_id: u64,
+        sql: &str,
+        params: &BTreeMap<String, serde_json::Value>,
+    ) -> Result<QueryResult> {
+        let started = SystemTime::now();
+        let timer = Instant::now();
+        let result = self.query(task_id, sql, params).await;
+        self.history.record(sql, started, timer.elapsed(), result.as_ref().map(|r| r.row_count));
+        result
+    }
+
+    /// 执行 SQL 查询，返回结果摘要
+    ///
+    /// 开启结果缓存时，只读查询先查找缓存，未命中时执行后保存结果。
+    async fn query(
+        &self,
+        task_id: u64,
+        sql: &str,
+        params: &BTreeMap<String, serde_json::Value>,
+    ) -> Result<QueryResult> {
+        let lookup = self.cache.lookup(sql, params).unwrap_or_else(|e| {
+            tracing::warn!("Failed to look up result cache: {:#}", e);
+            Lookup::Bypass
+        });
+        if let Lookup::Hit(result) = lookup {
+            return Ok(result);
+        }
+
+        // 可能改变目录的语句（DDL、增删数据）执行前先记录目录快照
+        let before = if catalog::may_change_catalog(sql) {
+            Some(self.catalog.snapshot()?)
+        } else {
+            None
+        };
+
+        // 直接执行 SQL（DuckDB 操作通常很快，不需要 spawn_blocking）
+        let batches = self.executor.execute(sql);
+        // 修改数据的语句（包括目录检测不到的 UPDATE）使涉及的表的缓存结果失效
+        self.cache.statement_executed(sql);
+        let batches = batches?;
+
+        if let Some(before) = before {
+            for table in catalog::replaced_tables(sql) {
+                self.catalog.forget_source(&table)?;
+            }
+            let change = self.catalog.changes_since(&before, &catalog::modified_tables(sql))?;
+            self.send_catalog_changed(task_id, &change);
+        }
+
+        // 计算结果统计
+        let row_count = batches.iter().map(|b| b.num_rows()).sum();
+        let column_count = batches.first().map(|b| b.num_columns()).unwrap_or(0);
+
+        // 生成预览数据（前 10 行）
+        let preview = self.generate_preview(&batches)?;
+
+        let mut result = QueryResult {
+            row_count,
+            column_count,
+            preview,
+            cache: None,
+        };
+        if let Lookup::Miss(key) = lookup {
+            self.cache.insert(key, &result);
+            result.cache = Some(CacheStatus::Miss);
+        }
+        Ok(result)
+    }
+
     /// 导入文件
     async fn import_file(
         &self,
@@ -181,73 +386,51 @@ impl DataWise {
         fmt: protocol::FileFmt,
         table_name: Option<String>,
         overwrite: bool,
+        options: ImportOptions,
     ) -> Result<()> {
-        use std::path::Path;
+        let table_name = table_name.unwrap_or_else(|| importer::default_table_name(path));
+        let source = TableSource::File {
+            paths: std::iter::once(path.to_string()).chain(options.extra_paths.iter().cloned()).collect(),
+            fmt,
+        };
 
-        let file_path = Path::new(path);
-        let table_name = table_name.unwrap_or_else(|| {
-            file_path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("imported_data")
-                .to_string()
-        });
+        // 展开 glob 模式、目录和额外路径
+        let mut files = importer::resolve_paths(path, fmt)?;
+        for extra in &options.extra_paths {
+            files.extend(importer::resolve_paths(extra, fmt)?);
+        }
 
         // 创建取消标记
         let cancel_flag = Arc::new(AtomicBool::new(false));
         self.task_cancels.insert(task_id, Arc::clone(&cancel_flag));
 
         // 定义进度回调
-        let tx = self.tx.clone();
-        let progress_callback: Box<dyn Fn(u64, u64) + Send + Sync> = Box::new(move |processed, total| {
-            let pct = if total > 0 {
-                ((processed as f64 / total as f64) * 100.0) as u8
-            } else {
-                0
-            };
-            let _ = tx.send(UiEvent {
-                task_id,
-                kind: EventKind::Progress {
-                    pct,
-                    bytes_processed: processed,
-                    total_bytes: total,
-                    eta_seconds: None,
-                },
-            });
-        });
+        let progress_callback = self.progress_callback(task_id);
+
+        let before = self.catalog.snapshot()?;
 
         // 使用 Importer 执行导入
         let import_config = ImportConfig {
-            table_name: table_name.clone(),
+            table_name,
             overwrite,
+            options,
         };
 
-        match fmt {
-            protocol::FileFmt::Csv => {
-                self.importer.import_csv(
-                    file_path,
-                    import_config,
-                    Some(progress_callback),
-                )?;
-            }
-            protocol::FileFmt::Parquet => {
-                self.importer.import_parquet(
-                    file_path,
-                    import_config,
-                    Some(progress_callback),
-                )?;
-            }
-            protocol::FileFmt::Json => {
-                self.importer.import_json(
-                    file_path,
-                    import_config,
-                    Some(progress_callback),
-                )?;
-            }
+        let ImportOutcome { tables, rejected_rows } = self.importer.import_files(
+            &files,
+            fmt,
+            import_config,
+            Some(&cancel_flag),
+            Some(progress_callback),
+        )?;
+        self.send_catalog_changed(task_id, &self.catalog.changes_since(&before, &tables)?);
+        for table in &tables {
+            self.catalog.record_source(table, source.clone())?;
+            self.validate_imported(task_id, table);
         }
 
-        // 查询导入的表以获取行数、列数和预览数据
-        let preview_sql = format!("SELECT * FROM {} LIMIT 10", table_name);
+        // 查询导入的（第一张）表以获取行数、列数和预览数据
+        let preview_sql = format!("SELECT * FROM {} LIMIT 10", quote_table_name(&tables[0]));
         let preview_batches = self.executor.execute(&preview_sql)?;
         let preview = self.generate_preview(&preview_batches)?;
 
@@ -261,6 +444,8 @@ impl DataWise {
                 row_count,
                 column_count,
                 preview,
+                rejected_rows,
+                cache: None,
             },
         });
 
@@ -274,6 +459,8 @@ impl DataWise {
         source: &str,
         path: &str,
         fmt: protocol::FileFmt,
+        overwrite: bool,
+        options: ExportOptions,
     ) -> Result<()> {
         use std::path::Path;
 
@@ -283,59 +470,764 @@ impl DataWise {
         let cancel_flag = Arc::new(AtomicBool::new(false));
         self.task_cancels.insert(task_id, Arc::clone(&cancel_flag));
 
-        // 定义进度回调
-        let tx = self.tx.clone();
-        let progress_callback: Box<dyn Fn(u64, u64) + Send + Sync> = Box::new(move |processed, total| {
-            let pct = if total > 0 {
-                ((processed as f64 / total as f64) * 100.0) as u8
-            } else {
-                0
-            };
-            let _ = tx.send(UiEvent {
-                task_id,
-                kind: EventKind::Progress {
-                    pct,
-                    bytes_processed: processed,
-                    total_bytes: total,
-                    eta_seconds: None,
-                },
+        // 定义进度回调（按已导出的行数和已写入的字节数）
+        let progress_callback = self.export_progress_callback(task_id);
+
+        // 使用 Exporter 执行导出
+        let config = ExportConfig {
+            options,
+            overwrite,
+            cancel: Some(cancel_flag),
+            ..ExportConfig::new_table(source.to_string())
+        };
+        let summary = match fmt {
+            protocol::FileFmt::Csv => self.exporter.export_csv(file_path, config, Some(progress_callback))?,
+            protocol::FileFmt::Parquet => self.exporter.export_parquet(file_path, config, Some(progress_callback))?,
+            protocol::FileFmt::Json => {
+                return Err(anyhow::anyhow!("JSON export not yet implemented"));
+            }
+            protocol::FileFmt::Excel => self.exporter.export_excel(file_path, config, Some(progress_callback))?,
+            protocol::FileFmt::Arrow => self.exporter.export_arrow(file_path, config, Some(progress_callback))?,
+        };
+
+        self.send_export_finished(task_id, &summary)
+    }
+
+    /// 导出 Excel 工作簿（多个工作表）
+    async fn export_workbook(
+        &self,
+        task_id: u64,
+        path: &str,
+        sheets: Vec<SheetExport>,
+        split_large_sheets: bool,
+        overwrite: bool,
+    ) -> Result<()> {
+        // 创建取消标记
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        self.task_cancels.insert(task_id, Arc::clone(&cancel_flag));
+
+        // 定义进度回调（按已写入的行数）
+        let progress_callback = self.export_progress_callback(task_id);
+
+        let sheets: Vec<ExcelSheet> = sheets
+            .into_iter()
+            .map(|sheet| {
+                let config = if sheet.is_query {
+                    ExportConfig::new_query(sheet.source)
+                } else {
+                    ExportConfig::new_table(sheet.source)
+                };
+                ExcelSheet {
+                    name: sheet.sheet_name,
+                    config: ExportConfig {
+                        cancel: Some(Arc::clone(&cancel_flag)),
+                        ..config
+                    },
+                }
+            })
+            .collect();
+
+        let summary = self.exporter.export_excel_sheets(
+            std::path::Path::new(path),
+            &sheets,
+            split_large_sheets,
+            overwrite,
+            Some(progress_callback),
+        )?;
+
+        self.send_export_finished(task_id, &summary)
+    }
+
+    /// 发送导出完成事件，`preview` 是 [`ExportSummary`] 的 JSON
+    fn send_export_finished(&self, task_id: u64, summary: &ExportSummary) -> Result<()> {
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count: summary.rows_exported as usize,
+                column_count: summary.column_count,
+                preview: serde_json::to_string(summary)?,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 列出数据库、schema、表，或描述单张表
+    ///
+    /// 列表操作的 `Finished.preview` 是 JSON 数组，`row_count` 是条目数；
+    /// 描述表时 `preview` 是 [`TableInfo`] 的 JSON，`row_count`、`column_count` 是表的行数和列数。
+    async fn browse_catalog(&self, task_id: u64, cmd_type: CmdType) -> Result<()> {
+        let (row_count, column_count, preview) = match cmd_type {
+            CmdType::ListDatabases => {
+                let databases = self.catalog.list_databases()?;
+                (databases.len(), 0, serde_json::to_string(&databases)?)
+            }
+            CmdType::ListSchemas { database } => {
+                let schemas = self.catalog.list_schemas(database.as_deref())?;
+                (schemas.len(), 0, serde_json::to_string(&schemas)?)
+            }
+            CmdType::ListTables { database, schema } => {
+                let tables = self.catalog.list_tables(database.as_deref(), schema.as_deref())?;
+                (tables.len(), 0, serde_json::to_string(&tables)?)
+            }
+            CmdType::DescribeTable { name } => {
+                let table = self.catalog.describe_table(&name)?;
+                (
+                    table.row_count.unwrap_or(0) as usize,
+                    table.columns.len(),
+                    serde_json::to_string(&table)?,
+                )
+            }
+            other => anyhow::bail!("Not a catalog command: {:?}", other),
+        };
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count,
+                column_count,
+                preview,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 计算表的列画像
+    ///
+    /// 进度按已完成的计算步骤报告；`Finished.preview` 是 [`TableProfile`] 的 JSON，
+    /// `row_count` 是表的总行数，`column_count` 是计算的列数。
+    async fn profile_table(&self, task_id: u64, table: &str, columns: &[String], sample: Option<u64>) -> Result<()> {
+        // 创建取消标记
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        self.task_cancels.insert(task_id, Arc::clone(&cancel_flag));
+
+        // 定义进度回调（按已完成的计算步骤）
+        let progress_callback = self.progress_callback(task_id);
+
+        let table = self.catalog.describe_table(table)?;
+        let profile = self.profiler.profile_table(&table, columns, sample, Some(&cancel_flag), &mut |done, total| {
+            progress_callback(done, total)
+        })?;
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count: profile.row_count as usize,
+                column_count: profile.columns.len(),
+                preview: serde_json::to_string(&profile)?,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 计算列的分布
+    ///
+    /// `Finished.preview` 是 [`Histogram`] 的 JSON，`row_count` 是分箱数，`column_count` 是分组数。
+    async fn histogram(
+        &self,
+        task_id: u64,
+        table: &str,
+        column: &str,
+        binning: Binning,
+        group_by: Option<&str>,
+    ) -> Result<()> {
+        let table = self.catalog.describe_table(table)?;
+        let histogram = self.profiler.histogram(&table, column, binning, group_by)?;
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count: histogram.edges.bin_count(),
+                column_count: histogram.series.len(),
+                preview: serde_json::to_string(&histogram)?,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 重命名、删除、复制、清空表，或把查询保存为视图/表
+    ///
+    /// 先发送 `CatalogChanged`，再发送 `Finished`：`preview` 是 [`TableChange`] 的 JSON，
+    /// `row_count` 是受影响的行数，`column_count` 是操作后表的列数。
+    /// 预览删除或清空（`dry_run`）时目录不变，不发送 `CatalogChanged`。
+    async fn manage_table(&self, task_id: u64, action: TableAction) -> Result<()> {
+        let result = match action {
+            TableAction::Rename { name, new_name } => self.catalog.rename_table(&name, &new_name)?,
+            TableAction::Drop { name, dry_run } => self.catalog.drop_table(&name, dry_run)?,
+            TableAction::Duplicate { name, new_name, schema_only } => {
+                self.catalog.duplicate_table(&name, &new_name, schema_only)?
+            }
+            TableAction::Truncate { name, dry_run } => self.catalog.truncate_table(&name, dry_run)?,
+            TableAction::SaveQuery { sql, name, materialize, overwrite } => {
+                self.catalog.save_query(&sql, &name, materialize, overwrite)?
+            }
+        };
+
+        self.send_catalog_changed(task_id, &result.change);
+        // 操作后表的数据来源以结果为准（例如保存查询时记录的 SQL）
+        if let Some(table) = &result.table {
+            if let Some(source) = &table.source {
+                let name = [&table.database, &table.schema, &table.name].map(|s| s.as_str()).join(".");
+                self.catalog.record_source(&name, source.clone())?;
+            }
+        }
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count: result.rows_affected as usize,
+                column_count: result.table.as_ref().map(|t| t.columns.len()).unwrap_or(0),
+                preview: serde_json::to_string(&result)?,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 按键列对比两张表
+    ///
+    /// `Finished.preview` 是 [`TableDiff`] 的 JSON，`row_count` 是新增、删除和修改的行数之和，
+    /// `column_count` 是有修改的列数。
+    async fn diff_tables(&self, task_id: u64, left: &str, right: &str, key_columns: &[String]) -> Result<()> {
+        let left = self.catalog.describe_table(left)?;
+        let right = self.catalog.describe_table(right)?;
+        let before = self.catalog.snapshot()?;
+        let diff = self.differ.diff(&left, &right, key_columns)?;
+        // 结果表被新建或替换
+        let tables = [&diff.tables.added, &diff.tables.removed, &diff.tables.changed].map(|t| t.to_string());
+        for table in &tables {
+            self.catalog.forget_source(table)?;
+        }
+        self.send_catalog_changed(task_id, &self.catalog.changes_since(&before, &tables)?);
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count: (diff.added_rows + diff.removed_rows + diff.changed_rows) as usize,
+                column_count: diff.column_changes.iter().filter(|c| c.changed_rows > 0).count(),
+                preview: serde_json::to_string(&diff)?,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 附加、列出、加载或保存数据质量规则
+    ///
+    /// `Finished.preview` 是操作后 [`RuleSet`] 的 JSON，`row_count` 是有规则的表数，`column_count` 是规则总数。
+    async fn rules_command(&self, task_id: u64, action: RulesAction) -> Result<()> {
+        let rules = match action {
+            RulesAction::List => self.validator.rules(),
+            RulesAction::Set { table, rules } => self.validator.set_rules(&table, rules)?,
+            RulesAction::Load { path } => self.validator.load(std::path::Path::new(&path))?,
+            RulesAction::Save { path } => self.validator.save(std::path::Path::new(&path))?,
+        };
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count: rules.tables.len(),
+                column_count: rules.rule_count(),
+                preview: serde_json::to_string(&rules)?,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 按数据质量规则检查表，`rules` 为空时使用附加到该表的规则
+    ///
+    /// 进度按已检查的规则数报告；`Finished.preview` 是 [`ValidationReport`] 的 JSON，
+    /// `row_count` 是表的行数，`column_count` 是未通过的规则数。
+    async fn validate(&self, task_id: u64, table: &str, rules: Vec<Rule>) -> Result<()> {
+        let rules = if rules.is_empty() {
+            self.validator
+                .rules_for(table)
+                .ok_or_else(|| anyhow::anyhow!("No rules attached to table: {}", table))?
+        } else {
+            rules
+        };
+
+        // 创建取消标记
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        self.task_cancels.insert(task_id, Arc::clone(&cancel_flag));
+
+        // 定义进度回调（按已检查的规则数）
+        let progress_callback = self.progress_callback(task_id);
+
+        let report = self.validator.validate(table, &rules, Some(&cancel_flag), &mut |done, total| {
+            progress_callback(done, total)
+        })?;
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count: report.row_count as usize,
+                column_count: report.failed_rules(),
+                preview: serde_json::to_string(&report)?,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 导入到附加了规则的表后自动检查，发送 `Validated` 事件
+    ///
+    /// 检查本身出错（如规则中的列不存在）不影响导入结果，只记录日志。
+    fn validate_imported(&self, task_id: u64, table: &str) {
+        let Some(rules) = self.validator.rules_for(table) else {
+            return;
+        };
+        match self.validator.validate(table, &rules, None, &mut |_, _| {}) {
+            Ok(report) => {
+                let _ = self.tx.send(UiEvent {
+                    task_id,
+                    kind: EventKind::Validated {
+                        table: table.to_string(),
+                        passed: report.passed,
+                        report,
+                    },
+                });
+            }
+            Err(e) => tracing::warn!("Failed to validate imported table {}: {:#}", table, e),
+        }
+    }
+
+    /// 打开、列出、搜索、重新执行或置顶查询历史
+    ///
+    /// 列出和搜索时 `Finished.preview` 是 [`HistoryEntry`] 的 JSON 数组，`row_count` 是条数；
+    /// 打开历史文件时 `row_count` 是记录数；置顶时 `preview` 是该记录的 JSON；
+    /// 重新执行的结果与 `ExecuteSql` 相同。
+    async fn history_command(&self, task_id: u64, action: HistoryAction) -> Result<()> {
+        let (row_count, preview) = match action {
+            HistoryAction::Open { path } => (self.history.open(std::path::Path::new(&path))?, String::new()),
+            HistoryAction::List { pinned_only, limit } => {
+                let entries = self.history.list(None, pinned_only, limit);
+                (entries.len(), serde_json::to_string(&entries)?)
+            }
+            HistoryAction::Search { text, limit } => {
+                let entries = self.history.list(Some(&text), false, limit);
+                (entries.len(), serde_json::to_string(&entries)?)
+            }
+            HistoryAction::Rerun { id } => {
+                let entry = self.history.get(id)?;
+                return self.execute_sql(task_id, &entry.sql, &BTreeMap::new()).await;
+            }
+            HistoryAction::Pin { id, pinned } => (1, serde_json::to_string(&self.history.pin(id, pinned)?)?),
+        };
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count,
+                column_count: 0,
+                preview,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 打开、列出、保存、修改、删除、执行、导入或导出查询片段
+    ///
+    /// 列出时 `Finished.preview` 是 [`Snippet`] 的 JSON 数组，`row_count` 是个数；
+    /// 保存、修改和删除时 `preview` 是该片段的 JSON；打开、导入和导出时 `row_count` 是涉及的片段数；
+    /// 执行的结果与 `ExecuteSql` 相同，代入参数后的 SQL 记录到查询历史。
+    async fn snippets_command(&self, task_id: u64, action: SnippetAction) -> Result<()> {
+        let (row_count, preview) = match action {
+            SnippetAction::Open { path } => (self.snippets.open(std::path::Path::new(&path))?, String::new()),
+            SnippetAction::List { folder, tag } => {
+                let snippets = self.snippets.list(folder.as_deref(), tag.as_deref());
+                (snippets.len(), serde_json::to_string(&snippets)?)
+            }
+            SnippetAction::Save { snippet } => (1, serde_json::to_string(&self.snippets.save(snippet)?)?),
+            SnippetAction::Update { name, snippet } => {
+                (1, serde_json::to_string(&self.snippets.update(&name, snippet)?)?)
+            }
+            SnippetAction::Delete { name } => (1, serde_json::to_string(&self.snippets.delete(&name)?)?),
+            SnippetAction::Execute { name, params } => {
+                let sql = self.snippets.get(&name)?.render(&params)?;
+                return self.execute_sql(task_id, &sql, &params).await;
+            }
+            SnippetAction::Export { path, names } => {
+                (self.snippets.export(std::path::Path::new(&path), &names)?, String::new())
+            }
+            SnippetAction::Import { path, overwrite } => {
+                (self.snippets.import(std::path::Path::new(&path), overwrite)?, String::new())
+            }
+        };
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count,
+                column_count: 0,
+                preview,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 打开、保存或执行工作簿
+    ///
+    /// `Finished.preview` 是（更新后的）[`Workbook`] 的 JSON。打开和保存时 `row_count` 是单元格数；
+    /// 执行时 `row_count` 是执行的单元格数，按单元格报告进度。
+    async fn workbook_command(&self, task_id: u64, path: &str, action: WorkbookAction) -> Result<()> {
+        let path = std::path::Path::new(path);
+        let mut workbook = match &action {
+            WorkbookAction::Create => {
+                if path.exists() {
+                    anyhow::bail!("Workbook already exists: {}", path.display());
+                }
+                Workbook::default()
+            }
+            WorkbookAction::Save { workbook } => workbook.clone(),
+            _ => Workbook::load(path)?,
+        };
+        let row_count = match action {
+            WorkbookAction::Open => workbook.cells.len(),
+            WorkbookAction::Create | WorkbookAction::Save { .. } => {
+                workbook.save(path)?;
+                workbook.cells.len()
+            }
+            WorkbookAction::RunCell { id, with_dependencies } => {
+                let index = workbook.sql_cell(&id)?;
+                let order = if with_dependencies {
+                    workbook.execution_order(Some(index))?
+                } else {
+                    vec![index]
+                };
+                self.run_cells(task_id, path, &mut workbook, &order).await?
+            }
+            WorkbookAction::RunAll => {
+                let order = workbook.execution_order(None)?;
+                self.run_cells(task_id, path, &mut workbook, &order).await?
+            }
+        };
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count,
+                column_count: 0,
+                preview: serde_json::to_string(&workbook)?,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 按顺序执行工作簿的 SQL 单元格，每个单元格的结果摘要都写回工作簿文件
+    ///
+    /// 单元格失败时停止执行，返回该错误；执行的 SQL 记录到查询历史。
+    async fn run_cells(
+        &self,
+        task_id: u64,
+        path: &std::path::Path,
+        workbook: &mut Workbook,
+        order: &[usize],
+    ) -> Result<usize> {
+        // 创建取消标记
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        self.task_cancels.insert(task_id, Arc::clone(&cancel_flag));
+
+        // 定义进度回调（按已执行的单元格数）
+        let progress_callback = self.progress_callback(task_id);
+
+        for (done, &index) in order.iter().enumerate() {
+            if cancel_flag.load(Ordering::SeqCst) {
+                anyhow::bail!("Workbook run cancelled");
+            }
+
+            let started = SystemTime::now();
+            let timer = Instant::now();
+            let result = self.run_sql(task_id, &workbook.cells[index].source, &BTreeMap::new()).await;
+            let cell = &mut workbook.cells[index];
+            cell.result = Some(match &result {
+                Ok(r) => CellResult::succeeded(started, timer.elapsed(), r.row_count, r.column_count, r.preview.clone()),
+                Err(e) => CellResult::failed(started, timer.elapsed(), e),
             });
+            let id = cell.id.clone();
+            workbook.save(path)?;
+            if let Err(e) = result {
+                anyhow::bail!("Workbook cell {} failed: {:#}", id, e);
+            }
+
+            progress_callback(done as u64 + 1, order.len() as u64);
+        }
+
+        Ok(order.len())
+    }
+
+    /// 开启/关闭、清空查询结果缓存或查看统计
+    ///
+    /// `Finished.preview` 是 [`CacheStats`] 的 JSON，`row_count` 是缓存的结果数。
+    async fn cache_command(&self, task_id: u64, action: CacheAction) -> Result<()> {
+        let stats = match action {
+            CacheAction::Configure { enabled, max_bytes } => self.cache.configure(enabled, max_bytes),
+            CacheAction::Clear => self.cache.clear(),
+            CacheAction::Stats => self.cache.stats(),
+        };
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count: stats.entries,
+                column_count: 0,
+                preview: serde_json::to_string(&stats)?,
+                rejected_rows: None,
+                cache: None,
+            },
         });
 
-        // 执行导出（直接使用 Executor 的连接）
-        match fmt {
-            protocol::FileFmt::Csv => {
-                self.executor.export_csv(
-                    file_path,
-                    source,
-                    Some(progress_callback),
-                )?;
+        Ok(())
+    }
+
+    /// 发送目录变化事件（没有变化时不发送）
+    ///
+    /// 被删除和修改的表的数据来源记录随之清除，调用方需要在此之后记录新的来源。
+    fn send_catalog_changed(&self, task_id: u64, change: &CatalogChange) {
+        // 只修改数据或结构的表保留数据来源；被替换的表由调用方清除或重新记录
+        self.catalog.forget_sources(&change.dropped);
+        // 变化的表的缓存结果失效
+        self.cache
+            .invalidate(&[change.created.as_slice(), change.dropped.as_slice(), change.altered.as_slice()].concat());
+        if change.is_empty() {
+            return;
+        }
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::CatalogChanged {
+                created: change.created.clone(),
+                dropped: change.dropped.clone(),
+                altered: change.altered.clone(),
+            },
+        });
+    }
+
+    /// 列出、附加或导入 SQLite 数据库
+    ///
+    /// `Finished.preview` 是涉及的表（表名、行数、列类型）的 JSON 数组，
+    /// `row_count` 是这些表的总行数，`column_count` 是表的数量。
+    async fn open_sqlite(&self, task_id: u64, path: &str, action: SqliteAction) -> Result<()> {
+        let file_path = std::path::Path::new(path);
+
+        // 附加和导入会改变目录
+        let before = match action {
+            SqliteAction::ListTables => None,
+            _ => Some(self.catalog.snapshot()?),
+        };
+        let mut imported = Vec::new();
+
+        let tables = match action {
+            SqliteAction::ListTables => self.importer.list_sqlite_tables(file_path)?,
+            SqliteAction::Attach { alias } => {
+                let alias = alias.unwrap_or_else(|| importer::default_table_name(path));
+                self.importer.attach_sqlite(file_path, &alias)?
             }
-            protocol::FileFmt::Parquet => {
-                self.executor.export_parquet(
+            SqliteAction::Import { tables, overwrite } => {
+                // 创建取消标记
+                let cancel_flag = Arc::new(AtomicBool::new(false));
+                self.task_cancels.insert(task_id, Arc::clone(&cancel_flag));
+
+                // 定义进度回调（按已复制的行数）
+                let progress_callback = self.progress_callback(task_id);
+
+                let tables = self.importer.import_sqlite(
                     file_path,
-                    source,
+                    &tables,
+                    overwrite,
+                    Some(&cancel_flag),
                     Some(progress_callback),
                 )?;
+                imported.extend(tables.iter().map(|table| table.name.clone()));
+                tables
             }
-            protocol::FileFmt::Json => {
-                return Err(anyhow::anyhow!("JSON export not yet implemented"));
-            }
+        };
+
+        if let Some(before) = before {
+            self.send_catalog_changed(task_id, &self.catalog.changes_since(&before, &imported)?);
+        }
+        for table in &imported {
+            let source = TableSource::Sqlite {
+                path: path.to_string(),
+                table: table.clone(),
+            };
+            self.catalog.record_source(table, source)?;
+            self.validate_imported(task_id, table);
         }
 
         // 发送完成事件
         let _ = self.tx.send(UiEvent {
             task_id,
             kind: EventKind::Finished {
-                row_count: 0,
-                column_count: 0,
-                preview: "{}".to_string(),
+                row_count: tables.iter().map(|t| t.row_count as usize).sum(),
+                column_count: tables.len(),
+                preview: serde_json::to_string(&tables)?,
+                rejected_rows: None,
+                cache: None,
             },
         });
 
         Ok(())
     }
 
+    /// 列出 PostgreSQL/MySQL 数据库的 schema 和表、执行下推查询或导入查询结果
+    ///
+    /// 列表操作的 `Finished.preview` 是 JSON 数组，`row_count` 是条目数，`column_count`
+    /// 是每个条目的字段数（schema 为 [`database::SCHEMA_FIELD_COUNT`]，表为 [`RemoteTable::FIELD_COUNT`]）；
+    /// 查询和导入的 `Finished` 与 SQL 查询、文件导入相同。
+    async fn database_command(&self, task_id: u64, profile: &ConnectionProfile, action: DatabaseAction) -> Result<()> {
+        let (row_count, column_count, preview) = match action {
+            DatabaseAction::ListSchemas => {
+                let schemas = self.importer.list_database_schemas(profile)?;
+                (schemas.len(), database::SCHEMA_FIELD_COUNT, serde_json::to_string(&schemas)?)
+            }
+            DatabaseAction::ListTables { schema } => {
+                let tables = self.importer.list_database_tables(profile, schema.as_deref())?;
+                (tables.len(), RemoteTable::FIELD_COUNT, serde_json::to_string(&tables)?)
+            }
+            DatabaseAction::Query { sql } => {
+                // 先确保已附加，再由执行器运行下推查询
+                self.importer.attach_database(profile)?;
+                let pushdown = format!("SELECT * FROM {}", database::pushdown_sql(profile, &sql));
+                let batches = self.executor.execute(&pushdown)?;
+
+                let row_count = batches.iter().map(|b| b.num_rows()).sum();
+                let column_count = batches.first().map(|b| b.num_columns()).unwrap_or(0);
+                (row_count, column_count, self.generate_preview(&batches)?)
+            }
+            DatabaseAction::Import { sql, table_name, overwrite } => {
+                // 创建取消标记
+                let cancel_flag = Arc::new(AtomicBool::new(false));
+                self.task_cancels.insert(task_id, Arc::clone(&cancel_flag));
+
+                // 定义进度回调（按 DuckDB 的查询进度）
+                let progress_callback = self.progress_callback(task_id);
+
+                let config = ImportConfig {
+                    table_name: table_name.clone(),
+                    overwrite,
+                    options: ImportOptions::default(),
+                };
+                let before = self.catalog.snapshot()?;
+                let rows = self.importer.import_database_query(profile, &sql, config, Some(&cancel_flag), Some(progress_callback))?;
+                let touched = [table_name.clone()];
+                self.send_catalog_changed(task_id, &self.catalog.changes_since(&before, &touched)?);
+                let source = TableSource::Database {
+                    profile: profile.name.clone(),
+                    sql,
+                };
+                self.catalog.record_source(&table_name, source)?;
+                self.validate_imported(task_id, &table_name);
+
+                let preview_batches = self.executor.execute(&format!("SELECT * FROM {} LIMIT 10", quote_table_name(&table_name)))?;
+                let column_count = preview_batches.first().map(|b| b.num_columns()).unwrap_or(0);
+                (rows as usize, column_count, self.generate_preview(&preview_batches)?)
+            }
+        };
+
+        // 发送完成事件
+        let _ = self.tx.send(UiEvent {
+            task_id,
+            kind: EventKind::Finished {
+                row_count,
+                column_count,
+                preview,
+                rejected_rows: None,
+                cache: None,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// 创建进度回调：把进度换算为百分比，并按平均吞吐量估算剩余时间
+    fn progress_callback(&self, task_id: u64) -> Box<dyn Fn(u64, u64) + Send + Sync> {
+        let tx = self.tx.clone();
+        let started = Instant::now();
+        Box::new(move |processed, total| {
+            let pct = if total > 0 {
+                ((processed as f64 / total as f64) * 100.0) as u8
+            } else {
+                0
+            };
+            let _ = tx.send(UiEvent {
+                task_id,
+                kind: EventKind::Progress {
+                    pct,
+                    bytes_processed: processed,
+                    total_bytes: total,
+                    eta_seconds: eta_seconds(started.elapsed(), processed, total),
+                    rows_processed: None,
+                    total_rows: None,
+                },
+            });
+        })
+    }
+
+    /// 创建导出进度回调：百分比和剩余时间按完成比例计算，同时报告已写入的字节数
+    fn export_progress_callback(&self, task_id: u64) -> exporter::ProgressCallback {
+        let tx = self.tx.clone();
+        let started = Instant::now();
+        Box::new(move |progress| {
+            // 总字节数按已完成的比例估算
+            let total_bytes = if progress.fraction > 0.0 {
+                (progress.bytes_written as f64 / progress.fraction) as u64
+            } else {
+                progress.bytes_written
+            };
+            let _ = tx.send(UiEvent {
+                task_id,
+                kind: EventKind::Progress {
+                    pct: (progress.fraction * 100.0) as u8,
+                    bytes_processed: progress.bytes_written,
+                    total_bytes,
+                    eta_seconds: eta_seconds(started.elapsed(), (progress.fraction * 10_000.0) as u64, 10_000),
+                    rows_processed: progress.rows_written,
+                    total_rows: progress.total_rows,
+                },
+            });
+        })
+    }
+
     /// 取消任务
     fn cancel_task(&self, task_id: u64) {
         if let Some((_, cancel_flag)) = self.task_cancels.remove(&task_id) {
@@ -400,6 +1292,15 @@ impl DataWise {
     }
 }
 
+/// 按平均吞吐量估算剩余时间（秒），尚无进度时返回 `None`
+fn eta_seconds(elapsed: Duration, processed: u64, total: u64) -> Option<u32> {
+    if processed == 0 || total == 0 {
+        return None;
+    }
+    let remaining = total.saturating_sub(processed) as f64;
+    Some((elapsed.as_secs_f64() * remaining / processed as f64).ceil() as u32)
+}
+
 impl Default for DataWise {
     fn default() -> Self {
         Self::new().expect("Failed to create default DataWise")
@@ -478,6 +1379,7 @@ mod tests {
                 row_count,
                 column_count,
                 preview,
+                ..
             } => {
                 assert_eq!(row_count, 1);
                 assert_eq!(column_count, 3);
@@ -535,6 +1437,7 @@ mod tests {
                 row_count,
                 column_count,
                 preview,
+                ..
             } => {
                 assert_eq!(row_count, 5);
                 assert_eq!(column_count, 1);
@@ -569,6 +1472,7 @@ mod tests {
                 row_count,
                 column_count,
                 preview,
+                ..
             } => {
                 assert_eq!(row_count, 1);
                 assert_eq!(column_count, 4);
@@ -605,6 +1509,7 @@ mod tests {
                 row_count,
                 column_count,
                 preview,
+                ..
             } => {
                 assert_eq!(row_count, 1);
                 assert_eq!(column_count, 2);
@@ -639,6 +1544,7 @@ mod tests {
                 row_count,
                 column_count,
                 preview,
+                ..
             } => {
                 assert_eq!(row_count, 1);
                 assert_eq!(column_count, 2);
@@ -676,6 +1582,7 @@ mod tests {
                 row_count,
                 column_count,
                 preview,
+                ..
             } => {
                 assert_eq!(row_count, 20);
                 assert_eq!(column_count, 1);
@@ -804,6 +1711,7 @@ mod tests {
                 row_count,
                 column_count,
                 preview,
+                ..
             } => {
                 assert_eq!(row_count, 1);
                 assert_eq!(column_count, 2);
@@ -813,4 +1721,12 @@ mod tests {
             _ => panic!("Expected Finished event"),
         }
     }
+
+    #[test]
+    fn test_eta_from_throughput() {
+        assert_eq!(eta_seconds(Duration::from_secs(2), 0, 100), None);
+        // 2 秒处理了 25%，剩余 75% 约需 6 秒
+        assert_eq!(eta_seconds(Duration::from_secs(2), 25, 100), Some(6));
+        assert_eq!(eta_seconds(Duration::from_secs(2), 100, 100), Some(0));
+    }
 }
@@ -0,0 +1,59 @@
+//! 状态文件的读写
+//!
+//! 查询历史、片段库、规则文件、工作簿等每次修改都整体写回。先写入同目录下的临时文件，
+//! 再重命名为目标文件，写入中途失败或进程退出时原文件保持完整。
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// 原子地写入整个文件（用法同 `std::fs::write`）
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::Builder::new()
+        .prefix(".")
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+    file.write_all(contents.as_ref())?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// 把无法解析的文件改名为 `<文件名>.corrupt`（覆盖之前保留的），返回新路径
+///
+/// 调用方随后按空内容继续，下次写入时重新创建文件。
+pub fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    let target = path.with_file_name(name);
+    std::fs::rename(path, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_and_quarantine() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("history.json");
+        write_atomic(&path, "[1]").unwrap();
+        write_atomic(&path, "[1, 2]").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[1, 2]");
+
+        let moved = quarantine(&path).unwrap();
+        assert_eq!(moved, dir.path().join("history.json.corrupt"));
+        assert!(!path.exists());
+
+        // 不留下临时文件
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["history.json.corrupt"]);
+    }
+}
@@ -0,0 +1,640 @@
+//! 列画像
+//!
+//! 用 DuckDB 聚合函数计算各列的空值数、不同值数、最小/最大值、均值/标准差、
+//! 最常见的值和字符串长度统计，结果为 [`TableProfile`]。所有列的统计量在一个聚合查询中计算，
+//! 最常见的值把各列逆透视后在一个查询中计算，不论列数多少都只扫描两次数据。
+//!
+//! 可以只对随机抽样的行计算（蓄水池抽样，固定种子，多次计算结果一致）。
+//!
+//! 直方图（[`Histogram`]）在 DuckDB 中分箱计数，只返回分箱边界和各箱的行数：
+//! 数值列按等宽或分位数分箱，日期/时间戳列按天、周、月分桶，可按类别列分组。
+
+use crate::catalog::{ColumnInfo, TableInfo};
+use crate::protocol::{Binning, TimeBucket};
+use crate::sql::{quote_ident, quote_literal};
+use anyhow::{bail, Context, Result};
+use duckdb::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 每列返回的最常见值的个数
+const TOP_K: usize = 10;
+
+/// 抽样的随机种子
+const SAMPLE_SEED: u32 = 42;
+
+/// 直方图最多的分箱数
+const MAX_BINS: u64 = 10_000;
+
+/// 直方图最多返回的分组数
+const MAX_GROUPS: usize = 20;
+
+/// 表的画像
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableProfile {
+    /// 表名
+    pub table: String,
+    /// 表的总行数
+    pub row_count: u64,
+    /// 参与计算的行数（抽样时小于总行数）
+    pub profiled_rows: u64,
+    /// 各列的画像（按列顺序）
+    pub columns: Vec<ColumnProfile>,
+}
+
+/// 列的画像
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnProfile {
+    /// 列名
+    pub name: String,
+    /// DuckDB 类型
+    pub data_type: String,
+    /// 空值数
+    pub null_count: u64,
+    /// 不同值的个数（不含空值）
+    pub distinct_count: u64,
+    /// 最小值（文本形式，嵌套类型为 `None`）
+    pub min: Option<String>,
+    /// 最大值（文本形式，嵌套类型为 `None`）
+    pub max: Option<String>,
+    /// 均值（仅数值列）
+    pub mean: Option<f64>,
+    /// 样本标准差（仅数值列）
+    pub stddev: Option<f64>,
+    /// 最常见的值及出现次数，按次数降序
+    pub top_values: Vec<ValueCount>,
+    /// 字符串长度统计（仅字符串列）
+    pub length: Option<LengthStats>,
+}
+
+/// 值及其出现次数
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValueCount {
+    /// 值（文本形式）
+    pub value: String,
+    /// 出现次数
+    pub count: u64,
+}
+
+/// 字符串长度（字符数）统计
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LengthStats {
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+}
+
+/// 列的分布
+///
+/// 第 `i` 箱包含 `[edges[i], edges[i + 1])` 中的值，最后一箱包含右边界。
+/// 列中没有可分箱的值时 `edges` 为空。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Histogram {
+    /// 列名
+    pub column: String,
+    /// 分组的类别列
+    pub group_by: Option<String>,
+    /// 分箱边界，比分箱数多一个
+    pub edges: BinEdges,
+    /// 各分组的计数，按分组的总行数降序；不分组时只有一个 `group` 为 `None` 的序列
+    pub series: Vec<HistogramSeries>,
+    /// 未计入分箱的行数（空值，以及数值列中的 NaN 和无穷大）
+    pub excluded_count: u64,
+    /// 超出返回上限而省略的分组数
+    pub omitted_groups: u64,
+}
+
+/// 分箱边界
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum BinEdges {
+    /// 数值
+    Numeric(Vec<f64>),
+    /// 时间戳（`YYYY-MM-DD HH:MM:SS`）
+    Temporal(Vec<String>),
+}
+
+impl BinEdges {
+    /// 分箱数
+    pub fn bin_count(&self) -> usize {
+        let edges = match self {
+            BinEdges::Numeric(edges) => edges.len(),
+            BinEdges::Temporal(edges) => edges.len(),
+        };
+        edges.saturating_sub(1)
+    }
+}
+
+/// 一个分组的各箱计数
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistogramSeries {
+    /// 分组的值（文本形式，不分组或分组值为空时为 `None`）
+    pub group: Option<String>,
+    /// 各箱的行数
+    pub counts: Vec<u64>,
+}
+
+/// 列画像计算器
+pub struct Profiler {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Profiler {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    /// 计算表的画像
+    ///
+    /// `columns` 为空时计算所有列；`sample` 指定时只对随机抽样的最多 `sample` 行计算。
+    /// 计算分两步（统计量、最常见的值），每完成一步以（已完成步数，总步数）调用一次 `on_step`，
+    /// `cancel` 被置位时在两步之间停止。
+    ///
+    /// 视图没有记录行数：不抽样时总行数就是参与计算的行数，抽样时另外统计一次。
+    pub fn profile_table(
+        &self,
+        table: &TableInfo,
+        columns: &[String],
+        sample: Option<u64>,
+        cancel: Option<&AtomicBool>,
+        on_step: &mut dyn FnMut(u64, u64),
+    ) -> Result<TableProfile> {
+        let selected = select_columns(table, columns)?;
+        let relation = relation_sql(table, sample);
+        let check_cancelled = || {
+            if cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+                bail!("Profiling cancelled");
+            }
+            Ok(())
+        };
+
+        let conn = self.conn.lock().unwrap();
+        check_cancelled()?;
+        let (profiled_rows, mut profiles) = column_stats(&conn, &relation, &selected)
+            .with_context(|| format!("Failed to profile table: {}", table.name))?;
+        on_step(1, 2);
+
+        check_cancelled()?;
+        let top_values = top_values(&conn, &relation, &selected)
+            .with_context(|| format!("Failed to compute top values of table: {}", table.name))?;
+        for (profile, values) in profiles.iter_mut().zip(top_values) {
+            profile.top_values = values;
+        }
+        on_step(2, 2);
+
+        let row_count = match (table.row_count, sample) {
+            (Some(rows), _) => rows,
+            (None, None) => profiled_rows,
+            (None, Some(_)) => {
+                let rows: i64 = conn
+                    .query_row(&format!("SELECT COUNT(*) FROM {}", table.quoted_name()), [], |row| row.get(0))
+                    .with_context(|| format!("Failed to count rows of table: {}", table.name))?;
+                rows as u64
+            }
+        };
+
+        Ok(TableProfile {
+            table: table.name.clone(),
+            row_count,
+            profiled_rows,
+            columns: profiles,
+        })
+    }
+
+    /// 计算列的分布，可按类别列 `group_by` 分组
+    pub fn histogram(
+        &self,
+        table: &TableInfo,
+        column: &str,
+        binning: Binning,
+        group_by: Option<&str>,
+    ) -> Result<Histogram> {
+        let column = find_column(table, column)?;
+        let group = group_by.map(|name| find_column(table, name)).transpose()?;
+        let relation = relation_sql(table, None);
+        let c = quote_ident(&column.name);
+
+        let conn = self.conn.lock().unwrap();
+
+        // 分箱边界、参与分箱的值（`v`）和条件，以及给每行分配分箱序号的查询
+        let (edges, value, condition, bins_sql) = match (ColumnKind::of(&column.data_type), binning) {
+            (ColumnKind::Numeric, Binning::EqualWidth { bins } | Binning::Quantile { bins }) => {
+                validate_bins(bins)?;
+                let value = format!("CAST({c} AS DOUBLE)");
+                let condition = format!("isfinite({value})");
+                let edges = numeric_edges(&conn, &relation, &value, &condition, binning, bins)?;
+                let bins_sql = numeric_bins_sql(&edges, binning);
+                (BinEdges::Numeric(edges), value, condition, bins_sql)
+            }
+            (ColumnKind::Temporal, Binning::Time { unit }) => {
+                let bucket = format!("date_trunc('{}', CAST({c} AS TIMESTAMP))", unit.sql_unit());
+                let edges = temporal_edges(&conn, &relation, &c, &bucket, unit)?;
+                let bins_sql = match edges.first() {
+                    Some(start) => format!(
+                        "SELECT g, date_diff('{}', CAST({} AS TIMESTAMP), v) AS bin FROM src",
+                        unit.sql_unit(),
+                        quote_literal(start)
+                    ),
+                    None => "SELECT g, 0 AS bin FROM src".to_string(),
+                };
+                (BinEdges::Temporal(edges), bucket, "TRUE".to_string(), bins_sql)
+            }
+            (ColumnKind::Numeric, Binning::Time { .. }) => {
+                bail!("Time buckets need a date or timestamp column: {} is {}", column.name, column.data_type)
+            }
+            (ColumnKind::Temporal, _) => {
+                bail!("Use time buckets for date or timestamp column: {}", column.name)
+            }
+            _ => bail!(
+                "Histograms need a numeric, date or timestamp column: {} is {}",
+                column.name,
+                column.data_type
+            ),
+        };
+
+        let bin_count = edges.bin_count();
+        let group_expr = match group {
+            Some(group) => format!("CAST({} AS VARCHAR)", quote_ident(&group.name)),
+            None => "CAST(NULL AS VARCHAR)".to_string(),
+        };
+
+        let total: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM {}", relation), [], |row| row.get(0))?;
+
+        let mut series = Vec::new();
+        let mut binned = 0;
+        let mut group_count = 0;
+        if bin_count > 0 {
+            let src = format!(
+                "src AS (SELECT {group_expr} AS g, {value} AS v FROM {relation} WHERE {c} IS NOT NULL AND {condition})"
+            );
+            (binned, group_count) = conn.query_row(
+                &format!("WITH {src} SELECT COUNT(*), COUNT(DISTINCT g) + CAST(bool_or(g IS NULL) AS BIGINT) FROM src"),
+                [],
+                |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, Option<i64>>(1)?.unwrap_or(0) as u64)),
+            )?;
+
+            // 只对行数最多的 MAX_GROUPS 个分组按（分组, 分箱）计数
+            let mut stmt = conn.prepare(&format!(
+                "WITH {src}, \
+                 top AS (SELECT g FROM src GROUP BY g ORDER BY COUNT(*) DESC, g NULLS LAST LIMIT {MAX_GROUPS}), \
+                 binned AS ({bins_sql}) \
+                 SELECT binned.g, bin, COUNT(*) FROM binned JOIN top ON binned.g IS NOT DISTINCT FROM top.g \
+                 GROUP BY ALL"
+            ))?;
+            let mut groups: HashMap<Option<String>, Vec<u64>> = HashMap::new();
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let group: Option<String> = row.get(0)?;
+                let bin: i64 = row.get(1)?;
+                let count = row.get::<_, i64>(2)? as u64;
+                groups.entry(group).or_insert_with(|| vec![0; bin_count])[bin as usize] += count;
+            }
+            series = groups
+                .into_iter()
+                .map(|(group, counts)| HistogramSeries { group, counts })
+                .collect();
+        }
+
+        series.sort_by(|a, b| {
+            let total = |s: &HistogramSeries| s.counts.iter().sum::<u64>();
+            total(b).cmp(&total(a)).then_with(|| a.group.cmp(&b.group))
+        });
+        let omitted_groups = group_count.saturating_sub(series.len() as u64);
+
+        Ok(Histogram {
+            column: column.name.clone(),
+            group_by: group.map(|g| g.name.clone()),
+            edges,
+            series,
+            excluded_count: total as u64 - binned,
+            omitted_groups,
+        })
+    }
+}
+
+fn validate_bins(bins: u32) -> Result<()> {
+    if bins == 0 || bins as u64 > MAX_BINS {
+        bail!("Number of bins must be between 1 and {}: {}", MAX_BINS, bins);
+    }
+    Ok(())
+}
+
+/// 数值列的分箱边界：等宽分箱按最小/最大值等分，分位数分箱取各分位点（去掉重复的边界）
+fn numeric_edges(
+    conn: &Connection,
+    relation: &str,
+    value: &str,
+    condition: &str,
+    binning: Binning,
+    bins: u32,
+) -> Result<Vec<f64>> {
+    let expressions: Vec<String> = match binning {
+        Binning::Quantile { .. } => (0..=bins)
+            .map(|i| format!("quantile_cont({}, {:?})", value, i as f64 / bins as f64))
+            .collect(),
+        _ => vec![format!("MIN({})", value), format!("MAX({})", value)],
+    };
+    let sql = format!("SELECT {} FROM {} WHERE {}", expressions.join(", "), relation, condition);
+    let points = conn.query_row(&sql, [], |row| {
+        (0..expressions.len())
+            .map(|i| row.get::<_, Option<f64>>(i))
+            .collect::<duckdb::Result<Option<Vec<f64>>>>()
+    })?;
+
+    let Some(mut edges) = points else {
+        // 没有可分箱的值
+        return Ok(Vec::new());
+    };
+    if let Binning::EqualWidth { .. } = binning {
+        let (min, max) = (edges[0], edges[1]);
+        edges = (0..bins).map(|i| min + (max - min) * i as f64 / bins as f64).collect();
+        edges.push(max);
+    }
+    edges.dedup();
+    if edges.len() == 1 {
+        // 所有值相同时只有一箱
+        edges.push(edges[0]);
+    }
+    Ok(edges)
+}
+
+/// 给 `src`（分组 `g`、数值 `v`）中的每行分配分箱序号的查询，最后一箱包含右边界
+///
+/// 等宽分箱直接按宽度计算序号；分位数分箱用 ASOF JOIN 查找不大于值的最大下边界。
+fn numeric_bins_sql(edges: &[f64], binning: Binning) -> String {
+    let bins = edges.len().saturating_sub(1);
+    if bins <= 1 {
+        return "SELECT g, 0 AS bin FROM src".to_string();
+    }
+    match binning {
+        Binning::EqualWidth { .. } => {
+            let (min, max) = (edges[0], edges[bins]);
+            format!(
+                "SELECT g, LEAST(CAST(FLOOR((v - {:?}) / {:?}) AS BIGINT), {}) AS bin FROM src",
+                min,
+                (max - min) / bins as f64,
+                bins - 1
+            )
+        }
+        _ => {
+            let lowers: Vec<String> = edges[..bins].iter().map(|edge| format!("{:?}", edge)).collect();
+            format!(
+                "SELECT src.g, b.bin FROM src ASOF JOIN \
+                 (SELECT unnest(CAST([{}] AS DOUBLE[])) AS lower, unnest(range({})) AS bin) b ON src.v >= b.lower",
+                lowers.join(", "),
+                bins
+            )
+        }
+    }
+}
+
+/// 时间分桶的边界：从最早的桶到最晚的桶之后一个单位
+fn temporal_edges(conn: &Connection, relation: &str, column: &str, bucket: &str, unit: TimeBucket) -> Result<Vec<String>> {
+    let (first, last, buckets): (Option<String>, Option<String>, Option<i64>) = conn.query_row(
+        &format!(
+            "SELECT CAST(MIN(b) AS VARCHAR), CAST(MAX(b) AS VARCHAR), date_diff('{unit}', MIN(b), MAX(b)) + 1 \
+             FROM (SELECT {bucket} AS b FROM {relation} WHERE {column} IS NOT NULL)",
+            unit = unit.sql_unit()
+        ),
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let (Some(first), Some(last), Some(buckets)) = (first, last, buckets) else {
+        return Ok(Vec::new());
+    };
+    if buckets as u64 > MAX_BINS {
+        bail!("Too many {} buckets ({}), use a coarser time unit", unit.sql_unit(), buckets);
+    }
+
+    let interval = format!("INTERVAL 1 {}", unit.sql_unit().to_uppercase());
+    let mut stmt = conn.prepare(&format!(
+        "SELECT CAST(generate_series AS VARCHAR) FROM generate_series(CAST({} AS TIMESTAMP), CAST({} AS TIMESTAMP) + {}, {})",
+        quote_literal(&first),
+        quote_literal(&last),
+        interval,
+        interval
+    ))?;
+    let edges = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<duckdb::Result<Vec<String>>>()?;
+    Ok(edges)
+}
+
+/// 按名称（不区分大小写）选出要计算的列，保持请求中的顺序
+fn select_columns<'a>(table: &'a TableInfo, columns: &[String]) -> Result<Vec<&'a ColumnInfo>> {
+    if columns.is_empty() {
+        return Ok(table.columns.iter().collect());
+    }
+    columns.iter().map(|name| find_column(table, name)).collect()
+}
+
+/// 按名称（不区分大小写）查找列
+pub(crate) fn find_column<'a>(table: &'a TableInfo, name: &str) -> Result<&'a ColumnInfo> {
+    table
+        .columns
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(name))
+        .with_context(|| format!("Column not found in {}: {}", table.name, name))
+}
+
+/// 画像所用的数据：整表，或固定种子的蓄水池抽样
+fn relation_sql(table: &TableInfo, sample: Option<u64>) -> String {
+    let name = table.quoted_name();
+    match sample {
+        Some(rows) => format!(
+            "(SELECT * FROM {} USING SAMPLE reservoir({} ROWS) REPEATABLE ({}))",
+            name, rows, SAMPLE_SEED
+        ),
+        None => name,
+    }
+}
+
+/// 每列在统计查询中的表达式个数
+const STATS_PER_COLUMN: usize = 9;
+
+/// 统计量的聚合表达式（最常见的值除外）
+fn stats_sql(column: &ColumnInfo) -> String {
+    let c = quote_ident(&column.name);
+    let kind = ColumnKind::of(&column.data_type);
+
+    let (min, max) = match kind {
+        ColumnKind::Nested => ("NULL".to_string(), "NULL".to_string()),
+        _ => (format!("CAST(MIN({c}) AS VARCHAR)"), format!("CAST(MAX({c}) AS VARCHAR)")),
+    };
+    let (mean, stddev) = match kind {
+        ColumnKind::Numeric => (format!("CAST(AVG({c}) AS DOUBLE)"), format!("CAST(STDDEV_SAMP({c}) AS DOUBLE)")),
+        _ => ("NULL".to_string(), "NULL".to_string()),
+    };
+    let lengths = match kind {
+        ColumnKind::Text => format!("MIN(LENGTH({c})), MAX(LENGTH({c})), CAST(AVG(LENGTH({c})) AS DOUBLE)"),
+        _ => "NULL, NULL, NULL".to_string(),
+    };
+    format!("COUNT(*) - COUNT({c}), COUNT(DISTINCT {c}), {min}, {max}, {mean}, {stddev}, {lengths}")
+}
+
+/// 在一个聚合查询中计算参与计算的行数和所有列的统计量
+fn column_stats(conn: &Connection, relation: &str, columns: &[&ColumnInfo]) -> Result<(u64, Vec<ColumnProfile>)> {
+    let aggregates = std::iter::once("COUNT(*)".to_string())
+        .chain(columns.iter().map(|column| stats_sql(column)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!("SELECT {aggregates} FROM {relation}");
+    let stats = conn.query_row(&sql, [], |row| {
+        let rows: i64 = row.get(0)?;
+        let profiles = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let base = 1 + i * STATS_PER_COLUMN;
+                let min_length: Option<i64> = row.get(base + 6)?;
+                let max_length: Option<i64> = row.get(base + 7)?;
+                let mean_length: Option<f64> = row.get(base + 8)?;
+                Ok(ColumnProfile {
+                    name: column.name.clone(),
+                    data_type: column.data_type.clone(),
+                    null_count: row.get::<_, i64>(base)? as u64,
+                    distinct_count: row.get::<_, i64>(base + 1)? as u64,
+                    min: row.get(base + 2)?,
+                    max: row.get(base + 3)?,
+                    mean: row.get(base + 4)?,
+                    stddev: row.get(base + 5)?,
+                    top_values: Vec::new(),
+                    length: match (min_length, max_length, mean_length) {
+                        (Some(min), Some(max), Some(mean)) => Some(LengthStats {
+                            min: min as u64,
+                            max: max as u64,
+                            mean,
+                        }),
+                        _ => None,
+                    },
+                })
+            })
+            .collect::<duckdb::Result<Vec<_>>>()?;
+        Ok((rows as u64, profiles))
+    })?;
+    Ok(stats)
+}
+
+/// 各列最常见的值（按列顺序）
+///
+/// 把各列转换为文本后逆透视为（列序号，值），在一个查询中分组计数并取每列的前 [`TOP_K`] 个。
+/// 逆透视时跳过空值。
+fn top_values(conn: &Connection, relation: &str, columns: &[&ColumnInfo]) -> Result<Vec<Vec<ValueCount>>> {
+    let mut top_values = vec![Vec::new(); columns.len()];
+    if columns.is_empty() {
+        return Ok(top_values);
+    }
+    let sql = top_values_sql(relation, columns);
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)? as usize,
+                ValueCount {
+                    value: row.get(1)?,
+                    count: row.get::<_, i64>(2)? as u64,
+                },
+            ))
+        })?
+        .collect::<duckdb::Result<Vec<_>>>()?;
+    for (index, value) in rows {
+        top_values[index].push(value);
+    }
+    Ok(top_values)
+}
+
+fn top_values_sql(relation: &str, columns: &[&ColumnInfo]) -> String {
+    let values = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| format!("CAST({} AS VARCHAR) AS \"{}\"", quote_ident(&column.name), i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "SELECT CAST(col AS BIGINT) AS col, val, n FROM (\
+         SELECT col, val, COUNT(*) AS n FROM (UNPIVOT (SELECT {values} FROM {relation}) \
+         ON COLUMNS(*) INTO NAME col VALUE val) GROUP BY col, val) \
+         QUALIFY row_number() OVER (PARTITION BY col ORDER BY n DESC, val) <= {TOP_K} \
+         ORDER BY 1, n DESC, val"
+    )
+}
+
+/// 决定计算哪些统计量的列类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Numeric,
+    Text,
+    /// DATE、TIMESTAMP 等，可以按时间单位分桶
+    Temporal,
+    /// LIST、STRUCT、MAP 等嵌套类型，不计算最小/最大值
+    Nested,
+    Other,
+}
+
+impl ColumnKind {
+    fn of(data_type: &str) -> Self {
+        let upper = data_type.to_uppercase();
+        if upper.ends_with(']') || ["STRUCT", "MAP", "UNION"].iter().any(|t| upper.starts_with(t)) {
+            return ColumnKind::Nested;
+        }
+        match upper.as_str() {
+            "TINYINT" | "SMALLINT" | "INTEGER" | "BIGINT" | "HUGEINT" | "UTINYINT" | "USMALLINT" | "UINTEGER"
+            | "UBIGINT" | "UHUGEINT" | "FLOAT" | "DOUBLE" => ColumnKind::Numeric,
+            "VARCHAR" => ColumnKind::Text,
+            _ if upper.starts_with("DECIMAL") => ColumnKind::Numeric,
+            _ if upper == "DATE" || upper.starts_with("TIMESTAMP") => ColumnKind::Temporal,
+            _ => ColumnKind::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_kind() {
+        assert_eq!(ColumnKind::of("BIGINT"), ColumnKind::Numeric);
+        assert_eq!(ColumnKind::of("DECIMAL(18,3)"), ColumnKind::Numeric);
+        assert_eq!(ColumnKind::of("VARCHAR"), ColumnKind::Text);
+        assert_eq!(ColumnKind::of("INTEGER[]"), ColumnKind::Nested);
+        assert_eq!(ColumnKind::of("STRUCT(a INTEGER)"), ColumnKind::Nested);
+        assert_eq!(ColumnKind::of("TIMESTAMP WITH TIME ZONE"), ColumnKind::Temporal);
+        assert_eq!(ColumnKind::of("TIME"), ColumnKind::Other);
+    }
+
+    #[test]
+    fn test_top_values_sql() {
+        let column = |name: &str| ColumnInfo {
+            name: name.to_string(),
+            data_type: "VARCHAR".to_string(),
+            nullable: true,
+        };
+        let (city, note) = (column("city"), column("note \"x\""));
+        assert_eq!(
+            top_values_sql("t", &[&city, &note]),
+            "SELECT CAST(col AS BIGINT) AS col, val, n FROM (\
+             SELECT col, val, COUNT(*) AS n FROM (UNPIVOT (SELECT CAST(\"city\" AS VARCHAR) AS \"0\", \
+             CAST(\"note \"\"x\"\"\" AS VARCHAR) AS \"1\" FROM t) \
+             ON COLUMNS(*) INTO NAME col VALUE val) GROUP BY col, val) \
+             QUALIFY row_number() OVER (PARTITION BY col ORDER BY n DESC, val) <= 10 \
+             ORDER BY 1, n DESC, val"
+        );
+    }
+
+    #[test]
+    fn test_numeric_bins_sql() {
+        let equal_width = Binning::EqualWidth { bins: 4 };
+        assert_eq!(numeric_bins_sql(&[1.0, 1.0], equal_width), "SELECT g, 0 AS bin FROM src");
+        assert_eq!(
+            numeric_bins_sql(&[0.0, 0.5, 1.0, 1.5, 2.0], equal_width),
+            "SELECT g, LEAST(CAST(FLOOR((v - 0.0) / 0.5) AS BIGINT), 3) AS bin FROM src"
+        );
+        assert_eq!(
+            numeric_bins_sql(&[0.0, 0.5, 1.0, 2.0], Binning::Quantile { bins: 3 }),
+            "SELECT src.g, b.bin FROM src ASOF JOIN \
+             (SELECT unnest(CAST([0.0, 0.5, 1.0] AS DOUBLE[])) AS lower, unnest(range(3)) AS bin) b ON src.v >= b.lower"
+        );
+    }
+}
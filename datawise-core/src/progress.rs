@@ -0,0 +1,178 @@
+//! DuckDB 查询进度
+//!
+//! duckdb crate 没有暴露查询进度，也没有暴露 `duckdb::Connection` 的原生连接句柄，
+//! 这里通过 C API 在单独的原生连接上执行语句，并在执行期间轮询 `duckdb_query_progress`。
+//! DuckDB 扫描 CSV、JSON、Parquet 文件时的进度按已读取的字节数计算。
+//!
+//! 单独的连接看不到共享连接的会话状态：临时表和临时视图、`SET` 设置的选项、
+//! 未提交的事务。附加的数据库和已加载的扩展属于数据库实例，在所有连接中可见。
+//! 依赖会话状态的语句（见 [`needs_session`]）应在共享连接上执行，不报告进度。
+
+use crate::sql::{tokenize, Token};
+use anyhow::{bail, Context, Result};
+use duckdb::ffi;
+use std::ffi::{CStr, CString};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// 执行期间轮询查询进度的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 导入、导出进度回调的最小间隔
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// DuckDB 数据库句柄
+///
+/// 可以打开 `duckdb::Connection`，也可以打开用于跟踪进度的原生连接，
+/// 两者访问同一个数据库。
+pub struct Database {
+    raw: ffi::duckdb_database,
+}
+
+// DuckDB 的数据库对象可以在线程间共享，每个连接各自使用
+unsafe impl Send for Database {}
+unsafe impl Sync for Database {}
+
+impl Database {
+    /// 打开内存数据库
+    pub fn open_in_memory() -> Result<Self> {
+        let mut raw = std::ptr::null_mut();
+        let state = unsafe { ffi::duckdb_open(std::ptr::null(), &mut raw) };
+        if state != ffi::DuckDBSuccess {
+            bail!("Failed to open DuckDB in-memory database");
+        }
+        Ok(Self { raw })
+    }
+
+    /// 打开一个 `duckdb::Connection`
+    ///
+    /// 连接不接管数据库句柄，句柄在 `Database` drop 时关闭；
+    /// 已打开的连接在此之后仍然有效（DuckDB 内部按引用计数释放数据库）。
+    pub fn connect(&self) -> Result<duckdb::Connection> {
+        unsafe { duckdb::Connection::open_from_raw(self.raw) }.context("Failed to connect to DuckDB database")
+    }
+
+    /// 执行语句，执行期间以完成比例（0.0-1.0）调用 `on_progress`，返回语句影响的行数
+    ///
+    /// 语句在单独的连接上执行（自动提交），看不到共享连接的会话状态（见模块文档）；
+    /// DuckDB 无法估计进度时不回调。
+    /// `cancel` 被置位时中断语句，语句返回错误。
+    pub fn execute_with_progress(
+        &self,
+        sql: &str,
+        cancel: Option<&AtomicBool>,
+        on_progress: &mut dyn FnMut(f64),
+    ) -> Result<u64> {
+        let conn = RawConnection::connect(self.raw)?;
+        conn.query("SET enable_progress_bar = true; SET enable_progress_bar_print = false")?;
+
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            let worker = scope.spawn(|| {
+                let result = conn.query(sql);
+                done.store(true, Ordering::SeqCst);
+                result
+            });
+
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+                if done.load(Ordering::SeqCst) {
+                    break;
+                }
+                if cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+                    unsafe { ffi::duckdb_interrupt(conn.raw) };
+                    continue;
+                }
+                let progress = unsafe { ffi::duckdb_query_progress(conn.raw) };
+                if progress.percentage >= 0.0 {
+                    on_progress((progress.percentage / 100.0).min(1.0));
+                }
+            }
+
+            worker.join().expect("DuckDB query thread panicked")
+        })
+    }
+}
+
+/// 语句是否依赖共享连接的会话状态，不能在单独的连接上执行
+///
+/// 共享连接处于显式事务中，或者语句中出现了临时表、临时视图的名称时返回 true。
+/// `SET` 设置的会话选项无法检测，在单独的连接上使用默认值。
+pub fn needs_session(conn: &duckdb::Connection, sql: &str) -> Result<bool> {
+    if !conn.is_autocommit() {
+        return Ok(true);
+    }
+    let mut stmt = conn.prepare(
+        "SELECT lower(table_name) FROM duckdb_tables() WHERE temporary \
+         UNION SELECT lower(view_name) FROM duckdb_views() WHERE temporary AND NOT internal",
+    )?;
+    let temporary = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<duckdb::Result<Vec<_>>>()
+        .context("Failed to list temporary tables")?;
+    if temporary.is_empty() {
+        return Ok(false);
+    }
+    Ok(tokenize(sql).iter().any(|token| match token {
+        Token::Word(name) | Token::Quoted(name) => temporary.contains(name),
+        _ => false,
+    }))
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        unsafe { ffi::duckdb_close(&mut self.raw) };
+    }
+}
+
+/// 原生 DuckDB 连接
+struct RawConnection {
+    raw: ffi::duckdb_connection,
+}
+
+// 查询在工作线程中执行，`duckdb_query_progress`、`duckdb_interrupt` 可以从其他线程并发调用
+unsafe impl Send for RawConnection {}
+unsafe impl Sync for RawConnection {}
+
+impl RawConnection {
+    fn connect(database: ffi::duckdb_database) -> Result<Self> {
+        let mut raw = std::ptr::null_mut();
+        let state = unsafe { ffi::duckdb_connect(database, &mut raw) };
+        if state != ffi::DuckDBSuccess {
+            bail!("Failed to connect to DuckDB database");
+        }
+        Ok(Self { raw })
+    }
+
+    /// 执行语句，返回影响的行数（`CREATE TABLE AS`、`COPY` 等语句返回写入的行数）
+    fn query(&self, sql: &str) -> Result<u64> {
+        let sql = CString::new(sql).context("SQL contains a NUL byte")?;
+        unsafe {
+            let mut result: ffi::duckdb_result = std::mem::zeroed();
+            let state = ffi::duckdb_query(self.raw, sql.as_ptr(), &mut result);
+            let rows = ffi::duckdb_rows_changed(&mut result);
+            let error = if state == ffi::DuckDBSuccess {
+                None
+            } else {
+                let message = ffi::duckdb_result_error(&mut result);
+                Some(if message.is_null() {
+                    "Unknown DuckDB error".to_string()
+                } else {
+                    CStr::from_ptr(message).to_string_lossy().into_owned()
+                })
+            };
+            ffi::duckdb_destroy_result(&mut result);
+
+            match error {
+                Some(message) => bail!(message),
+                None => Ok(rows),
+            }
+        }
+    }
+}
+
+impl Drop for RawConnection {
+    fn drop(&mut self) {
+        unsafe { ffi::duckdb_disconnect(&mut self.raw) };
+    }
+}
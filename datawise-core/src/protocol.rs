@@ -4,6 +4,8 @@
 //! 所有数据结构都支持 serde 序列化，确保跨语言兼容性。
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
 
 /// UI 事件 - Core 向 UI 推送的事件
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -26,10 +28,18 @@ pub enum EventKind {
         pct: u8,
         /// 已处理字节数
         bytes_processed: u64,
-        /// 总字节数
+        /// 总字节数（导出时是按已写入字节数和完成比例估算的值，导出完成时才准确）
         total_bytes: u64,
-        /// 预计剩余时间（秒）
+        /// 预计剩余时间（秒），按平均吞吐量估算
         eta_seconds: Option<u32>,
+        /// 已处理行数（导出时提供；此时 `bytes_processed` 为已写入的字节数）
+        ///
+        /// 用 DuckDB 的 `COPY` 导出（CSV、Parquet）时行数在完成前未知，只有最后一次进度提供。
+        #[serde(default)]
+        rows_processed: Option<u64>,
+        /// 总行数（导出时提供，未知时同上）
+        #[serde(default)]
+        total_rows: Option<u64>,
     },
     
     /// 任务完成
@@ -40,10 +50,53 @@ pub enum EventKind {
         row_count: usize,
         /// 结果列数
         column_count: usize,
-        /// 数据摘要（前 10 行的 JSON）
+        /// 数据摘要：查询为前 10 行的 JSON，其他命令为结果类型的 JSON（见各命令的文档）
+        ///
+        /// 结果类型都实现了 `Deserialize`，客户端可以直接反序列化，例如目录命令：
+        ///
+        /// - `ListDatabases`：`Vec<DatabaseInfo>`，如 `[{"name":"memory","kind":"duckdb","path":null,"read_only":false}]`
+        /// - `ListSchemas`：`Vec<SchemaInfo>`，如 `[{"database":"memory","name":"main","table_count":2}]`
+        /// - `ListTables`、`DescribeTable`：`Vec<TableInfo>`、`TableInfo`，如
+        ///   `{"database":"memory","schema":"main","name":"sales","table_type":"BASE TABLE","row_count":3,
+        ///   "columns":[{"name":"id","data_type":"INTEGER","nullable":true}],
+        ///   "source":{"File":{"paths":["sales.csv"],"fmt":"Csv"}}}`（`source` 没有来源时为 `null`）
+        /// - `ManageTable`：`TableChange`，如 `{"table":{...},"rows_affected":3,
+        ///   "change":{"created":[],"dropped":["memory.main.sales"],"altered":[]},"dependent_views":[]}`
         preview: String,
+        /// 容错导入时被拒绝的行数（未开启容错导入时为 `None`）
+        #[serde(default)]
+        rejected_rows: Option<u64>,
+        /// 查询结果是否来自缓存（未开启结果缓存或查询不可缓存时为 `None`）
+        #[serde(default)]
+        cache: Option<CacheStatus>,
     },
     
+    /// 目录发生变化（表名为 `数据库.schema.表名`），在任务的 `Finished` 之前发送
+    ///
+    /// 导入、附加数据库、改变目录的 SQL（DDL、增删改数据）和表管理命令都会发送，
+    /// 所有订阅者都能收到，UI 可以据此刷新表列表。只包括本地的表，不包括远程数据库中的表。
+    ///
+    /// 数据修改按表的估计行数，以及 `INSERT`/`UPDATE`/`DELETE`/`TRUNCATE` 语句的目标表检测；
+    /// 通过其他方式（如宏、函数）修改数据且行数不变时检测不到。
+    CatalogChanged {
+        /// 新建的表和视图
+        created: Vec<String>,
+        /// 删除的表和视图
+        dropped: Vec<String>,
+        /// 结构或数据被修改的表和视图
+        altered: Vec<String>,
+    },
+
+    /// 导入到附加了数据质量规则的表后自动检查的结果，在任务的 `Finished` 之前发送
+    Validated {
+        /// 表名
+        table: String,
+        /// 是否所有规则都通过
+        passed: bool,
+        /// 检查结果
+        report: ValidationReport,
+    },
+
     /// 任务失败
     Error(String),
 }
@@ -77,6 +130,9 @@ pub enum CmdType {
         /// 是否覆盖现有表（默认 false）
         #[serde(default)]
         overwrite: bool,
+        /// 导入选项（多文件、文件名列、Hive 分区等）
+        #[serde(default)]
+        options: ImportOptions,
     },
     
     /// 导出数据
@@ -87,8 +143,153 @@ pub enum CmdType {
         path: String,
         /// 导出格式
         fmt: FileFmt,
+        /// 是否覆盖已存在的目标文件（默认 false，目标存在时报错）
+        #[serde(default)]
+        overwrite: bool,
+        /// 导出选项（Parquet 压缩、行组大小、分区等）
+        #[serde(default)]
+        options: ExportOptions,
     },
     
+    /// 导出 Excel 工作簿，每个表或查询写入一个工作表
+    ExportWorkbook {
+        /// 导出路径
+        path: String,
+        /// 工作表列表（按顺序写入）
+        sheets: Vec<SheetExport>,
+        /// 超过 Excel 行数限制时是否拆分到多个工作表（默认 false，即报错）
+        #[serde(default)]
+        split_large_sheets: bool,
+        /// 是否覆盖已存在的目标文件（默认 false，目标存在时报错）
+        #[serde(default)]
+        overwrite: bool,
+    },
+    
+    /// 打开 SQLite 数据库（`.sqlite`/`.db`）：列出表、附加或导入
+    OpenSqlite {
+        /// 数据库文件路径
+        path: String,
+        /// 要执行的操作
+        action: SqliteAction,
+    },
+    
+    /// 连接 PostgreSQL/MySQL 数据库：列出 schema 和表、执行下推查询或导入
+    Database {
+        /// 连接配置
+        profile: ConnectionProfile,
+        /// 要执行的操作
+        action: DatabaseAction,
+    },
+    
+    /// 列出工作区中的数据库（包括附加的数据库），结果为 [`DatabaseInfo`](crate::DatabaseInfo) 的 JSON 数组
+    ListDatabases,
+    
+    /// 列出 schema，结果为 [`SchemaInfo`](crate::SchemaInfo) 的 JSON 数组
+    ListSchemas {
+        /// 只列出该数据库中的 schema（默认所有数据库）
+        #[serde(default)]
+        database: Option<String>,
+    },
+    
+    /// 列出表和视图（含列信息和数据来源），结果为 [`TableInfo`](crate::TableInfo) 的 JSON 数组
+    ListTables {
+        /// 只列出该数据库中的表（默认所有数据库）
+        #[serde(default)]
+        database: Option<String>,
+        /// 只列出该 schema 中的表（默认所有 schema）
+        #[serde(default)]
+        schema: Option<String>,
+    },
+    
+    /// 描述单张表或视图（精确行数、列信息和数据来源），结果为 [`TableInfo`](crate::TableInfo) 的 JSON
+    DescribeTable {
+        /// 表名，可以带 schema 或数据库前缀
+        name: String,
+    },
+    
+    /// 计算表的列画像，逐列报告进度，结果为 [`TableProfile`](crate::TableProfile) 的 JSON
+    ProfileTable {
+        /// 表名，可以带 schema 或数据库前缀
+        table: String,
+        /// 要计算的列（为空时计算所有列）
+        #[serde(default)]
+        columns: Vec<String>,
+        /// 只对随机抽样的最多这么多行计算（默认整表）
+        #[serde(default)]
+        sample: Option<u64>,
+    },
+    
+    /// 计算列的分布（直方图），可按类别列分组，结果为 [`Histogram`](crate::Histogram) 的 JSON
+    Histogram {
+        /// 表名，可以带 schema 或数据库前缀
+        table: String,
+        /// 数值列或日期/时间戳列
+        column: String,
+        /// 分箱方式（默认 20 个等宽分箱）
+        #[serde(default)]
+        binning: Binning,
+        /// 分组的类别列（可选）
+        #[serde(default)]
+        group_by: Option<String>,
+    },
+    
+    /// 管理表：重命名、删除、复制、清空，或把查询保存为视图/表，结果为 [`TableChange`](crate::TableChange) 的 JSON
+    ManageTable {
+        /// 要执行的操作
+        action: TableAction,
+    },
+
+    /// 按键列对比两张表：新增、删除和修改的行写入结果表，结果为 [`TableDiff`](crate::TableDiff) 的 JSON
+    DiffTables {
+        /// 旧表，可以带 schema 或数据库前缀
+        left: String,
+        /// 新表，可以带 schema 或数据库前缀
+        right: String,
+        /// 键列，在两张表中都须唯一
+        key_columns: Vec<String>,
+    },
+
+    /// 管理数据质量规则：附加到表、列出、从文件加载或保存到文件
+    Rules {
+        /// 要执行的操作
+        action: RulesAction,
+    },
+
+    /// 按数据质量规则检查表，结果为 [`ValidationReport`](crate::ValidationReport) 的 JSON
+    Validate {
+        /// 表名，可以带 schema 或数据库前缀
+        table: String,
+        /// 要检查的规则（为空时使用附加到该表的规则）
+        #[serde(default)]
+        rules: Vec<Rule>,
+    },
+
+    /// 查询历史：打开历史文件、列出、搜索、重新执行和置顶
+    History {
+        /// 要执行的操作
+        action: HistoryAction,
+    },
+
+    /// 查询片段库：打开、列出、保存、修改、删除、执行、导入和导出命名查询
+    Snippets {
+        /// 要执行的操作
+        action: SnippetAction,
+    },
+
+    /// 工作簿：创建、打开、保存和执行 SQL 单元格
+    Workbook {
+        /// 工作簿文件路径（JSON）
+        path: String,
+        /// 要执行的操作
+        action: WorkbookAction,
+    },
+
+    /// 查询结果缓存：开启/关闭、清空和查看统计，结果为 [`CacheStats`](crate::CacheStats) 的 JSON
+    Cache {
+        /// 要执行的操作
+        action: CacheAction,
+    },
+
     /// 取消任务
     Cancel {
         /// 要取消的任务 ID
@@ -96,6 +297,805 @@ pub enum CmdType {
     },
 }
 
+/// 导出结果摘要，以 JSON 放在导出任务的 `Finished.preview` 中
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// 导出的行数
+    pub rows_exported: u64,
+    /// 导出的列数
+    pub column_count: usize,
+    /// 输出文件大小（字节）
+    pub bytes_written: u64,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+/// 对 SQLite 数据库执行的操作
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SqliteAction {
+    /// 列出数据库中的表，结果（表名、行数、列类型）以 JSON 放在 `Finished.preview` 中
+    ListTables,
+    /// 以只读方式附加到工作区，之后可以用 `<alias>.<表名>` 直接查询
+    Attach {
+        /// 数据库别名（默认使用文件名）
+        alias: Option<String>,
+    },
+    /// 把表导入到工作区，表名与 SQLite 中的表名相同
+    Import {
+        /// 要导入的表（为空时导入所有表）
+        #[serde(default)]
+        tables: Vec<String>,
+        /// 是否覆盖现有表（默认 false）
+        #[serde(default)]
+        overwrite: bool,
+    },
+}
+
+/// 表管理操作，表名可以带 schema 或数据库前缀
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TableAction {
+    /// 重命名表或视图
+    Rename {
+        /// 表名
+        name: String,
+        /// 新表名（不带 schema，表留在原来的 schema 中）
+        new_name: String,
+    },
+    /// 删除表或视图，结果中带有删除的行数和引用它的视图
+    Drop {
+        /// 表名
+        name: String,
+        /// 只预览删除的行数和引用它的视图，不删除（默认 false）
+        #[serde(default)]
+        dry_run: bool,
+    },
+    /// 把表（或视图的结果）复制为新表
+    Duplicate {
+        /// 表名
+        name: String,
+        /// 新表名
+        new_name: String,
+        /// 只复制列，不复制数据（默认 false）
+        #[serde(default)]
+        schema_only: bool,
+    },
+    /// 清空表中的数据，保留表结构
+    Truncate {
+        /// 表名
+        name: String,
+        /// 只预览将被删除的行数，不修改表（默认 false）
+        #[serde(default)]
+        dry_run: bool,
+    },
+    /// 把查询保存为视图或表
+    SaveQuery {
+        /// 查询 SQL
+        sql: String,
+        /// 视图或表名
+        name: String,
+        /// 保存为表而不是视图（默认 false）
+        #[serde(default)]
+        materialize: bool,
+        /// 是否替换同名的表或视图（默认 false）
+        #[serde(default)]
+        overwrite: bool,
+    },
+}
+
+/// 对 PostgreSQL/MySQL 数据库执行的操作
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DatabaseAction {
+    /// 列出 schema（MySQL 中即数据库），结果以 JSON 数组放在 `Finished.preview` 中
+    ListSchemas,
+    /// 列出表和视图，结果以 JSON 数组放在 `Finished.preview` 中
+    ListTables {
+        /// 只列出该 schema 中的表（默认所有 schema）
+        #[serde(default)]
+        schema: Option<String>,
+    },
+    /// 在远程服务器上执行查询（整体下推），返回预览
+    Query {
+        /// 远程数据库方言的 SQL
+        sql: String,
+    },
+    /// 在远程服务器上执行查询，并把结果导入到工作区的表中
+    Import {
+        /// 远程数据库方言的 SQL（导入整张表时为 `SELECT * FROM schema.table`）
+        sql: String,
+        /// 导入到的表名
+        table_name: String,
+        /// 是否覆盖现有表（默认 false）
+        #[serde(default)]
+        overwrite: bool,
+    },
+}
+
+/// 数据库类型
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatabaseKind {
+    /// PostgreSQL
+    Postgres,
+    /// MySQL / MariaDB
+    MySql,
+}
+
+impl DatabaseKind {
+    /// 默认端口
+    pub fn default_port(&self) -> u16 {
+        match self {
+            DatabaseKind::Postgres => 5432,
+            DatabaseKind::MySql => 3306,
+        }
+    }
+}
+
+/// 数据库连接配置
+///
+/// `Debug` 输出中隐藏密码，避免写入日志。
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ConnectionProfile {
+    /// 配置名，同时作为附加到工作区的数据库别名
+    pub name: String,
+    /// 数据库类型
+    pub kind: DatabaseKind,
+    /// 主机名
+    pub host: String,
+    /// 端口（默认使用数据库类型的默认端口）
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// 用户名
+    pub user: String,
+    /// 密码（为空时使用 `PGPASSWORD` 等环境变量或无密码连接）
+    #[serde(default)]
+    pub password: Option<String>,
+    /// 数据库名
+    pub database: String,
+}
+
+impl ConnectionProfile {
+    /// 生成 `key=value` 形式的连接字符串
+    ///
+    /// PostgreSQL 使用 libpq 的关键字（`dbname`），MySQL 使用 DuckDB mysql 扩展的关键字（`database`）。
+    /// 含空格或引号的值用单引号括起并转义。
+    pub fn connection_string(&self) -> String {
+        let database_key = match self.kind {
+            DatabaseKind::Postgres => "dbname",
+            DatabaseKind::MySql => "database",
+        };
+        let port = self.port.unwrap_or(self.kind.default_port()).to_string();
+
+        let mut params = vec![
+            ("host", self.host.as_str()),
+            ("port", port.as_str()),
+            ("user", self.user.as_str()),
+            (database_key, self.database.as_str()),
+        ];
+        if let Some(password) = &self.password {
+            params.push(("password", password.as_str()));
+        }
+
+        params
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, quote_conn_value(value)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl std::fmt::Debug for ConnectionProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionProfile")
+            .field("name", &self.name)
+            .field("kind", &self.kind)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("database", &self.database)
+            .finish()
+    }
+}
+
+fn quote_conn_value(value: &str) -> String {
+    if !value.is_empty() && !value.contains([' ', '\'', '\\', '=']) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Excel 工作簿中的一个工作表
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SheetExport {
+    /// 工作表名（非法字符会被替换，过长会被截断）
+    pub sheet_name: String,
+    /// 表名或 SQL 查询
+    pub source: String,
+    /// `source` 是否是 SQL 查询（默认 false，即表名）
+    #[serde(default)]
+    pub is_query: bool,
+}
+
+/// 导入选项
+///
+/// `ImportFile.path` 可以是单个文件、glob 模式（如 `sales_2024_*.csv`）或目录，
+/// 所有匹配的文件会被导入到同一张表中。
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// 额外的文件路径（或 glob 模式），与 `path` 一起导入到同一张表
+    pub extra_paths: Vec<String>,
+    /// 是否添加 `filename` 列，记录每一行的来源文件
+    pub filename_column: bool,
+    /// 是否从 Hive 风格的目录（`key=value/`）中提取分区列
+    pub hive_partitioning: bool,
+    /// 是否按列名合并 schema 不一致的文件
+    pub union_by_name: bool,
+    /// 要从 zip 压缩包中导入的成员路径（为空时导入所有与格式匹配的成员）
+    pub archive_members: Vec<String>,
+    /// 要导入的 Excel 工作表名（默认第一个工作表）
+    pub sheet: Option<String>,
+    /// 要导入的 Excel 单元格区域（如 `B2:F100`），区域的第一行作为表头
+    pub cell_range: Option<String>,
+    /// 是否把每个 Excel 工作表导入为单独的表（表名为 `<表名>_<工作表名>`）
+    pub all_sheets: bool,
+    /// CSV 方言（为 `None` 时由 DuckDB 自动检测）
+    pub csv: Option<CsvDialect>,
+    /// CSV 容错导入：跳过无法解析的行，把它们连同行号和原因写入指定位置（为 `None` 时遇到错误行导入失败）
+    pub rejects: Option<RejectsOutput>,
+}
+
+/// 容错导入时被拒绝的行的去向
+///
+/// 每个错误一行，列为 `file`、`line`、`column_name`、`error_type`、`reason`、`content`（原始行）。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RejectsOutput {
+    /// 写入 `<表名>_rejects` 表（替换已有的同名表）
+    Table,
+    /// 写入 CSV 文件（覆盖已有文件）
+    File {
+        /// 文件路径
+        path: String,
+    },
+}
+
+/// 导出选项
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// CSV 方言（其他格式忽略）
+    pub csv: CsvDialect,
+    /// Parquet 导出选项（其他格式忽略）
+    pub parquet: ParquetOptions,
+}
+
+/// CSV 方言
+///
+/// 导入和导出共用，用导入时的方言导出即可还原原始文件的格式。
+/// `force_quote`、`line_ending`、`bom` 只用于导出，导入时自动识别。
+/// 分隔符、引号和 NULL 字符串未设置时，导入由 DuckDB 自动识别，导出使用 `,`、`"` 和空字符串。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct CsvDialect {
+    /// 分隔符（ASCII 字符，如 `,`、`;`、`\t`）
+    pub delimiter: Option<char>,
+    /// 第一行是否为表头
+    pub header: bool,
+    /// 引号字符（ASCII 字符）
+    pub quote: Option<char>,
+    /// 是否给所有字段加引号
+    pub force_quote: bool,
+    /// 表示 NULL 的字符串
+    pub null_string: Option<String>,
+    /// 换行符
+    pub line_ending: LineEnding,
+    /// 是否写入 UTF-8 BOM（便于 Excel 识别编码，仅 UTF-8）
+    pub bom: bool,
+    /// 文本编码
+    pub encoding: TextEncoding,
+    /// 日期格式（strftime 格式，如 `%d/%m/%Y`）
+    pub date_format: Option<String>,
+    /// 时间戳格式（strftime 格式，如 `%Y-%m-%d %H:%M:%S`）
+    pub timestamp_format: Option<String>,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            header: true,
+            quote: None,
+            force_quote: false,
+            null_string: None,
+            line_ending: LineEnding::Lf,
+            bom: false,
+            encoding: TextEncoding::Utf8,
+            date_format: None,
+            timestamp_format: None,
+        }
+    }
+}
+
+/// 换行符
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+/// 文本编码
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// UTF-8
+    #[default]
+    Utf8,
+    /// GBK（简体中文 Windows 的默认编码）
+    Gbk,
+}
+
+/// Parquet 导出选项
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ParquetOptions {
+    /// 压缩编码（默认 snappy）
+    pub compression: ParquetCompression,
+    /// 每个行组的行数（默认由 DuckDB 决定）
+    pub row_group_size: Option<u64>,
+    /// 分区列，非空时导出路径是目录，按 Hive 风格（`key=value/`）写入多个文件
+    pub partition_by: Vec<String>,
+    /// 写入文件页脚的键值元数据
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// 直方图的分箱方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binning {
+    /// 数值列：等宽分箱
+    EqualWidth {
+        /// 分箱数
+        bins: u32,
+    },
+    /// 数值列：按分位数分箱，每箱的行数大致相同（重复值多时分箱数可能减少）
+    Quantile {
+        /// 分箱数
+        bins: u32,
+    },
+    /// 日期/时间戳列：按时间单位分桶
+    Time {
+        /// 时间单位
+        unit: TimeBucket,
+    },
+}
+
+impl Default for Binning {
+    fn default() -> Self {
+        Binning::EqualWidth { bins: 20 }
+    }
+}
+
+/// 时间分桶的单位
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeBucket {
+    /// 天
+    Day,
+    /// 周（从周一开始）
+    Week,
+    /// 月
+    Month,
+}
+
+impl TimeBucket {
+    /// DuckDB `date_trunc`/`date_diff` 的单位名
+    pub fn sql_unit(&self) -> &'static str {
+        match self {
+            TimeBucket::Day => "day",
+            TimeBucket::Week => "week",
+            TimeBucket::Month => "month",
+        }
+    }
+}
+
+/// 数据质量规则
+///
+/// 规则文件（JSON/YAML）中用 `rule` 字段区分规则类型，例如：
+///
+/// ```yaml
+/// - rule: not_null
+///   column: id
+/// - rule: range
+///   column: amount
+///   min: 0
+/// ```
+///
+/// 列中的空值只由 `not_null` 检查，其他列规则忽略空值。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    /// 列不能为空
+    NotNull {
+        column: String,
+    },
+    /// 列（或多列组合）的值不能重复
+    Unique {
+        columns: Vec<String>,
+    },
+    /// 列的值在 `[min, max]` 范围内（数值、日期或字符串，缺省的一端不检查）
+    Range {
+        column: String,
+        #[serde(default)]
+        min: Option<serde_json::Value>,
+        #[serde(default)]
+        max: Option<serde_json::Value>,
+    },
+    /// 列的值（文本形式）完整匹配正则表达式
+    Regex {
+        column: String,
+        pattern: String,
+    },
+    /// 列的值必须是给定的值之一
+    AllowedValues {
+        column: String,
+        values: Vec<serde_json::Value>,
+    },
+    /// 列的值必须出现在另一张表的列中（类似外键）
+    References {
+        column: String,
+        /// 被引用的表，可以带 schema 或数据库前缀
+        table: String,
+        /// 被引用的列（默认与 `column` 同名）
+        #[serde(default)]
+        referenced_column: Option<String>,
+    },
+    /// 表的行数在 `[min, max]` 范围内（缺省的一端不检查）
+    RowCount {
+        #[serde(default)]
+        min: Option<u64>,
+        #[serde(default)]
+        max: Option<u64>,
+    },
+}
+
+/// 对查询历史执行的操作
+///
+/// 列表结果为 [`HistoryEntry`](crate::HistoryEntry) 的 JSON 数组，最新的在前。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum HistoryAction {
+    /// 打开 JSON 历史文件（不存在时创建），之后的记录写入该文件
+    Open {
+        /// 历史文件路径
+        path: String,
+    },
+    /// 列出最近的记录
+    List {
+        /// 只列出置顶的记录（默认 false）
+        #[serde(default)]
+        pinned_only: bool,
+        /// 最多返回的条数（默认 0，即不限制）
+        #[serde(default)]
+        limit: usize,
+    },
+    /// 搜索 SQL 中包含给定文本（不区分大小写）的记录
+    Search {
+        /// 要搜索的文本
+        text: String,
+        /// 最多返回的条数（默认 0，即不限制）
+        #[serde(default)]
+        limit: usize,
+    },
+    /// 重新执行记录中的 SQL，结果与 `ExecuteSql` 相同，并记录为新的一条
+    Rerun {
+        /// 记录 ID
+        id: u64,
+    },
+    /// 置顶或取消置顶，结果为该记录的 JSON
+    Pin {
+        /// 记录 ID
+        id: u64,
+        /// 是否置顶
+        pinned: bool,
+    },
+}
+
+/// 查询片段，SQL 中的 `{{参数名}}` 在执行时代入参数值
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Snippet {
+    /// 名称，在片段库中唯一（不区分大小写）
+    pub name: String,
+    /// 文件夹，用 `/` 分隔层级（为空时在根目录）
+    #[serde(default)]
+    pub folder: String,
+    /// 说明
+    #[serde(default)]
+    pub description: String,
+    /// 标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// SQL，可以包含 `{{参数名}}` 占位符
+    pub sql: String,
+    /// SQL 中的参数名（按首次出现的顺序，保存时从 SQL 中提取）
+    #[serde(default)]
+    pub parameters: Vec<String>,
+    /// 最后修改时间（UTC，RFC 3339，保存时设置）
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// 对查询片段库执行的操作
+///
+/// 列表结果为 [`Snippet`] 的 JSON 数组，按文件夹和名称排序。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum SnippetAction {
+    /// 打开 JSON 片段库文件（不存在时创建），替换当前的片段，之后的修改写回该文件
+    Open {
+        /// 片段库文件路径
+        path: String,
+    },
+    /// 列出片段
+    List {
+        /// 只列出该文件夹及其子文件夹中的片段
+        #[serde(default)]
+        folder: Option<String>,
+        /// 只列出带该标签的片段（不区分大小写）
+        #[serde(default)]
+        tag: Option<String>,
+    },
+    /// 保存新片段（同名片段已存在时报错），结果为保存的片段的 JSON
+    Save {
+        /// 片段（`parameters` 和 `updated_at` 保存时自动设置）
+        snippet: Snippet,
+    },
+    /// 替换已有的片段（可以改名），结果为保存的片段的 JSON
+    Update {
+        /// 原来的名称
+        name: String,
+        /// 新的片段
+        snippet: Snippet,
+    },
+    /// 删除片段
+    Delete {
+        /// 片段名称
+        name: String,
+    },
+    /// 代入参数值后执行片段，结果与 `ExecuteSql` 相同
+    Execute {
+        /// 片段名称
+        name: String,
+        /// 参数值：字符串、数字、布尔值或 null
+        #[serde(default)]
+        params: BTreeMap<String, serde_json::Value>,
+    },
+    /// 把片段导出为 JSON 包，用于分享
+    Export {
+        /// JSON 包路径
+        path: String,
+        /// 要导出的片段名称（为空时导出全部）
+        #[serde(default)]
+        names: Vec<String>,
+    },
+    /// 从 JSON 包导入片段
+    Import {
+        /// JSON 包路径
+        path: String,
+        /// 同名片段存在时替换（默认 false，即报错且不导入）
+        #[serde(default)]
+        overwrite: bool,
+    },
+}
+
+/// 工作簿：按顺序排列的 SQL 单元格和 Markdown 单元格，保存为 JSON 文件
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Workbook {
+    #[serde(default)]
+    pub cells: Vec<WorkbookCell>,
+}
+
+/// 工作簿单元格
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WorkbookCell {
+    /// 单元格 ID，在工作簿中唯一（为空时保存时自动生成）
+    #[serde(default)]
+    pub id: String,
+    /// 单元格类型
+    pub kind: CellKind,
+    /// SQL 或 Markdown 文本
+    pub source: String,
+    /// 上次执行的结果摘要（只有 SQL 单元格有）
+    #[serde(default)]
+    pub result: Option<CellResult>,
+}
+
+/// 单元格类型
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CellKind {
+    /// SQL 查询
+    Sql,
+    /// Markdown 文本（不执行）
+    Markdown,
+}
+
+/// SQL 单元格上次执行的结果摘要
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CellResult {
+    /// 开始时间（UTC，RFC 3339）
+    pub executed_at: String,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+    /// 结果行数
+    pub row_count: u64,
+    /// 结果列数
+    pub column_count: u64,
+    /// 前 10 行的 JSON（失败时为空）
+    #[serde(default)]
+    pub preview: String,
+    /// 错误信息（成功时为 `None`）
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// 对工作簿执行的操作
+///
+/// 执行单元格后结果摘要写回工作簿文件，`Finished.preview` 为更新后的 [`Workbook`] 的 JSON。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum WorkbookAction {
+    /// 打开已有的工作簿（文件不存在时报错），结果为 [`Workbook`] 的 JSON
+    Open,
+    /// 创建空工作簿（文件已存在时报错）
+    Create,
+    /// 保存整个工作簿（替换文件内容）
+    Save {
+        /// 工作簿
+        workbook: Workbook,
+    },
+    /// 执行一个 SQL 单元格
+    RunCell {
+        /// 单元格 ID
+        id: String,
+        /// 先按依赖顺序执行它依赖的单元格（默认 false）
+        #[serde(default)]
+        with_dependencies: bool,
+    },
+    /// 按依赖顺序执行所有 SQL 单元格，遇到失败的单元格时停止
+    RunAll,
+}
+
+/// 查询结果的缓存状态
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    /// 结果来自缓存
+    Hit {
+        /// 缓存的结果已保存的时间（毫秒）
+        age_ms: u64,
+    },
+    /// 缓存中没有，执行后保存
+    Miss,
+}
+
+/// 对查询结果缓存执行的操作
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheAction {
+    /// 开启或关闭缓存（默认关闭），关闭时清空缓存
+    Configure {
+        /// 是否开启
+        enabled: bool,
+        /// 内存上限（字节，默认 64 MiB；不指定时保持不变）
+        #[serde(default)]
+        max_bytes: Option<usize>,
+    },
+    /// 清空缓存的结果和统计
+    Clear,
+    /// 查看统计
+    Stats,
+}
+
+/// 检查结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidationReport {
+    /// 表名
+    pub table: String,
+    /// 表的行数
+    pub row_count: u64,
+    /// 是否所有规则都通过
+    pub passed: bool,
+    /// 示例行的列名
+    pub columns: Vec<String>,
+    /// 各规则的结果（按规则顺序）
+    pub results: Vec<RuleResult>,
+}
+
+/// 单条规则的检查结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RuleResult {
+    /// 规则
+    pub rule: Rule,
+    /// 是否通过
+    pub passed: bool,
+    /// 违反规则的行数（行数规则为 0）
+    pub failing_rows: u64,
+    /// 违反规则的示例行（各列的文本形式，列顺序同 [`ValidationReport::columns`]）
+    pub samples: Vec<Vec<Option<String>>>,
+    /// 未通过原因（行数规则）
+    pub message: Option<String>,
+}
+
+/// 对数据质量规则执行的操作
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum RulesAction {
+    /// 列出所有表的规则，结果为 [`RuleSet`](crate::RuleSet) 的 JSON
+    List,
+    /// 替换表的规则（`rules` 为空时移除），已加载或保存过规则文件时同时写回该文件
+    Set {
+        /// 表名，与导入或查询时使用的表名一致
+        table: String,
+        /// 规则
+        rules: Vec<Rule>,
+    },
+    /// 从 JSON/YAML 文件加载规则（替换现有规则），之后的修改写回该文件
+    Load {
+        /// 规则文件路径（`.yaml`/`.yml` 为 YAML，其他为 JSON）
+        path: String,
+    },
+    /// 把规则保存到 JSON/YAML 文件，之后的修改写回该文件
+    Save {
+        /// 规则文件路径（`.yaml`/`.yml` 为 YAML，其他为 JSON）
+        path: String,
+    },
+}
+
+/// Parquet 压缩编码
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParquetCompression {
+    /// Snappy
+    #[default]
+    Snappy,
+    /// Zstandard，`level` 为 1-22（默认 3）
+    Zstd {
+        #[serde(default)]
+        level: Option<i32>,
+    },
+    /// gzip
+    Gzip,
+    /// 不压缩
+    Uncompressed,
+}
+
+/// 压缩格式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip（`.gz`）
+    Gzip,
+    /// Zstandard（`.zst`）
+    Zstd,
+    /// bzip2（`.bz2`）
+    Bzip2,
+    /// zip 压缩包（`.zip`），可包含多个成员文件
+    Zip,
+}
+
+impl Compression {
+    /// 从文件扩展名推断压缩格式
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            "zip" => Some(Compression::Zip),
+            _ => None,
+        }
+    }
+
+    /// 从文件路径推断压缩格式（只看最后一个扩展名）
+    pub fn from_path(path: &str) -> Option<Self> {
+        Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(Self::from_extension)
+    }
+}
+
 /// 文件格式
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FileFmt {
@@ -105,6 +1105,10 @@ pub enum FileFmt {
     Parquet,
     /// JSON 格式
     Json,
+    /// Excel 工作簿（xlsx/xlsm/xlsb/xls）
+    Excel,
+    /// Arrow IPC 文件（Feather v2）或流（`.arrows`）
+    Arrow,
 }
 
 impl FileFmt {
@@ -114,16 +1118,33 @@ impl FileFmt {
             "csv" => Some(FileFmt::Csv),
             "parquet" | "pq" => Some(FileFmt::Parquet),
             "json" | "jsonl" => Some(FileFmt::Json),
+            "xlsx" | "xlsm" | "xlsb" | "xls" => Some(FileFmt::Excel),
+            "arrow" | "arrows" | "feather" | "ipc" => Some(FileFmt::Arrow),
             _ => None,
         }
     }
     
+    /// 从文件路径推断格式
+    ///
+    /// 会跳过压缩后缀，如 `data.csv.gz` 推断为 CSV、`events.json.zst` 推断为 JSON。
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = Path::new(path);
+        let ext = path.extension()?.to_str()?;
+        if Compression::from_extension(ext).is_some() {
+            let inner = Path::new(path.file_stem()?);
+            return inner.extension()?.to_str().and_then(Self::from_extension);
+        }
+        Self::from_extension(ext)
+    }
+
     /// 获取默认文件扩展名
     pub fn extension(&self) -> &'static str {
         match self {
             FileFmt::Csv => "csv",
             FileFmt::Parquet => "parquet",
             FileFmt::Json => "json",
+            FileFmt::Excel => "xlsx",
+            FileFmt::Arrow => "arrow",
         }
     }
 }
@@ -139,7 +1160,83 @@ mod tests {
         assert_eq!(FileFmt::from_extension("parquet"), Some(FileFmt::Parquet));
         assert_eq!(FileFmt::from_extension("pq"), Some(FileFmt::Parquet));
         assert_eq!(FileFmt::from_extension("json"), Some(FileFmt::Json));
+        assert_eq!(FileFmt::from_extension("XLSX"), Some(FileFmt::Excel));
+        assert_eq!(FileFmt::from_extension("xls"), Some(FileFmt::Excel));
+        assert_eq!(FileFmt::from_extension("feather"), Some(FileFmt::Arrow));
+        assert_eq!(FileFmt::from_extension("arrows"), Some(FileFmt::Arrow));
         assert_eq!(FileFmt::from_extension("unknown"), None);
     }
-}
 
+    #[test]
+    fn test_file_fmt_from_path_skips_compression() {
+        assert_eq!(FileFmt::from_path("data.csv"), Some(FileFmt::Csv));
+        assert_eq!(FileFmt::from_path("dir/data.csv.gz"), Some(FileFmt::Csv));
+        assert_eq!(FileFmt::from_path("events.JSON.zst"), Some(FileFmt::Json));
+        assert_eq!(FileFmt::from_path("part.parquet.bz2"), Some(FileFmt::Parquet));
+        assert_eq!(FileFmt::from_path("bundle.zip"), None);
+        assert_eq!(FileFmt::from_path("data.gz"), None);
+        assert_eq!(FileFmt::from_path("README"), None);
+
+        assert_eq!(Compression::from_path("data.csv.gz"), Some(Compression::Gzip));
+        assert_eq!(Compression::from_path("bundle.ZIP"), Some(Compression::Zip));
+        assert_eq!(Compression::from_path("data.csv"), None);
+    }
+
+    #[test]
+    fn test_import_options_default_when_missing() {
+        let json = r#"{"ImportFile":{"path":"a.csv","fmt":"Csv","table_name":null}}"#;
+        let cmd: CmdType = serde_json::from_str(json).unwrap();
+        match cmd {
+            CmdType::ImportFile { options, overwrite, .. } => {
+                assert!(!overwrite);
+                assert_eq!(options, ImportOptions::default());
+            }
+            _ => panic!("Expected ImportFile"),
+        }
+    }
+
+    #[test]
+    fn test_export_options() {
+        let json = r#"{"ExportFile":{"source":"t","path":"t.parquet","fmt":"Parquet"}}"#;
+        match serde_json::from_str(json).unwrap() {
+            CmdType::ExportFile { options, overwrite, .. } => {
+                assert!(!overwrite);
+                assert_eq!(options, ExportOptions::default());
+            }
+            _ => panic!("Expected ExportFile"),
+        }
+
+        let json = r#"{"parquet":{"compression":{"Zstd":{"level":9}},"partition_by":["year"],"metadata":{"owner":"etl"}}}"#;
+        let options: ExportOptions = serde_json::from_str(json).unwrap();
+        assert_eq!(options.parquet.compression, ParquetCompression::Zstd { level: Some(9) });
+        assert_eq!(options.parquet.row_group_size, None);
+        assert_eq!(options.parquet.partition_by, vec!["year"]);
+        assert_eq!(options.parquet.metadata["owner"], "etl");
+    }
+
+    #[test]
+    fn test_connection_string() {
+        let mut profile = ConnectionProfile {
+            name: "warehouse".to_string(),
+            kind: DatabaseKind::Postgres,
+            host: "localhost".to_string(),
+            port: None,
+            user: "analyst".to_string(),
+            password: Some("it's secret".to_string()),
+            database: "sales".to_string(),
+        };
+        assert_eq!(
+            profile.connection_string(),
+            r"host=localhost port=5432 user=analyst dbname=sales password='it\'s secret'"
+        );
+        assert!(!format!("{:?}", profile).contains("secret"));
+
+        profile.kind = DatabaseKind::MySql;
+        profile.port = Some(3307);
+        profile.password = None;
+        assert_eq!(
+            profile.connection_string(),
+            "host=localhost port=3307 user=analyst database=sales"
+        );
+    }
+}
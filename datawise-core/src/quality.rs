@@ -0,0 +1,377 @@
+//! 数据质量规则
+//!
+//! 给表附加 [`Rule`]（非空、唯一、范围、正则、允许值、引用其他表、行数阈值），
+//! 用 SQL 找出违反每条规则的行，结果为 [`ValidationReport`]：每条规则的违规行数和几行示例。
+//!
+//! 规则以 [`RuleSet`] 保存在 JSON/YAML 文件中（按扩展名区分），加载或保存过规则文件后，
+//! 对规则的修改自动写回该文件。导入到附加了规则的表后会自动检查。
+
+use crate::catalog::{Catalog, TableInfo};
+use crate::profile::find_column;
+use crate::persist;
+use crate::protocol::{Rule, RuleResult, ValidationReport};
+use crate::sql::{quote_ident, quote_literal};
+use anyhow::{bail, Context, Result};
+use duckdb::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 每条规则返回的违规示例行数
+const SAMPLE_ROWS: usize = 5;
+
+/// 所有表的规则，即规则文件的内容
+///
+/// ```yaml
+/// tables:
+///   orders:
+///     - rule: unique
+///       columns: [order_id]
+///     - rule: row_count
+///       min: 1
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RuleSet {
+    /// 表名 -> 规则（表名不区分大小写）
+    #[serde(default)]
+    pub tables: BTreeMap<String, Vec<Rule>>,
+}
+
+impl RuleSet {
+    /// 从 JSON/YAML 文件读取（`.yaml`/`.yml` 为 YAML，其他为 JSON）
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rules file: {}", path.display()))?;
+        let rules = if is_yaml(path) {
+            serde_yaml_ng::from_str(&text).map_err(anyhow::Error::from)
+        } else {
+            serde_json::from_str(&text).map_err(anyhow::Error::from)
+        };
+        rules.with_context(|| format!("Invalid rules file: {}", path.display()))
+    }
+
+    /// 写入 JSON/YAML 文件（`.yaml`/`.yml` 为 YAML，其他为 JSON）
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = if is_yaml(path) {
+            serde_yaml_ng::to_string(self)?
+        } else {
+            serde_json::to_string_pretty(self)?
+        };
+        persist::write_atomic(path, text).with_context(|| format!("Failed to write rules file: {}", path.display()))
+    }
+
+    /// 表的规则（表名不区分大小写）
+    pub fn get(&self, table: &str) -> Option<&Vec<Rule>> {
+        self.tables
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(table))
+            .map(|(_, rules)| rules)
+    }
+
+    /// 规则总数
+    pub fn rule_count(&self) -> usize {
+        self.tables.values().map(Vec::len).sum()
+    }
+}
+
+fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"))
+}
+
+impl ValidationReport {
+    /// 未通过的规则数
+    pub fn failed_rules(&self) -> usize {
+        self.results.iter().filter(|r| !r.passed).count()
+    }
+}
+
+/// 规则存储和检查
+pub struct Validator {
+    conn: Arc<Mutex<Connection>>,
+    catalog: Arc<Catalog>,
+    store: Mutex<RuleStore>,
+}
+
+#[derive(Default)]
+struct RuleStore {
+    rules: RuleSet,
+    /// 加载或保存过的规则文件，修改规则时写回
+    path: Option<PathBuf>,
+}
+
+impl Validator {
+    pub fn new(conn: Arc<Mutex<Connection>>, catalog: Arc<Catalog>) -> Self {
+        Self {
+            conn,
+            catalog,
+            store: Mutex::new(RuleStore::default()),
+        }
+    }
+
+    /// 所有表的规则
+    pub fn rules(&self) -> RuleSet {
+        self.store.lock().unwrap().rules.clone()
+    }
+
+    /// 表的规则（表名不区分大小写）
+    pub fn rules_for(&self, table: &str) -> Option<Vec<Rule>> {
+        self.store.lock().unwrap().rules.get(table).cloned()
+    }
+
+    /// 替换表的规则，`rules` 为空时移除；有规则文件时写回
+    pub fn set_rules(&self, table: &str, rules: Vec<Rule>) -> Result<RuleSet> {
+        let mut store = self.store.lock().unwrap();
+        store.rules.tables.retain(|name, _| !name.eq_ignore_ascii_case(table));
+        if !rules.is_empty() {
+            store.rules.tables.insert(table.to_string(), rules);
+        }
+        if let Some(path) = &store.path {
+            store.rules.save(path)?;
+        }
+        Ok(store.rules.clone())
+    }
+
+    /// 从文件加载规则，替换现有规则
+    pub fn load(&self, path: &Path) -> Result<RuleSet> {
+        let rules = RuleSet::load(path)?;
+        let mut store = self.store.lock().unwrap();
+        store.rules = rules;
+        store.path = Some(path.to_path_buf());
+        Ok(store.rules.clone())
+    }
+
+    /// 把规则保存到文件
+    pub fn save(&self, path: &Path) -> Result<RuleSet> {
+        let mut store = self.store.lock().unwrap();
+        store.rules.save(path)?;
+        store.path = Some(path.to_path_buf());
+        Ok(store.rules.clone())
+    }
+
+    /// 按规则检查表
+    ///
+    /// 每检查完一条规则以（已完成规则数，总规则数）调用一次 `on_rule`，`cancel` 被置位时停止。
+    pub fn validate(
+        &self,
+        table: &str,
+        rules: &[Rule],
+        cancel: Option<&AtomicBool>,
+        on_rule: &mut dyn FnMut(u64, u64),
+    ) -> Result<ValidationReport> {
+        let info = self.catalog.describe_table(table)?;
+
+        // 被引用的表先解析好（describe_table 需要连接锁）
+        let mut referenced = BTreeMap::new();
+        for rule in rules {
+            if let Rule::References { table, .. } = rule {
+                if !referenced.contains_key(table) {
+                    referenced.insert(table.clone(), self.catalog.describe_table(table)?);
+                }
+            }
+        }
+
+        let relation = info.quoted_name();
+        let conn = self.conn.lock().unwrap();
+        let row_count = match info.row_count {
+            Some(rows) => rows,
+            None => conn.query_row(&format!("SELECT COUNT(*) FROM {}", relation), [], |row| row.get::<_, i64>(0))?
+                as u64,
+        };
+
+        let total = rules.len() as u64;
+        let mut results = Vec::with_capacity(rules.len());
+        for (done, rule) in rules.iter().enumerate() {
+            if cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+                bail!("Validation cancelled");
+            }
+            let result = match rule {
+                Rule::RowCount { min, max } => check_row_count(rule, row_count, *min, *max),
+                _ => {
+                    let offending = offending_rows_sql(&info, &relation, rule, &referenced)?;
+                    check_rows(&conn, &info, rule, &offending)
+                        .with_context(|| format!("Failed to check rule: {:?}", rule))?
+                }
+            };
+            results.push(result);
+            on_rule(done as u64 + 1, total);
+        }
+
+        Ok(ValidationReport {
+            table: info.name.clone(),
+            row_count,
+            passed: results.iter().all(|r| r.passed),
+            columns: info.columns.iter().map(|c| c.name.clone()).collect(),
+            results,
+        })
+    }
+}
+
+fn check_row_count(rule: &Rule, row_count: u64, min: Option<u64>, max: Option<u64>) -> RuleResult {
+    let message = match (min, max) {
+        (Some(min), _) if row_count < min => Some(format!("Row count {} is below the minimum {}", row_count, min)),
+        (_, Some(max)) if row_count > max => Some(format!("Row count {} is above the maximum {}", row_count, max)),
+        _ => None,
+    };
+    RuleResult {
+        rule: rule.clone(),
+        passed: message.is_none(),
+        failing_rows: 0,
+        samples: Vec::new(),
+        message,
+    }
+}
+
+/// 统计违规行数并取示例行
+fn check_rows(conn: &Connection, table: &TableInfo, rule: &Rule, offending: &str) -> Result<RuleResult> {
+    let failing_rows: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM ({})", offending), [], |row| row.get(0))?;
+
+    let projection = table
+        .columns
+        .iter()
+        .map(|c| format!("CAST({} AS VARCHAR)", quote_ident(&c.name)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM ({}) LIMIT {}", projection, offending, SAMPLE_ROWS))?;
+    let mut rows = stmt.query([])?;
+    let mut samples = Vec::new();
+    while let Some(row) = rows.next()? {
+        samples.push(
+            (0..table.columns.len())
+                .map(|i| row.get(i))
+                .collect::<duckdb::Result<Vec<Option<String>>>>()?,
+        );
+    }
+
+    Ok(RuleResult {
+        rule: rule.clone(),
+        passed: failing_rows == 0,
+        failing_rows: failing_rows as u64,
+        samples,
+        message: None,
+    })
+}
+
+/// 违反规则的行的查询；空值只由 `not_null` 规则检查
+fn offending_rows_sql(
+    table: &TableInfo,
+    relation: &str,
+    rule: &Rule,
+    referenced: &BTreeMap<String, TableInfo>,
+) -> Result<String> {
+    let sql = match rule {
+        Rule::NotNull { column } => {
+            let c = quote_ident(&find_column(table, column)?.name);
+            format!("SELECT * FROM {relation} WHERE {c} IS NULL")
+        }
+        Rule::Unique { columns } => {
+            if columns.is_empty() {
+                bail!("Unique rule needs at least one column");
+            }
+            let keys = columns
+                .iter()
+                .map(|name| Ok(quote_ident(&find_column(table, name)?.name)))
+                .collect::<Result<Vec<_>>>()?;
+            let not_null = keys.iter().map(|k| format!("{k} IS NOT NULL")).collect::<Vec<_>>().join(" AND ");
+            let keys = keys.join(", ");
+            format!(
+                "SELECT * FROM {relation} WHERE {not_null} \
+                 QUALIFY COUNT(*) OVER (PARTITION BY {keys}) > 1 ORDER BY {keys}"
+            )
+        }
+        Rule::Range { column, min, max } => {
+            let column = find_column(table, column)?;
+            let c = quote_ident(&column.name);
+            let mut conditions = Vec::new();
+            if let Some(min) = min {
+                conditions.push(format!("{c} < {}", value_sql(min, &column.data_type)?));
+            }
+            if let Some(max) = max {
+                conditions.push(format!("{c} > {}", value_sql(max, &column.data_type)?));
+            }
+            if conditions.is_empty() {
+                bail!("Range rule on {} needs a minimum or a maximum", column.name);
+            }
+            format!("SELECT * FROM {relation} WHERE {}", conditions.join(" OR "))
+        }
+        Rule::Regex { column, pattern } => {
+            let c = quote_ident(&find_column(table, column)?.name);
+            format!(
+                "SELECT * FROM {relation} WHERE NOT regexp_full_match(CAST({c} AS VARCHAR), {})",
+                quote_literal(pattern)
+            )
+        }
+        Rule::AllowedValues { column, values } => {
+            let column = find_column(table, column)?;
+            let c = quote_ident(&column.name);
+            if values.is_empty() {
+                format!("SELECT * FROM {relation} WHERE {c} IS NOT NULL")
+            } else {
+                let values = values
+                    .iter()
+                    .map(|v| value_sql(v, &column.data_type))
+                    .collect::<Result<Vec<_>>>()?;
+                format!("SELECT * FROM {relation} WHERE {c} NOT IN ({})", values.join(", "))
+            }
+        }
+        Rule::References { column, table: target, referenced_column } => {
+            let c = quote_ident(&find_column(table, column)?.name);
+            let target = referenced
+                .get(target)
+                .with_context(|| format!("Referenced table not found: {}", target))?;
+            let rc = quote_ident(&find_column(target, referenced_column.as_deref().unwrap_or(column))?.name);
+            format!(
+                "SELECT * FROM {relation} WHERE {c} NOT IN (SELECT {rc} FROM {} WHERE {rc} IS NOT NULL)",
+                target.quoted_name()
+            )
+        }
+        Rule::RowCount { .. } => bail!("Row count rules are not checked row by row"),
+    };
+    Ok(sql)
+}
+
+/// 规则中的值转换为 SQL：数字和布尔值直接使用，字符串转换为列的类型
+fn value_sql(value: &serde_json::Value, data_type: &str) -> Result<String> {
+    match value {
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        serde_json::Value::Bool(b) => Ok(if *b { "TRUE" } else { "FALSE" }.to_string()),
+        serde_json::Value::String(s) => Ok(format!("CAST({} AS {})", quote_literal(s), data_type)),
+        other => bail!("Unsupported value in rule: {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_file_formats() {
+        let yaml = "tables:\n  orders:\n    - rule: not_null\n      column: id\n    - rule: range\n      column: amount\n      min: 0\n";
+        let rules: RuleSet = serde_yaml_ng::from_str(yaml).unwrap();
+        assert_eq!(
+            rules.get("ORDERS").unwrap(),
+            &vec![
+                Rule::NotNull { column: "id".to_string() },
+                Rule::Range {
+                    column: "amount".to_string(),
+                    min: Some(serde_json::json!(0)),
+                    max: None,
+                },
+            ]
+        );
+
+        let json = serde_json::to_string(&rules).unwrap();
+        assert!(json.contains(r#""rule":"not_null""#));
+        assert_eq!(serde_json::from_str::<RuleSet>(&json).unwrap(), rules);
+    }
+
+    #[test]
+    fn test_value_sql() {
+        assert_eq!(value_sql(&serde_json::json!(1.5), "DOUBLE").unwrap(), "1.5");
+        assert_eq!(value_sql(&serde_json::json!("2024-01-01"), "DATE").unwrap(), "CAST('2024-01-01' AS DATE)");
+        assert!(value_sql(&serde_json::json!([1]), "INTEGER").is_err());
+    }
+}
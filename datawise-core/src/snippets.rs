@@ -0,0 +1,403 @@
+//! 查询片段库
+//!
+//! 命名、可复用的查询，按文件夹和标签组织，SQL 中可以使用 `{{参数名}}` 占位符，
+//! 执行时代入参数值（作为 SQL 字面量）。字符串字面量、带引号的名称和注释中的
+//! `{{...}}` 保持原样。
+//!
+//! 片段库保存在打开的 JSON 文件中，每次修改都写回；也可以把部分片段导出为 JSON 包
+//! 分享给同事，再导入到自己的片段库中。
+
+use crate::persist;
+use crate::protocol::Snippet;
+use crate::sql::quote_literal;
+use anyhow::{bail, Context, Result};
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+impl Snippet {
+    /// 代入参数值，得到可执行的 SQL
+    ///
+    /// 字符串加单引号，数字和布尔值直接使用，`null` 为 `NULL`；缺少参数时报错。
+    pub fn render(&self, params: &BTreeMap<String, serde_json::Value>) -> Result<String> {
+        let missing: Vec<&str> = self
+            .parameters
+            .iter()
+            .filter(|name| !params.contains_key(name.as_str()))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            bail!("Missing parameter(s) for snippet {}: {}", self.name, missing.join(", "));
+        }
+
+        let mut sql = String::with_capacity(self.sql.len());
+        for part in split_placeholders(&self.sql) {
+            match part {
+                Part::Text(text) => sql.push_str(text),
+                Part::Parameter(name) => sql.push_str(&literal(&params[name])?),
+            }
+        }
+        Ok(sql)
+    }
+
+    /// 从 SQL 中重新提取参数名，并更新修改时间
+    fn touch(&mut self) {
+        self.normalize();
+        self.updated_at = Some(now());
+    }
+
+    /// 从 SQL 中重新提取参数名，去掉文件夹两侧的 `/`
+    fn normalize(&mut self) {
+        let mut parameters: Vec<String> = Vec::new();
+        for part in split_placeholders(&self.sql) {
+            if let Part::Parameter(name) = part {
+                if !parameters.iter().any(|p| p == name) {
+                    parameters.push(name.to_string());
+                }
+            }
+        }
+        self.parameters = parameters;
+        self.folder = self.folder.trim_matches('/').to_string();
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// 片段库，也是导入/导出的 JSON 包的格式
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnippetLibrary {
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
+}
+
+impl SnippetLibrary {
+    /// 读取 JSON 文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read snippet file: {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid snippet file: {}", path.display()))
+    }
+
+    /// 写入 JSON 文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string_pretty(self)?;
+        persist::write_atomic(path, text).with_context(|| format!("Failed to write snippet file: {}", path.display()))
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.snippets.iter().position(|s| s.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// 片段库管理
+#[derive(Default)]
+pub struct Snippets {
+    store: Mutex<SnippetStore>,
+}
+
+#[derive(Default)]
+struct SnippetStore {
+    library: SnippetLibrary,
+    /// 打开的片段库文件，修改时写回
+    path: Option<PathBuf>,
+}
+
+impl SnippetStore {
+    fn save(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => self.library.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Snippets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 打开片段库文件（不存在时创建），替换当前的片段
+    pub fn open(&self, path: &Path) -> Result<usize> {
+        let library = if path.exists() {
+            SnippetLibrary::load(path)?
+        } else {
+            SnippetLibrary::default()
+        };
+        let mut store = self.store.lock().unwrap();
+        store.library = library;
+        store.path = Some(path.to_path_buf());
+        store.save()?;
+        Ok(store.library.snippets.len())
+    }
+
+    /// 列出片段，按文件夹和名称排序
+    ///
+    /// `folder` 指定时只列出该文件夹及其子文件夹中的片段；`tag` 指定时只列出带该标签的片段。
+    pub fn list(&self, folder: Option<&str>, tag: Option<&str>) -> Vec<Snippet> {
+        let folder = folder.map(|f| f.trim_matches('/')).filter(|f| !f.is_empty());
+        let store = self.store.lock().unwrap();
+        let mut snippets: Vec<Snippet> = store
+            .library
+            .snippets
+            .iter()
+            .filter(|s| folder.is_none_or(|f| s.folder == f || s.folder.starts_with(&format!("{}/", f))))
+            .filter(|s| tag.is_none_or(|t| s.tags.iter().any(|tag| tag.eq_ignore_ascii_case(t))))
+            .cloned()
+            .collect();
+        snippets.sort_by(|a, b| (&a.folder, &a.name).cmp(&(&b.folder, &b.name)));
+        snippets
+    }
+
+    /// 按名称（不区分大小写）查找片段
+    pub fn get(&self, name: &str) -> Result<Snippet> {
+        let store = self.store.lock().unwrap();
+        store
+            .library
+            .position(name)
+            .map(|i| store.library.snippets[i].clone())
+            .with_context(|| format!("Snippet not found: {}", name))
+    }
+
+    /// 保存新片段，同名片段已存在时报错
+    pub fn save(&self, mut snippet: Snippet) -> Result<Snippet> {
+        validate_name(&snippet.name)?;
+        let mut store = self.store.lock().unwrap();
+        if store.library.position(&snippet.name).is_some() {
+            bail!("Snippet already exists: {}", snippet.name);
+        }
+        snippet.touch();
+        store.library.snippets.push(snippet.clone());
+        store.save()?;
+        Ok(snippet)
+    }
+
+    /// 替换名为 `name` 的片段，可以同时改名
+    pub fn update(&self, name: &str, mut snippet: Snippet) -> Result<Snippet> {
+        validate_name(&snippet.name)?;
+        let mut store = self.store.lock().unwrap();
+        let index = store
+            .library
+            .position(name)
+            .with_context(|| format!("Snippet not found: {}", name))?;
+        if store.library.position(&snippet.name).is_some_and(|other| other != index) {
+            bail!("Snippet already exists: {}", snippet.name);
+        }
+        snippet.touch();
+        store.library.snippets[index] = snippet.clone();
+        store.save()?;
+        Ok(snippet)
+    }
+
+    /// 删除片段
+    pub fn delete(&self, name: &str) -> Result<Snippet> {
+        let mut store = self.store.lock().unwrap();
+        let index = store
+            .library
+            .position(name)
+            .with_context(|| format!("Snippet not found: {}", name))?;
+        let snippet = store.library.snippets.remove(index);
+        store.save()?;
+        Ok(snippet)
+    }
+
+    /// 把片段（`names` 为空时全部）导出为 JSON 包，返回导出的个数
+    pub fn export(&self, path: &Path, names: &[String]) -> Result<usize> {
+        let store = self.store.lock().unwrap();
+        let snippets = if names.is_empty() {
+            store.library.snippets.clone()
+        } else {
+            names
+                .iter()
+                .map(|name| {
+                    store
+                        .library
+                        .position(name)
+                        .map(|i| store.library.snippets[i].clone())
+                        .with_context(|| format!("Snippet not found: {}", name))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        let count = snippets.len();
+        SnippetLibrary { snippets }.save(path)?;
+        Ok(count)
+    }
+
+    /// 从 JSON 包导入片段，返回导入的个数
+    ///
+    /// 同名片段存在时，`overwrite` 为 true 则替换，否则报错且不导入任何片段。
+    /// 保留包中片段的修改时间（没有时为导入时间）。
+    pub fn import(&self, path: &Path, overwrite: bool) -> Result<usize> {
+        let bundle = SnippetLibrary::load(path)?;
+        for snippet in &bundle.snippets {
+            validate_name(&snippet.name)?;
+        }
+
+        let mut store = self.store.lock().unwrap();
+        if !overwrite {
+            if let Some(existing) = bundle.snippets.iter().find(|s| store.library.position(&s.name).is_some()) {
+                bail!("Snippet already exists: {}", existing.name);
+            }
+        }
+        let count = bundle.snippets.len();
+        for mut snippet in bundle.snippets {
+            snippet.normalize();
+            snippet.updated_at.get_or_insert_with(now);
+            match store.library.position(&snippet.name) {
+                Some(index) => store.library.snippets[index] = snippet,
+                None => store.library.snippets.push(snippet),
+            }
+        }
+        store.save()?;
+        Ok(count)
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        bail!("Snippet name must not be empty");
+    }
+    Ok(())
+}
+
+/// SQL 中的文本或 `{{参数名}}` 占位符
+#[derive(Debug, PartialEq, Eq)]
+enum Part<'a> {
+    Text(&'a str),
+    Parameter(&'a str),
+}
+
+/// 按 `{{参数名}}` 拆分 SQL，参数名两侧的空白被忽略；没有闭合的 `{{` 作为普通文本
+///
+/// 跳过字符串字面量、带引号的名称和注释，其中的 `{{...}}` 不是占位符。
+fn split_placeholders(sql: &str) -> Vec<Part<'_>> {
+    let bytes = sql.as_bytes();
+    let mut parts = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // 两个引号表示引号本身，相当于两段相邻的引号区域
+            quote @ (b'\'' | b'"') => {
+                i = sql[i + 1..].find(quote as char).map_or(bytes.len(), |end| i + 1 + end + 1);
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end + 1);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            b'{' if sql[i..].starts_with("{{") => {
+                let Some(len) = sql[i + 2..].find("}}") else {
+                    break;
+                };
+                if i > text_start {
+                    parts.push(Part::Text(&sql[text_start..i]));
+                }
+                parts.push(Part::Parameter(sql[i + 2..i + 2 + len].trim()));
+                i += 2 + len + 2;
+                text_start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    if text_start < sql.len() {
+        parts.push(Part::Text(&sql[text_start..]));
+    }
+    parts
+}
+
+/// 参数值转换为 SQL 字面量
+fn literal(value: &serde_json::Value) -> Result<String> {
+    match value {
+        serde_json::Value::Null => Ok("NULL".to_string()),
+        serde_json::Value::Bool(b) => Ok(if *b { "TRUE" } else { "FALSE" }.to_string()),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        serde_json::Value::String(s) => Ok(quote_literal(s)),
+        other => bail!("Unsupported parameter value: {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_split_placeholders() {
+        assert_eq!(
+            split_placeholders("SELECT * FROM t WHERE a = {{ a }} AND b = {{b}} {{"),
+            [
+                Part::Text("SELECT * FROM t WHERE a = "),
+                Part::Parameter("a"),
+                Part::Text(" AND b = "),
+                Part::Parameter("b"),
+                Part::Text(" {{"),
+            ]
+        );
+
+        // 字面量、带引号的名称和注释中的占位符保持原样
+        assert_eq!(
+            split_placeholders("SELECT '{{a}}', 'it''s {{a}}', \"{{a}}\" -- {{a}}\n/* {{a}} */ FROM t WHERE x = {{a}}"),
+            [
+                Part::Text("SELECT '{{a}}', 'it''s {{a}}', \"{{a}}\" -- {{a}}\n/* {{a}} */ FROM t WHERE x = "),
+                Part::Parameter("a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let mut snippet = Snippet {
+            name: "by_region".to_string(),
+            folder: String::new(),
+            description: String::new(),
+            tags: Vec::new(),
+            sql: "SELECT * FROM sales WHERE region = {{region}} AND amount > {{min}} OR region = {{region}}".to_string(),
+            parameters: Vec::new(),
+            updated_at: None,
+        };
+        snippet.touch();
+        assert_eq!(snippet.parameters, ["region", "min"]);
+
+        let params = BTreeMap::from([("region".to_string(), json!("O'Hare")), ("min".to_string(), json!(10))]);
+        assert_eq!(
+            snippet.render(&params).unwrap(),
+            "SELECT * FROM sales WHERE region = 'O''Hare' AND amount > 10 OR region = 'O''Hare'"
+        );
+
+        let err = snippet.render(&BTreeMap::new()).unwrap_err();
+        assert!(err.to_string().contains("region, min"));
+    }
+
+    #[test]
+    fn test_import_keeps_timestamps() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let bundle = dir.path().join("bundle.json");
+        let snippet = |name: &str, updated_at: Option<&str>| Snippet {
+            name: name.to_string(),
+            folder: "/reports/".to_string(),
+            description: String::new(),
+            tags: Vec::new(),
+            sql: "SELECT {{x}}".to_string(),
+            parameters: Vec::new(),
+            updated_at: updated_at.map(str::to_string),
+        };
+        SnippetLibrary {
+            snippets: vec![snippet("old", Some("2024-01-02T03:04:05.000Z")), snippet("new", None)],
+        }
+        .save(&bundle)
+        .unwrap();
+
+        let snippets = Snippets::new();
+        assert_eq!(snippets.import(&bundle, false).unwrap(), 2);
+        let old = snippets.get("old").unwrap();
+        assert_eq!(old.updated_at.as_deref(), Some("2024-01-02T03:04:05.000Z"));
+        assert_eq!(old.folder, "reports");
+        assert_eq!(old.parameters, ["x"]);
+        assert!(snippets.get("new").unwrap().updated_at.is_some());
+    }
+}
@@ -0,0 +1,185 @@
+//! SQL 标识符与字面量
+//!
+//! 所有拼接到 SQL 中的表名、列名、文件路径和字符串都经过这里加引号，
+//! 文件名中的引号、空格、中文或 SQL 片段不会破坏语句或被当作 SQL 执行。
+//!
+//! 另外提供简单的词法分析，用于分析工作簿单元格的依赖和规范化缓存的查询。
+
+use std::path::Path;
+
+/// 标识符加双引号：`my "table"` → `"my ""table"""`
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// 表名加引号，按 `.` 拆分为数据库/schema/表名分别加引号
+///
+/// `sales` → `"sales"`，`warehouse.public.orders` → `"warehouse"."public"."orders"`。
+pub fn quote_table_name(name: &str) -> String {
+    name.split('.').map(quote_ident).collect::<Vec<_>>().join(".")
+}
+
+/// 字符串字面量加单引号：`O'Brien` → `'O''Brien'`
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// 文件路径作为字符串字面量
+pub fn quote_path(path: &Path) -> String {
+    quote_literal(&path.to_string_lossy())
+}
+
+/// 把文件名等任意文本转换为无需加引号即可在 SQL 中使用的表名
+///
+/// 字母、数字（包括中文等）保留，其余字符替换为 `_`，以数字开头时加 `t_` 前缀：
+/// `2024-sales` → `t_2024_sales`，`O'Brien data` → `O_Brien_data`。
+/// 没有可用字符时返回 `None`。
+pub fn sanitize_table_name(name: &str) -> Option<String> {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            result.push(c);
+        } else if !result.is_empty() && !result.ends_with('_') {
+            result.push('_');
+        }
+    }
+    let result = result.trim_end_matches('_');
+    match result.chars().next() {
+        None => None,
+        Some(c) if c.is_numeric() => Some(format!("t_{}", result)),
+        Some(_) => Some(result.to_string()),
+    }
+}
+
+/// SQL 中的词法单元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Token {
+    /// 名称或关键字（小写）
+    Word(String),
+    /// 带双引号的名称（小写）
+    Quoted(String),
+    /// 字符串字面量
+    Literal(String),
+    Dot,
+    Semicolon,
+    /// 运算符、括号等
+    Other(char),
+}
+
+/// 拆分 SQL，跳过空白和注释，名称转为小写
+pub(crate) fn tokenize(sql: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '\'' | '"' => {
+                let mut text = String::new();
+                while let Some(next) = chars.next() {
+                    if next == c {
+                        // 两个引号表示引号本身
+                        if chars.peek() == Some(&c) {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    text.push(next);
+                }
+                tokens.push(if c == '"' { Token::Quoted(text.to_lowercase()) } else { Token::Literal(text) });
+            }
+            '.' => tokens.push(Token::Dot),
+            ';' => tokens.push(Token::Semicolon),
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_lowercase().to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    word.extend(next.to_lowercase());
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push(Token::Other(c)),
+        }
+    }
+    tokens
+}
+
+/// 规范化 SQL：去掉注释和多余的空白、末尾的分号，名称和关键字转为小写，字面量保持不变
+///
+/// 只是写法不同的同一条查询规范化后相同：`SELECT *  FROM Sales;` → `select * from sales`。
+pub(crate) fn normalize_sql(sql: &str) -> String {
+    let mut tokens = tokenize(sql);
+    while tokens.last() == Some(&Token::Semicolon) {
+        tokens.pop();
+    }
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::Word(word) => word.clone(),
+            Token::Quoted(name) => quote_ident(name),
+            Token::Literal(text) => quote_literal(text),
+            Token::Dot => ".".to_string(),
+            Token::Semicolon => ";".to_string(),
+            Token::Other(c) => c.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(quote_ident("a\"b"), "\"a\"\"b\"");
+        assert_eq!(quote_table_name("db.orders"), "\"db\".\"orders\"");
+        assert_eq!(quote_literal("O'Brien.csv"), "'O''Brien.csv'");
+        assert_eq!(
+            quote_literal("x'); DROP TABLE users; --"),
+            "'x''); DROP TABLE users; --'"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_table_name() {
+        assert_eq!(sanitize_table_name("sales_2024").as_deref(), Some("sales_2024"));
+        assert_eq!(sanitize_table_name("2024-sales").as_deref(), Some("t_2024_sales"));
+        assert_eq!(sanitize_table_name("O'Brien").as_deref(), Some("O_Brien"));
+        assert_eq!(sanitize_table_name("销售 数据 (上半年)").as_deref(), Some("销售_数据_上半年"));
+        assert_eq!(sanitize_table_name("x; DROP TABLE t").as_deref(), Some("x_DROP_TABLE_t"));
+        assert_eq!(sanitize_table_name(" -- "), None);
+    }
+
+    #[test]
+    fn test_normalize_sql() {
+        assert_eq!(
+            normalize_sql("SELECT *\n  FROM \"Sales\" -- all rows\nWHERE region = 'North';"),
+            "select * from \"sales\" where region = 'North'"
+        );
+        assert_eq!(
+            normalize_sql("select * from sales /* same */ where REGION = 'North'"),
+            normalize_sql("SELECT * FROM sales WHERE region='North'")
+        );
+    }
+}
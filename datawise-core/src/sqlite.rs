@@ -0,0 +1,325 @@
+//! SQLite 数据库读取
+//!
+//! 通过 rusqlite 以只读方式打开 SQLite 数据库文件，把表复制到 DuckDB。
+//!
+//! SQLite 是弱类型的：列的声明类型只决定类型亲和性，声明为 INTEGER 的列
+//! 也可能存有文本。因此列类型由声明类型和实际存储类型共同决定，
+//! 与声明类型不一致的列退化为能容纳所有值的类型（通常是 VARCHAR）。
+
+use crate::sql::quote_ident;
+use anyhow::{bail, Context, Result};
+use duckdb::types::Value;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 复制表时每批追加的行数，每批之后检查一次取消标记
+const BATCH_ROWS: u64 = 2048;
+
+/// SQLite 数据库中的表
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SqliteTable {
+    /// 表名
+    pub name: String,
+    /// 行数
+    pub row_count: u64,
+    /// 列信息
+    pub columns: Vec<SqliteColumn>,
+}
+
+/// SQLite 表中的列
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SqliteColumn {
+    /// 列名
+    pub name: String,
+    /// SQLite 中的声明类型（可能为空）
+    pub declared_type: String,
+    /// 导入后的 DuckDB 类型
+    pub duckdb_type: String,
+    #[serde(skip)]
+    column_type: ColumnType,
+}
+
+/// 以只读方式打开 SQLite 数据库
+pub fn open(path: &Path) -> Result<Connection> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+        .with_context(|| format!("Failed to open SQLite database: {:?}", path))
+}
+
+/// 列出数据库中的用户表（按表名排序）
+pub fn list_tables(conn: &Connection) -> Result<Vec<SqliteTable>> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to list SQLite tables")?;
+
+    names.iter().map(|name| describe_table(conn, name)).collect()
+}
+
+fn describe_table(conn: &Connection, name: &str) -> Result<SqliteTable> {
+    let table = quote_ident(name);
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let declared = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .with_context(|| format!("Failed to read columns of SQLite table: {}", name))?;
+
+    // 一次扫描得到行数、每列的实际存储类型和不能按声明类型转换的值的个数
+    let aggregates = std::iter::once("COUNT(*)".to_string())
+        .chain(declared.iter().flat_map(|(column, declared_type)| {
+            let column = quote_ident(column);
+            [
+                format!("group_concat(DISTINCT CASE WHEN {c} IS NOT NULL THEN typeof({c}) END)", c = column),
+                format!("COALESCE(SUM({}), 0)", invalid_condition(&column, declared_type)),
+            ]
+        }))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let (row_count, stats) = conn
+        .query_row(&format!("SELECT {} FROM {}", aggregates, table), [], |row| {
+            let stats = (0..declared.len())
+                .map(|i| Ok((row.get::<_, Option<String>>(1 + 2 * i)?, row.get::<_, i64>(2 + 2 * i)?)))
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok((row.get::<_, i64>(0)?, stats))
+        })
+        .with_context(|| format!("Failed to analyze SQLite table: {}", name))?;
+
+    let columns = declared
+        .into_iter()
+        .zip(stats)
+        .map(|((column, declared_type), (classes, invalid))| {
+            let classes: Vec<String> = classes
+                .map(|c| c.split(',').map(str::to_string).collect())
+                .unwrap_or_default();
+            let column_type = column_type(&declared_type, &classes, invalid);
+            SqliteColumn {
+                name: column,
+                declared_type,
+                duckdb_type: column_type.sql_type().to_string(),
+                column_type,
+            }
+        })
+        .collect();
+
+    Ok(SqliteTable {
+        name: name.to_string(),
+        row_count: row_count as u64,
+        columns,
+    })
+}
+
+/// 导入后的列类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Boolean,
+    BigInt,
+    Double,
+    Varchar,
+    Blob,
+    Date,
+    Timestamp,
+}
+
+impl ColumnType {
+    fn sql_type(self) -> &'static str {
+        match self {
+            ColumnType::Boolean => "BOOLEAN",
+            ColumnType::BigInt => "BIGINT",
+            ColumnType::Double => "DOUBLE",
+            ColumnType::Varchar => "VARCHAR",
+            ColumnType::Blob => "BLOB",
+            ColumnType::Date => "DATE",
+            ColumnType::Timestamp => "TIMESTAMP",
+        }
+    }
+
+    /// 只根据实际存储类型推断
+    fn infer(classes: &[String]) -> Self {
+        let only = |allowed: &[&str]| classes.iter().all(|c| allowed.contains(&c.as_str()));
+        if classes.is_empty() {
+            ColumnType::Varchar
+        } else if only(&["integer"]) {
+            ColumnType::BigInt
+        } else if only(&["integer", "real"]) {
+            ColumnType::Double
+        } else if only(&["blob"]) {
+            ColumnType::Blob
+        } else {
+            ColumnType::Varchar
+        }
+    }
+
+    /// 把 SQLite 值转换为该类型的参数值
+    fn value(self, value: ValueRef<'_>) -> Value {
+        match (self, value) {
+            (_, ValueRef::Null) => Value::Null,
+            (ColumnType::Boolean, ValueRef::Integer(i)) => Value::Boolean(i != 0),
+            (ColumnType::BigInt, ValueRef::Integer(i)) => Value::BigInt(i),
+            (ColumnType::Double, ValueRef::Integer(i)) => Value::Double(i as f64),
+            (ColumnType::Double, ValueRef::Real(f)) => Value::Double(f),
+            (ColumnType::Blob, ValueRef::Blob(b)) => Value::Blob(b.to_vec()),
+            (_, ValueRef::Integer(i)) => Value::Text(i.to_string()),
+            (_, ValueRef::Real(f)) => Value::Text(f.to_string()),
+            (_, ValueRef::Text(t)) | (_, ValueRef::Blob(t)) => {
+                Value::Text(String::from_utf8_lossy(t).into_owned())
+            }
+        }
+    }
+}
+
+/// 按声明类型转换时不能转换的值的条件（SQL 表达式，`column` 已加引号）
+///
+/// 只有 BOOLEAN 和日期类型需要检查值本身，其他类型只看存储类型。
+fn invalid_condition(column: &str, declared: &str) -> String {
+    match declared_kind(declared) {
+        Some(ColumnType::Boolean) => format!("{} NOT IN (0, 1)", column),
+        Some(ColumnType::Date) => format!("{c} IS NOT NULL AND date({c}) IS NULL", c = column),
+        Some(ColumnType::Timestamp) => format!("{c} IS NOT NULL AND datetime({c}) IS NULL", c = column),
+        _ => "0".to_string(),
+    }
+}
+
+/// 声明类型对应的列类型（无法从声明类型确定时为 `None`）
+///
+/// 亲和性规则参见 <https://www.sqlite.org/datatype3.html#determination_of_column_affinity>，
+/// 另外识别 BOOLEAN、DATE、DATETIME/TIMESTAMP 这几种常见的声明类型。
+fn declared_kind(declared: &str) -> Option<ColumnType> {
+    let declared = declared.to_uppercase();
+    if declared.contains("BOOL") {
+        Some(ColumnType::Boolean)
+    } else if declared == "DATE" {
+        Some(ColumnType::Date)
+    } else if declared.contains("DATETIME") || declared.contains("TIMESTAMP") {
+        Some(ColumnType::Timestamp)
+    } else if declared.contains("INT") {
+        Some(ColumnType::BigInt)
+    } else if declared.contains("CHAR") || declared.contains("CLOB") || declared.contains("TEXT") {
+        Some(ColumnType::Varchar)
+    } else if declared.contains("REAL") || declared.contains("FLOA") || declared.contains("DOUB") {
+        Some(ColumnType::Double)
+    } else {
+        // BLOB、无声明类型和 NUMERIC 亲和性：按实际存储类型推断
+        None
+    }
+}
+
+/// 根据声明类型、实际存储类型和不能转换的值的个数决定列类型
+fn column_type(declared: &str, classes: &[String], invalid: i64) -> ColumnType {
+    let only = |allowed: &[&str]| classes.iter().all(|c| allowed.contains(&c.as_str()));
+    let inferred = ColumnType::infer(classes);
+    match declared_kind(declared) {
+        Some(ColumnType::Boolean) if only(&["integer"]) && invalid == 0 => ColumnType::Boolean,
+        // 日期通常以文本存储，只有所有值都能被 SQLite 的日期函数解析时才转换
+        Some(ty @ (ColumnType::Date | ColumnType::Timestamp))
+            if !classes.is_empty() && only(&["text"]) && invalid == 0 =>
+        {
+            ty
+        }
+        Some(ColumnType::BigInt) if only(&["integer"]) => ColumnType::BigInt,
+        Some(ColumnType::Varchar) => ColumnType::Varchar,
+        Some(ColumnType::Double) if only(&["integer", "real"]) => ColumnType::Double,
+        _ => inferred,
+    }
+}
+
+/// 把 SQLite 表复制到 DuckDB 的 `target` 表（不含引号），每复制一行调用一次 `on_row`
+///
+/// 用 Appender 批量写入；导入多张表时调用方应在同一个事务中调用。
+/// `cancel` 被置位时在两批之间中止，已追加的行由调用方回滚事务丢弃。
+pub fn copy_table(
+    source: &Connection,
+    table: &SqliteTable,
+    target_conn: &duckdb::Connection,
+    target: &str,
+    cancel: Option<&AtomicBool>,
+    on_row: &mut dyn FnMut(),
+) -> Result<u64> {
+    let columns = table
+        .columns
+        .iter()
+        .map(|c| format!("{} {}", quote_ident(&c.name), c.duckdb_type))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let target_sql = quote_ident(target);
+    target_conn
+        .execute(&format!("CREATE TABLE {} ({})", target_sql, columns), [])
+        .with_context(|| format!("Failed to create table: {}", target))?;
+
+    // 日期在 SQLite 中统一格式化后再转换，兼容 `2024-01-15T08:30` 等写法
+    let select = table
+        .columns
+        .iter()
+        .map(|c| {
+            let name = quote_ident(&c.name);
+            match c.column_type {
+                ColumnType::Date => format!("date({})", name),
+                ColumnType::Timestamp => format!("strftime('%Y-%m-%d %H:%M:%f', {})", name),
+                _ => name,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // 日期和时间戳以文本追加，由 Appender 转换为列类型
+    let mut appender = target_conn
+        .appender(target)
+        .with_context(|| format!("Failed to create appender for table: {}", target))?;
+    let mut stmt = source.prepare(&format!("SELECT {} FROM {}", select, quote_ident(&table.name)))?;
+    let mut rows = stmt.query([])?;
+
+    let mut copied = 0;
+    while let Some(row) = rows.next()? {
+        let mut values = Vec::with_capacity(table.columns.len());
+        for (idx, column) in table.columns.iter().enumerate() {
+            values.push(column.column_type.value(row.get_ref(idx)?));
+        }
+        appender
+            .append_row(duckdb::appender_params_from_iter(values))
+            .with_context(|| format!("Failed to copy row {} of SQLite table: {}", copied + 1, table.name))?;
+        copied += 1;
+        on_row();
+
+        if copied % BATCH_ROWS == 0 && cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+            bail!("SQLite import cancelled");
+        }
+    }
+    appender
+        .flush()
+        .with_context(|| format!("Failed to copy SQLite table: {}", table.name))?;
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_column_type() {
+        assert_eq!(column_type("BOOLEAN", &classes(&["integer"]), 0), ColumnType::Boolean);
+        assert_eq!(column_type("BOOLEAN", &classes(&["integer"]), 2), ColumnType::BigInt);
+        assert_eq!(column_type("DATE", &classes(&["text"]), 0), ColumnType::Date);
+        assert_eq!(column_type("DATE", &classes(&["text"]), 1), ColumnType::Varchar);
+        assert_eq!(column_type("DATETIME", &classes(&[]), 0), ColumnType::Varchar);
+        assert_eq!(column_type("INTEGER", &classes(&["integer", "text"]), 0), ColumnType::Varchar);
+        assert_eq!(column_type("varchar(20)", &classes(&["integer"]), 0), ColumnType::Varchar);
+        assert_eq!(column_type("REAL", &classes(&["integer", "real"]), 0), ColumnType::Double);
+        assert_eq!(column_type("", &classes(&["blob"]), 0), ColumnType::Blob);
+        assert_eq!(column_type("NUMERIC", &classes(&["integer"]), 0), ColumnType::BigInt);
+    }
+
+    #[test]
+    fn test_invalid_condition() {
+        assert_eq!(invalid_condition("\"flag\"", "bool"), "\"flag\" NOT IN (0, 1)");
+        assert_eq!(invalid_condition("d", "DATE"), "d IS NOT NULL AND date(d) IS NULL");
+        assert_eq!(invalid_condition("n", "INTEGER"), "0");
+    }
+}
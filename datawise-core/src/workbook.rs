@@ -0,0 +1,308 @@
+//! 工作簿
+//!
+//! 工作簿是按顺序排列的 SQL 单元格和 Markdown 单元格，保存为 JSON 文件，
+//! SQL 单元格保存上次执行的结果摘要。
+//!
+//! 执行顺序按单元格之间的依赖关系确定：读取某张表的单元格依赖创建该表
+//! （`CREATE TABLE`/`CREATE VIEW`）的单元格；没有依赖关系的单元格保持文档顺序。
+//! 依赖关系按 SQL 中出现的名称分析，只是近似的（例如与表同名的列也会被当作引用）。
+
+use crate::persist;
+use crate::protocol::{CellKind, CellResult, Workbook, WorkbookCell};
+use crate::sql::{tokenize, Token};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+impl Workbook {
+    /// 读取工作簿文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read workbook: {}", path.display()))?;
+        let mut workbook: Self =
+            serde_json::from_str(&text).with_context(|| format!("Invalid workbook: {}", path.display()))?;
+        workbook.assign_ids()?;
+        Ok(workbook)
+    }
+
+    /// 写入工作簿文件，为没有 ID 的单元格生成 ID
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        self.assign_ids()?;
+        let text = serde_json::to_string_pretty(self)?;
+        persist::write_atomic(path, text).with_context(|| format!("Failed to write workbook: {}", path.display()))
+    }
+
+    /// 按 ID 查找 SQL 单元格的位置
+    pub fn sql_cell(&self, id: &str) -> Result<usize> {
+        let index = self
+            .cells
+            .iter()
+            .position(|c| c.id == id)
+            .with_context(|| format!("Workbook cell not found: {}", id))?;
+        if self.cells[index].kind != CellKind::Sql {
+            bail!("Workbook cell is not a SQL cell: {}", id);
+        }
+        Ok(index)
+    }
+
+    /// SQL 单元格的执行顺序（单元格位置）
+    ///
+    /// `target` 为 `None` 时包括所有 SQL 单元格；否则只包括该 SQL 单元格及它（直接或间接）依赖的单元格。
+    pub fn execution_order(&self, target: Option<usize>) -> Result<Vec<usize>> {
+        let dependencies = self.dependencies();
+
+        let selected: BTreeSet<usize> = match target {
+            None => dependencies.keys().copied().collect(),
+            Some(index) => {
+                let mut selected = BTreeSet::new();
+                let mut pending = vec![index];
+                while let Some(index) = pending.pop() {
+                    if selected.insert(index) {
+                        pending.extend(dependencies[&index].iter().copied());
+                    }
+                }
+                selected
+            }
+        };
+
+        // 拓扑排序，每次取文档中最靠前的可执行单元格
+        let mut order = Vec::with_capacity(selected.len());
+        let mut done = HashSet::new();
+        while order.len() < selected.len() {
+            let Some(&next) = selected
+                .iter()
+                .find(|i| !done.contains(*i) && dependencies[*i].iter().all(|d| done.contains(d)))
+            else {
+                let ids: Vec<&str> = selected
+                    .iter()
+                    .filter(|i| !done.contains(*i))
+                    .map(|&i| self.cells[i].id.as_str())
+                    .collect();
+                bail!("Workbook cells have circular dependencies: {}", ids.join(", "));
+            };
+            done.insert(next);
+            order.push(next);
+        }
+        Ok(order)
+    }
+
+    /// 每个 SQL 单元格依赖的单元格
+    ///
+    /// 同一张表被多个单元格创建时，依赖文档中在它之前最近的那个（之前没有时依赖之后第一个）。
+    fn dependencies(&self) -> HashMap<usize, Vec<usize>> {
+        let analyzed: Vec<(usize, SqlNames)> = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.kind == CellKind::Sql)
+            .map(|(i, c)| (i, SqlNames::analyze(&c.source)))
+            .collect();
+
+        let mut creators: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, names) in &analyzed {
+            for table in &names.created {
+                creators.entry(table.as_str()).or_default().push(*index);
+            }
+        }
+
+        analyzed
+            .iter()
+            .map(|(index, names)| {
+                let mut depends: Vec<usize> = names
+                    .referenced
+                    .iter()
+                    .filter(|name| !names.created.contains(*name))
+                    .filter_map(|name| creators.get(name.as_str()))
+                    .filter_map(|cells| {
+                        cells
+                            .iter()
+                            .rev()
+                            .find(|&&c| c < *index)
+                            .or_else(|| cells.iter().find(|&&c| c > *index))
+                            .copied()
+                    })
+                    .collect();
+                depends.sort_unstable();
+                depends.dedup();
+                (*index, depends)
+            })
+            .collect()
+    }
+
+    /// 去掉 ID 两侧的空白，为没有 ID 的单元格生成 `cell-<序号>`，ID 重复时报错
+    fn assign_ids(&mut self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for cell in &mut self.cells {
+            cell.id = cell.id.trim().to_string();
+            if !cell.id.is_empty() && !seen.insert(cell.id.clone()) {
+                bail!("Duplicate workbook cell id: {}", cell.id);
+            }
+        }
+        let mut next = 1;
+        for cell in self.cells.iter_mut().filter(|c| c.id.is_empty()) {
+            while seen.contains(&format!("cell-{}", next)) {
+                next += 1;
+            }
+            cell.id = format!("cell-{}", next);
+            seen.insert(cell.id.clone());
+        }
+        Ok(())
+    }
+}
+
+impl WorkbookCell {
+    /// SQL 单元格
+    pub fn sql(id: &str, source: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            kind: CellKind::Sql,
+            source: source.to_string(),
+            result: None,
+        }
+    }
+
+    /// Markdown 单元格
+    pub fn markdown(id: &str, source: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            kind: CellKind::Markdown,
+            source: source.to_string(),
+            result: None,
+        }
+    }
+}
+
+impl CellResult {
+    /// 执行成功的结果摘要
+    pub fn succeeded(
+        started: SystemTime,
+        duration: Duration,
+        row_count: usize,
+        column_count: usize,
+        preview: String,
+    ) -> Self {
+        Self {
+            executed_at: DateTime::<Utc>::from(started).to_rfc3339_opts(SecondsFormat::Millis, true),
+            duration_ms: duration.as_millis() as u64,
+            row_count: row_count as u64,
+            column_count: column_count as u64,
+            preview,
+            error: None,
+        }
+    }
+
+    /// 执行失败的结果摘要
+    pub fn failed(started: SystemTime, duration: Duration, error: &anyhow::Error) -> Self {
+        Self {
+            error: Some(error.to_string()),
+            ..Self::succeeded(started, duration, 0, 0, String::new())
+        }
+    }
+}
+
+/// SQL 中创建的表和出现的名称（小写，带前缀的名称只取最后一段）
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SqlNames {
+    created: HashSet<String>,
+    referenced: HashSet<String>,
+}
+
+impl SqlNames {
+    fn analyze(sql: &str) -> Self {
+        let tokens = tokenize(sql);
+        let mut names = Self::default();
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Word(word) if word == "create" => {
+                    if let Some(table) = created_table(&tokens[i + 1..]) {
+                        names.created.insert(table);
+                    }
+                }
+                Token::Word(name) | Token::Quoted(name) => {
+                    names.referenced.insert(name.clone());
+                }
+                _ => {}
+            }
+        }
+        names
+    }
+}
+
+/// `CREATE [OR REPLACE] [TEMP|TEMPORARY] TABLE|VIEW [IF NOT EXISTS] 名称` 中的表名
+fn created_table(tokens: &[Token]) -> Option<String> {
+    let mut rest = tokens;
+    let word = |token: Option<&Token>, expected: &[&str]| match token {
+        Some(Token::Word(w)) => expected.contains(&w.as_str()),
+        _ => false,
+    };
+    if word(rest.first(), &["or"]) && word(rest.get(1), &["replace"]) {
+        rest = &rest[2..];
+    }
+    if word(rest.first(), &["temp", "temporary"]) {
+        rest = &rest[1..];
+    }
+    if !word(rest.first(), &["table", "view"]) {
+        return None;
+    }
+    rest = &rest[1..];
+    if word(rest.first(), &["if"]) && word(rest.get(1), &["not"]) && word(rest.get(2), &["exists"]) {
+        rest = &rest[3..];
+    }
+
+    // 带前缀的名称取最后一段
+    let mut name = None;
+    let mut iter = rest.iter().peekable();
+    while let Some(Token::Word(part) | Token::Quoted(part)) = iter.next() {
+        name = Some(part.clone());
+        if iter.peek() != Some(&&Token::Dot) {
+            break;
+        }
+        iter.next();
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_names() {
+        let names = SqlNames::analyze(
+            "-- create table commented\n\
+             CREATE OR REPLACE TEMP TABLE IF NOT EXISTS main.\"Monthly\" AS \
+             SELECT 'create table quoted' AS note, * FROM Sales /* CREATE VIEW x */",
+        );
+        assert_eq!(names.created, HashSet::from(["monthly".to_string()]));
+        assert!(names.referenced.contains("sales"));
+        assert!(!names.referenced.contains("quoted"));
+        assert!(!names.referenced.contains("x"));
+    }
+
+    #[test]
+    fn test_execution_order() {
+        let mut workbook = Workbook {
+            cells: vec![
+                WorkbookCell::markdown("", "# Monthly sales"),
+                WorkbookCell::sql("report", "SELECT * FROM monthly ORDER BY month"),
+                WorkbookCell::sql("monthly", "CREATE VIEW monthly AS SELECT month, SUM(amount) FROM sales GROUP BY 1"),
+                WorkbookCell::sql("other", "SELECT 1"),
+                WorkbookCell::sql("sales", "CREATE TABLE sales AS SELECT * FROM raw_sales"),
+            ],
+        };
+        workbook.assign_ids().unwrap();
+        assert_eq!(workbook.cells[0].id, "cell-1");
+
+        // report 依赖 monthly，monthly 依赖 sales；other 没有依赖，保持文档顺序
+        assert_eq!(workbook.execution_order(None).unwrap(), [3, 4, 2, 1]);
+        assert_eq!(workbook.execution_order(Some(1)).unwrap(), [4, 2, 1]);
+        assert_eq!(workbook.sql_cell("report").unwrap(), 1);
+        assert!(workbook.sql_cell("cell-1").is_err());
+
+        workbook.cells[4].source = "CREATE TABLE sales AS SELECT * FROM report_source JOIN monthly USING (month)".into();
+        let err = workbook.execution_order(None).unwrap_err();
+        assert!(err.to_string().contains("circular"));
+    }
+}
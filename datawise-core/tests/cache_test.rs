@@ -0,0 +1,125 @@
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType, FileFmt, EventKind, ImportOptions};
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_result_cache() {
+    use datawise_core::{CacheAction, CacheStats, CacheStatus};
+
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("sales.csv");
+    fs::write(&csv_path, "region,amount\nnorth,10\nsouth,20\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+    let import = || CmdType::ImportFile {
+        path: csv_path.to_string_lossy().to_string(),
+        fmt: FileFmt::Csv,
+        table_name: Some("sales".to_string()),
+        overwrite: true,
+        options: ImportOptions::default(),
+    };
+    run_until_finished(&core, 1, import()).await;
+
+    // 执行 SQL，返回结果预览和缓存状态
+    let query = |task_id: u64, sql: &str| {
+        let core = &core;
+        let sql = sql.to_string();
+        async move {
+            let mut rx = core.subscribe();
+            core.handle(Command { task_id, cmd_type: CmdType::ExecuteSql { sql } }).await.unwrap();
+            while let Ok(event) = rx.recv().await {
+                match event.kind {
+                    EventKind::Finished { preview, cache, .. } => return (preview, cache),
+                    EventKind::Error(e) => panic!("Unexpected error: {}", e),
+                    _ => {}
+                }
+            }
+            panic!("Did not receive Finished event");
+        }
+    };
+    let total = "SELECT CAST(SUM(amount) AS BIGINT) AS total FROM sales";
+
+    // 默认不缓存
+    assert_eq!(query(2, total).await.1, None);
+
+    run_until_finished(
+        &core,
+        3,
+        CmdType::Cache { action: CacheAction::Configure { enabled: true, max_bytes: None } },
+    )
+    .await;
+    assert_eq!(query(4, total).await.1, Some(CacheStatus::Miss));
+    // 只是写法不同的同一条查询命中缓存
+    let (preview, cache) = query(5, "select cast(sum(amount) as bigint) as total\n  from SALES;").await;
+    assert!(matches!(cache, Some(CacheStatus::Hit { .. })));
+    assert!(preview.contains("30"), "{}", preview);
+    // 修改数据后失效
+    assert_eq!(query(6, "UPDATE sales SET amount = amount + 1").await.1, None);
+    let (preview, cache) = query(7, total).await;
+    assert_eq!(cache, Some(CacheStatus::Miss));
+    assert!(preview.contains("32"), "{}", preview);
+    // 重新导入后失效
+    run_until_finished(&core, 8, import()).await;
+    let (preview, cache) = query(9, total).await;
+    assert_eq!(cache, Some(CacheStatus::Miss));
+    assert!(preview.contains("30"), "{}", preview);
+    // 视图读取的表被修改后，视图的查询也失效
+    query(10, "CREATE VIEW north AS SELECT * FROM sales WHERE region = 'north'").await;
+    let count = "SELECT COUNT(*) AS n FROM north";
+    assert_eq!(query(11, count).await.1, Some(CacheStatus::Miss));
+    assert!(matches!(query(12, count).await.1, Some(CacheStatus::Hit { .. })));
+    query(13, "INSERT INTO sales VALUES ('north', 5)").await;
+    assert_eq!(query(14, count).await.1, Some(CacheStatus::Miss));
+    // 结果不确定的查询不缓存
+    assert_eq!(query(15, "SELECT random() AS r").await.1, None);
+
+    let (entries, _, preview) = run_until_finished(&core, 16, CmdType::Cache { action: CacheAction::Stats }).await;
+    let stats: CacheStats = serde_json::from_str(&preview).unwrap();
+    assert_eq!(entries, stats.entries);
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.misses, 5);
+
+    // 关闭时清空
+    let (entries, _, _) = run_until_finished(
+        &core,
+        17,
+        CmdType::Cache { action: CacheAction::Configure { enabled: false, max_bytes: None } },
+    )
+    .await;
+    assert_eq!(entries, 0);
+    assert_eq!(query(18, total).await.1, None);
+}
+
+#[tokio::test]
+async fn test_result_cache_skips_catalog_queries() {
+    use datawise_core::CacheAction;
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::Cache { action: CacheAction::Configure { enabled: true, max_bytes: None } },
+    )
+    .await;
+
+    // 列出表，建表后再次列出：结果不来自缓存
+    let list = || CmdType::ExecuteSql {
+        sql: "SELECT table_name FROM information_schema.tables ORDER BY table_name".to_string(),
+    };
+    let mut rx = core.subscribe();
+    let (row_count, _, _) = run_until_finished(&core, 2, list()).await;
+    assert_eq!(row_count, 0);
+    run_until_finished(&core, 3, CmdType::ExecuteSql { sql: "CREATE TABLE foo (id INTEGER)".to_string() }).await;
+    let (row_count, _, preview) = run_until_finished(&core, 4, list()).await;
+    assert_eq!(row_count, 1);
+    assert!(preview.contains("foo"), "{}", preview);
+
+    while let Ok(event) = rx.try_recv() {
+        if let EventKind::Finished { cache, .. } = event.kind {
+            assert_eq!(cache, None);
+        }
+    }
+}
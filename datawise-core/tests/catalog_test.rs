@@ -0,0 +1,512 @@
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType, FileFmt, EventKind, ImportOptions};
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_catalog_lists_and_describes_tables() {
+    use datawise_core::{DatabaseInfo, SchemaInfo, TableInfo, TableSource};
+
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("customers.csv");
+    fs::write(&csv_path, "id,name\n1,Alice\n2,Bob\n3,\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: csv_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+    run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "CREATE SCHEMA staging".to_string(),
+        },
+    )
+    .await;
+    run_until_finished(
+        &core,
+        3,
+        CmdType::ExecuteSql {
+            sql: "CREATE TABLE staging.orders (id INTEGER NOT NULL, customer_id INTEGER, total DOUBLE)".to_string(),
+        },
+    )
+    .await;
+    run_until_finished(
+        &core,
+        4,
+        CmdType::ExecuteSql {
+            sql: "CREATE VIEW named_customers AS SELECT * FROM customers WHERE name IS NOT NULL".to_string(),
+        },
+    )
+    .await;
+
+    let (row_count, _, preview) = run_until_finished(&core, 5, CmdType::ListDatabases).await;
+    let databases: Vec<DatabaseInfo> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(row_count, databases.len());
+    assert!(databases.iter().all(|d| d.name != "system" && d.name != "temp"));
+
+    let (_, _, preview) = run_until_finished(&core, 6, CmdType::ListSchemas { database: None }).await;
+    let schemas: Vec<SchemaInfo> = serde_json::from_str(&preview).unwrap();
+    let staging = schemas.iter().find(|s| s.name == "staging").expect("staging schema should be listed");
+    assert_eq!(staging.table_count, 1);
+
+    let (row_count, _, preview) = run_until_finished(
+        &core,
+        7,
+        CmdType::ListTables {
+            database: None,
+            schema: None,
+        },
+    )
+    .await;
+    let tables: Vec<TableInfo> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(row_count, 3);
+    let names: Vec<_> = tables.iter().map(|t| format!("{}.{}", t.schema, t.name)).collect();
+    assert_eq!(names, ["main.customers", "main.named_customers", "staging.orders"]);
+
+    // 导入的表带有数据来源，视图没有行数
+    let customers = &tables[0];
+    assert_eq!(
+        customers.source,
+        Some(TableSource::File {
+            paths: vec![csv_path.to_string_lossy().to_string()],
+            fmt: FileFmt::Csv,
+        })
+    );
+    assert_eq!(tables[1].table_type, "VIEW");
+    assert_eq!(tables[1].row_count, None);
+    assert_eq!(tables[1].source, None);
+
+    // 按 schema 过滤，列按定义顺序并带有可空性
+    let (_, _, preview) = run_until_finished(
+        &core,
+        8,
+        CmdType::ListTables {
+            database: None,
+            schema: Some("staging".to_string()),
+        },
+    )
+    .await;
+    let tables: Vec<TableInfo> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(tables.len(), 1);
+    let columns: Vec<_> = tables[0]
+        .columns
+        .iter()
+        .map(|c| (c.name.as_str(), c.data_type.as_str(), c.nullable))
+        .collect();
+    assert_eq!(
+        columns,
+        [("id", "INTEGER", false), ("customer_id", "INTEGER", true), ("total", "DOUBLE", true)]
+    );
+
+    // 描述表：名称不区分大小写，行数为精确值
+    let (row_count, column_count, preview) = run_until_finished(
+        &core,
+        9,
+        CmdType::DescribeTable {
+            name: "Customers".to_string(),
+        },
+    )
+    .await;
+    let table: TableInfo = serde_json::from_str(&preview).unwrap();
+    assert_eq!((row_count, column_count), (3, 2));
+    assert_eq!(table.name, "customers");
+    assert_eq!(table.row_count, Some(3));
+    assert!(table.source.is_some());
+
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        10,
+        CmdType::DescribeTable {
+            name: "staging.orders".to_string(),
+        },
+    )
+    .await;
+    assert_eq!(row_count, 0);
+
+    let result = core
+        .handle(Command {
+            task_id: 11,
+            cmd_type: CmdType::DescribeTable {
+                name: "missing".to_string(),
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("Table not found"));
+}
+
+/// 执行表管理命令，返回结果和 `Finished` 之前收到的目录变化
+async fn manage_table(
+    core: &DataWise,
+    task_id: u64,
+    action: datawise_core::TableAction,
+) -> (datawise_core::TableChange, Option<datawise_core::CatalogChange>) {
+    let mut rx = core.subscribe();
+    core.handle(Command {
+        task_id,
+        cmd_type: CmdType::ManageTable { action },
+    })
+    .await
+    .unwrap();
+
+    let mut change = None;
+    while let Ok(event) = rx.recv().await {
+        match event.kind {
+            EventKind::CatalogChanged { created, dropped, altered } => {
+                change = Some(datawise_core::CatalogChange { created, dropped, altered });
+            }
+            EventKind::Finished { preview, .. } => return (serde_json::from_str(&preview).unwrap(), change),
+            EventKind::Error(e) => panic!("Unexpected error: {}", e),
+            _ => {}
+        }
+    }
+    panic!("Did not receive Finished event");
+}
+
+#[tokio::test]
+async fn test_table_management_actions() {
+    use datawise_core::{TableAction, TableSource};
+
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("orders.csv");
+    fs::write(&csv_path, "id,total\n1,10\n2,20\n3,30\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: csv_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+
+    // 重命名：数据来源随表移动
+    let (result, change) = manage_table(
+        &core,
+        2,
+        TableAction::Rename {
+            name: "orders".to_string(),
+            new_name: "orders_2024".to_string(),
+        },
+    )
+    .await;
+    let change = change.expect("Rename should emit CatalogChanged");
+    assert_eq!(change.dropped, ["memory.main.orders"]);
+    assert_eq!(change.created, ["memory.main.orders_2024"]);
+    let table = result.table.unwrap();
+    assert_eq!(table.row_count, Some(3));
+    assert!(matches!(table.source, Some(TableSource::File { .. })));
+
+    // 只复制结构
+    let (result, change) = manage_table(
+        &core,
+        3,
+        TableAction::Duplicate {
+            name: "orders_2024".to_string(),
+            new_name: "orders_empty".to_string(),
+            schema_only: true,
+        },
+    )
+    .await;
+    assert_eq!(change.unwrap().created, ["memory.main.orders_empty"]);
+    assert_eq!(result.rows_affected, 0);
+    assert_eq!(result.table.unwrap().columns.len(), 2);
+
+    // 复制数据后清空副本
+    let (result, _) = manage_table(
+        &core,
+        4,
+        TableAction::Duplicate {
+            name: "orders_2024".to_string(),
+            new_name: "orders_copy".to_string(),
+            schema_only: false,
+        },
+    )
+    .await;
+    assert_eq!(result.rows_affected, 3);
+    let (result, change) = manage_table(
+        &core,
+        5,
+        TableAction::Truncate {
+            name: "orders_copy".to_string(),
+            dry_run: false,
+        },
+    )
+    .await;
+    assert_eq!(result.rows_affected, 3);
+    let table = result.table.unwrap();
+    assert_eq!(table.row_count, Some(0));
+    assert!(table.source.is_some(), "Truncate keeps the data source");
+    assert_eq!(change.unwrap().altered, ["memory.main.orders_copy"]);
+
+    // 用 SQL 修改数据时保留数据来源
+    run_until_finished(
+        &core,
+        19,
+        CmdType::ExecuteSql { sql: "INSERT INTO orders_copy VALUES (4, 40)".to_string() },
+    )
+    .await;
+    let (_, _, preview) = run_until_finished(
+        &core,
+        18,
+        CmdType::DescribeTable {
+            name: "orders_copy".to_string(),
+        },
+    )
+    .await;
+    let table: datawise_core::TableInfo = serde_json::from_str(&preview).unwrap();
+    assert_eq!(table.row_count, Some(1));
+    assert!(table.source.is_some(), "Changing the data keeps the data source");
+
+    // 用 SQL 替换表后，原来的数据来源不再适用
+    run_until_finished(
+        &core,
+        23,
+        CmdType::ExecuteSql { sql: "CREATE OR REPLACE TABLE orders_copy AS SELECT 1 AS id".to_string() },
+    )
+    .await;
+    let (_, _, preview) = run_until_finished(
+        &core,
+        24,
+        CmdType::DescribeTable {
+            name: "orders_copy".to_string(),
+        },
+    )
+    .await;
+    let table: datawise_core::TableInfo = serde_json::from_str(&preview).unwrap();
+    assert!(table.source.is_none());
+
+    // 把查询保存为视图，再替换为物化表
+    let sql = "SELECT id, total * 2 AS doubled FROM orders_2024 WHERE total > 10;".to_string();
+    let (result, change) = manage_table(
+        &core,
+        6,
+        TableAction::SaveQuery {
+            sql: sql.clone(),
+            name: "big_orders".to_string(),
+            materialize: false,
+            overwrite: false,
+        },
+    )
+    .await;
+    assert_eq!(change.unwrap().created, ["memory.main.big_orders"]);
+    let view = result.table.unwrap();
+    assert_eq!(view.table_type, "VIEW");
+    assert_eq!(
+        view.source,
+        Some(TableSource::Query {
+            sql: sql.trim_end_matches(';').to_string()
+        })
+    );
+
+    let result = core
+        .handle(Command {
+            task_id: 7,
+            cmd_type: CmdType::ManageTable {
+                action: TableAction::SaveQuery {
+                    sql: sql.clone(),
+                    name: "big_orders".to_string(),
+                    materialize: true,
+                    overwrite: false,
+                },
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("already exists"));
+
+    let (result, change) = manage_table(
+        &core,
+        8,
+        TableAction::SaveQuery {
+            sql,
+            name: "big_orders".to_string(),
+            materialize: true,
+            overwrite: true,
+        },
+    )
+    .await;
+    assert_eq!(change.unwrap().altered, ["memory.main.big_orders"]);
+    assert_eq!(result.rows_affected, 2);
+    assert_eq!(result.table.unwrap().table_type, "BASE TABLE");
+
+    // 预览删除：统计行数和引用它的视图，目录不变
+    run_until_finished(
+        &core,
+        20,
+        CmdType::ExecuteSql { sql: "CREATE VIEW recent_orders AS SELECT * FROM Orders_2024 WHERE id > 1".to_string() },
+    )
+    .await;
+    let (result, change) = manage_table(
+        &core,
+        21,
+        TableAction::Drop {
+            name: "orders_2024".to_string(),
+            dry_run: true,
+        },
+    )
+    .await;
+    assert!(change.is_none());
+    assert_eq!(result.rows_affected, 3);
+    assert_eq!(result.dependent_views, ["memory.main.recent_orders"]);
+    assert_eq!(result.table.unwrap().name, "orders_2024");
+
+    let (result, change) = manage_table(
+        &core,
+        22,
+        TableAction::Truncate {
+            name: "orders_2024".to_string(),
+            dry_run: true,
+        },
+    )
+    .await;
+    assert!(change.is_none());
+    assert_eq!(result.rows_affected, 3);
+    assert_eq!(result.table.unwrap().row_count, Some(3));
+
+    // 删除：结果中带有被删除的行数，之后表不存在
+    let (result, change) = manage_table(
+        &core,
+        9,
+        TableAction::Drop {
+            name: "ORDERS_2024".to_string(),
+            dry_run: false,
+        },
+    )
+    .await;
+    assert!(result.table.is_none());
+    assert_eq!(result.rows_affected, 3);
+    assert_eq!(result.dependent_views, ["memory.main.recent_orders"]);
+    assert_eq!(change.unwrap().dropped, ["memory.main.orders_2024"]);
+
+    let result = core
+        .handle(Command {
+            task_id: 10,
+            cmd_type: CmdType::DescribeTable {
+                name: "orders_2024".to_string(),
+            },
+        })
+        .await;
+    assert!(result.is_err());
+}
+
+/// 执行命令直到完成，返回期间收到的目录变化
+async fn catalog_changes(core: &DataWise, task_id: u64, cmd_type: CmdType) -> Vec<datawise_core::CatalogChange> {
+    let mut rx = core.subscribe();
+    core.handle(Command { task_id, cmd_type }).await.unwrap();
+
+    let mut changes = Vec::new();
+    while let Ok(event) = rx.recv().await {
+        match event.kind {
+            EventKind::CatalogChanged { created, dropped, altered } => {
+                changes.push(datawise_core::CatalogChange { created, dropped, altered });
+            }
+            EventKind::Finished { .. } => return changes,
+            EventKind::Error(e) => panic!("Unexpected error: {}", e),
+            _ => {}
+        }
+    }
+    panic!("Did not receive Finished event");
+}
+
+#[tokio::test]
+async fn test_catalog_changed_events() {
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("events.csv");
+    fs::write(&csv_path, "id,kind\n1,click\n2,view\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+    let import = |task_id: u64, overwrite: bool| {
+        (
+            task_id,
+            CmdType::ImportFile {
+                path: csv_path.to_string_lossy().to_string(),
+                fmt: FileFmt::Csv,
+                table_name: None,
+                overwrite,
+                options: ImportOptions::default(),
+            },
+        )
+    };
+
+    // 首次导入新建表
+    let (task_id, cmd_type) = import(1, false);
+    let changes = catalog_changes(&core, task_id, cmd_type).await;
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].created, ["memory.main.events"]);
+
+    // 覆盖导入：结构和行数不变也算修改
+    let (task_id, cmd_type) = import(2, true);
+    let changes = catalog_changes(&core, task_id, cmd_type).await;
+    assert_eq!(changes.len(), 1);
+    assert!(changes[0].created.is_empty());
+    assert_eq!(changes[0].altered, ["memory.main.events"]);
+
+    // 查询不改变目录
+    let changes = catalog_changes(
+        &core,
+        3,
+        CmdType::ExecuteSql {
+            sql: "SELECT * FROM events".to_string(),
+        },
+    )
+    .await;
+    assert!(changes.is_empty());
+
+    // SQL 中的 DDL 和增删数据
+    let changes = catalog_changes(
+        &core,
+        4,
+        CmdType::ExecuteSql {
+            sql: "CREATE VIEW clicks AS SELECT * FROM events WHERE kind = 'click'".to_string(),
+        },
+    )
+    .await;
+    assert_eq!(changes[0].created, ["memory.main.clicks"]);
+
+    let changes = catalog_changes(
+        &core,
+        5,
+        CmdType::ExecuteSql {
+            sql: "ALTER TABLE events ADD COLUMN ts TIMESTAMP".to_string(),
+        },
+    )
+    .await;
+    assert_eq!(changes[0].altered, ["memory.main.events"]);
+
+    let changes = catalog_changes(
+        &core,
+        6,
+        CmdType::ExecuteSql {
+            sql: "INSERT INTO events VALUES (3, 'click', NULL)".to_string(),
+        },
+    )
+    .await;
+    assert_eq!(changes[0].altered, ["memory.main.events"]);
+
+    let changes = catalog_changes(
+        &core,
+        7,
+        CmdType::ExecuteSql {
+            sql: "DROP VIEW clicks".to_string(),
+        },
+    )
+    .await;
+    assert_eq!(changes[0].dropped, ["memory.main.clicks"]);
+    assert!(changes[0].created.is_empty() && changes[0].altered.is_empty());
+}
@@ -0,0 +1,34 @@
+//! 集成测试共用的辅助函数
+
+// 每个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use datawise_core::{CmdType, Command, DataWise, EventKind};
+
+/// 发送命令并等待 Finished 事件，返回 (row_count, column_count, preview)
+pub async fn run_until_finished(core: &DataWise, task_id: u64, cmd_type: CmdType) -> (usize, usize, String) {
+    let (row_count, column_count, preview, _) = run_with_progress(core, task_id, cmd_type).await;
+    (row_count, column_count, preview)
+}
+
+/// 发送命令并等待 Finished 事件，返回 (row_count, column_count, preview, 进度百分比)
+pub async fn run_with_progress(core: &DataWise, task_id: u64, cmd_type: CmdType) -> (usize, usize, String, Vec<u8>) {
+    let mut rx = core.subscribe();
+    core.handle(Command { task_id, cmd_type }).await.unwrap();
+
+    let mut progress = Vec::new();
+    while let Ok(event) = rx.recv().await {
+        match event.kind {
+            EventKind::Progress { pct, .. } => progress.push(pct),
+            EventKind::Finished {
+                row_count,
+                column_count,
+                preview,
+                ..
+            } => return (row_count, column_count, preview, progress),
+            EventKind::Error(e) => panic!("Unexpected error: {}", e),
+            _ => {}
+        }
+    }
+    panic!("Did not receive Finished event");
+}
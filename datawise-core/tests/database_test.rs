@@ -0,0 +1,139 @@
+//! PostgreSQL / MySQL 连接器测试
+//!
+//! 需要数据库服务器，默认忽略。本地用 `tests/docker-compose.yml` 启动服务器后，
+//! 用 `cargo test --test database_test -- --ignored` 运行；CI 的 connectors 任务以服务容器运行。
+//! 连接参数可通过环境变量 `DATAWISE_TEST_PG_*`、`DATAWISE_TEST_MYSQL_*`
+//! （`HOST`、`PORT`、`USER`、`PASSWORD`、`DATABASE`）覆盖。
+
+mod common;
+
+use common::run_with_progress;
+use datawise_core::{CmdType, ConnectionProfile, DataWise, DatabaseAction, DatabaseKind};
+
+fn test_profile(kind: DatabaseKind) -> ConnectionProfile {
+    let (prefix, user, password, database) = match kind {
+        DatabaseKind::Postgres => ("DATAWISE_TEST_PG", "postgres", "postgres", "postgres"),
+        DatabaseKind::MySql => ("DATAWISE_TEST_MYSQL", "root", "root", "datawise_test"),
+    };
+    let var = |name: &str, default: &str| {
+        std::env::var(format!("{}_{}", prefix, name)).unwrap_or_else(|_| default.to_string())
+    };
+
+    ConnectionProfile {
+        name: "remote".to_string(),
+        kind,
+        host: var("HOST", "127.0.0.1"),
+        port: std::env::var(format!("{}_PORT", prefix)).ok().map(|p| p.parse().unwrap()),
+        user: var("USER", user),
+        password: Some(var("PASSWORD", password)),
+        database: var("DATABASE", database),
+    }
+}
+
+/// 在远程服务器上创建测试表（通过单独的 DuckDB 连接以读写方式附加）
+fn create_fixture(profile: &ConnectionProfile, schema: &str) {
+    let (extension, db_type) = match profile.kind {
+        DatabaseKind::Postgres => ("postgres", "POSTGRES"),
+        DatabaseKind::MySql => ("mysql", "MYSQL"),
+    };
+    let conn = duckdb::Connection::open_in_memory().unwrap();
+    conn.execute_batch(&format!("INSTALL {0}; LOAD {0}", extension)).unwrap();
+    conn.execute_batch(&format!(
+        "ATTACH '{}' AS fixture (TYPE {});
+         DROP TABLE IF EXISTS fixture.{schema}.dw_orders;
+         CREATE TABLE fixture.{schema}.dw_orders (id INTEGER, customer VARCHAR, amount DOUBLE);
+         INSERT INTO fixture.{schema}.dw_orders VALUES (1, 'alice', 10.5), (2, 'bob', 20.0), (3, 'alice', 4.5);",
+        profile.connection_string().replace('\'', "''"),
+        db_type,
+        schema = schema
+    ))
+    .unwrap();
+}
+
+/// 对远程数据库执行操作，返回 (row_count, column_count, preview, 进度百分比)
+async fn run_until_finished(
+    core: &DataWise,
+    task_id: u64,
+    profile: &ConnectionProfile,
+    action: DatabaseAction,
+) -> (usize, usize, String, Vec<u8>) {
+    let cmd_type = CmdType::Database {
+        profile: profile.clone(),
+        action,
+    };
+    run_with_progress(core, task_id, cmd_type).await
+}
+
+async fn check_connector(kind: DatabaseKind, schema: &str) {
+    let profile = test_profile(kind);
+    create_fixture(&profile, schema);
+
+    let core = DataWise::new().unwrap();
+
+    // 列出 schema 和表
+    let (_, _, preview, _) = run_until_finished(&core, 1, &profile, DatabaseAction::ListSchemas).await;
+    let schemas: Vec<String> = serde_json::from_str(&preview).unwrap();
+    assert!(schemas.iter().any(|s| s == schema), "{:?}", schemas);
+
+    let (_, _, preview, _) = run_until_finished(
+        &core,
+        2,
+        &profile,
+        DatabaseAction::ListTables {
+            schema: Some(schema.to_string()),
+        },
+    )
+    .await;
+    let tables: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert!(tables.iter().any(|t| t["name"] == "dw_orders" && t["schema"] == schema));
+
+    // 下推查询（使用远程方言，在服务器上聚合）
+    let (row_count, column_count, preview, _) = run_until_finished(
+        &core,
+        3,
+        &profile,
+        DatabaseAction::Query {
+            sql: "SELECT customer, COUNT(*) AS orders FROM dw_orders GROUP BY customer ORDER BY customer;"
+                .to_string(),
+        },
+    )
+    .await;
+    assert_eq!((row_count, column_count), (2, 2));
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], "alice");
+
+    // 导入查询结果，带进度
+    let (row_count, column_count, _, progress) = run_until_finished(
+        &core,
+        4,
+        &profile,
+        DatabaseAction::Import {
+            sql: "SELECT * FROM dw_orders".to_string(),
+            table_name: "orders".to_string(),
+            overwrite: false,
+        },
+    )
+    .await;
+    assert_eq!((row_count, column_count), (3, 3));
+    assert_eq!(progress.last(), Some(&100));
+
+    let cmd_type = CmdType::ExecuteSql {
+        sql: "SELECT SUM(amount) FROM orders".to_string(),
+    };
+    let (_, _, preview, _) = run_with_progress(&core, 5, cmd_type).await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], 35.0);
+}
+
+#[tokio::test]
+#[ignore = "requires a PostgreSQL server"]
+async fn test_postgres_connector() {
+    check_connector(DatabaseKind::Postgres, "public").await;
+}
+
+#[tokio::test]
+#[ignore = "requires a MySQL server"]
+async fn test_mysql_connector() {
+    let database = test_profile(DatabaseKind::MySql).database;
+    check_connector(DatabaseKind::MySql, &database).await;
+}
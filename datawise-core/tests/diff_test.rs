@@ -0,0 +1,76 @@
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType, EventKind};
+
+#[tokio::test]
+async fn test_diff_tables() {
+    use datawise_core::TableDiff;
+
+    let core = DataWise::new().unwrap();
+    for sql in [
+        "CREATE TABLE jan AS SELECT * FROM (VALUES (1, 'a', 10, 'x'), (2, 'b', 20, 'y'), (3, 'c', 30, 'z')) \
+         t(id, name, amount, note)",
+        "CREATE TABLE feb AS SELECT * FROM (VALUES (1, 'a', 10, 'n'), (2, 'B', 25, 's'), (4, 'd', 40, 'e')) \
+         t(id, name, amount, region)",
+    ] {
+        run_until_finished(&core, 1, CmdType::ExecuteSql { sql: sql.to_string() }).await;
+    }
+
+    let mut rx = core.subscribe();
+    let (row_count, column_count, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::DiffTables {
+            left: "jan".to_string(),
+            right: "feb".to_string(),
+            key_columns: vec!["id".to_string()],
+        },
+    )
+    .await;
+    let diff: TableDiff = serde_json::from_str(&preview).unwrap();
+
+    assert_eq!((row_count, column_count), (3, 2));
+    assert_eq!((diff.added_rows, diff.removed_rows, diff.changed_rows, diff.unchanged_rows), (1, 1, 1, 1));
+    let column_changes: Vec<_> = diff.column_changes.iter().map(|c| (c.column.as_str(), c.changed_rows)).collect();
+    assert_eq!(column_changes, [("name", 1), ("amount", 1)]);
+    assert_eq!(diff.schema.added_columns[0].name, "region");
+    assert_eq!(diff.schema.removed_columns[0].name, "note");
+    assert!(diff.schema.type_changes.is_empty());
+
+    // 结果表出现在目录中
+    let mut created = None;
+    while let Ok(event) = rx.try_recv() {
+        if let EventKind::CatalogChanged { created: tables, .. } = event.kind {
+            created = Some(tables);
+        }
+    }
+    assert_eq!(
+        created.unwrap(),
+        ["memory.main.diff_jan_feb_added", "memory.main.diff_jan_feb_changed", "memory.main.diff_jan_feb_removed"]
+    );
+
+    // 差异行可以直接查询
+    let query = |task_id: u64, sql: String| run_until_finished(&core, task_id, CmdType::ExecuteSql { sql });
+    let (_, _, preview) = query(3, format!("SELECT id FROM {}", diff.tables.added)).await;
+    assert_eq!(preview, r#"[{"col_0":4}]"#);
+    let (_, _, preview) = query(4, format!("SELECT id FROM {}", diff.tables.removed)).await;
+    assert_eq!(preview, r#"[{"col_0":3}]"#);
+    let (_, _, preview) = query(5, format!("SELECT id, name_left, name_right, amount_right FROM {}", diff.tables.changed)).await;
+    let rows: serde_json::Value = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows, serde_json::json!([{"col_0": 2, "col_1": "b", "col_2": "B", "col_3": 25}]));
+
+    // 键列不唯一时无法对比
+    query(6, "INSERT INTO feb VALUES (4, 'dup', 1, 'w')".to_string()).await;
+    let result = core
+        .handle(Command {
+            task_id: 7,
+            cmd_type: CmdType::DiffTables {
+                left: "jan".to_string(),
+                right: "feb".to_string(),
+                key_columns: vec!["id".to_string()],
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("not unique in feb"));
+}
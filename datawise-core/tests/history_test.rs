@@ -0,0 +1,95 @@
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType};
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_query_history() {
+    use datawise_core::{HistoryAction, HistoryEntry};
+
+    let temp_dir = TempDir::new().unwrap();
+    let history_path = temp_dir.path().join("history.json");
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(&core, 1, CmdType::ExecuteSql { sql: "SELECT 42 AS answer".to_string() }).await;
+    let result = core
+        .handle(Command {
+            task_id: 2,
+            cmd_type: CmdType::ExecuteSql { sql: "SELECT * FROM missing_table".to_string() },
+        })
+        .await;
+    assert!(result.is_err());
+
+    // 打开历史文件后，之前的记录也写入文件
+    let (count, _, _) = run_until_finished(
+        &core,
+        3,
+        CmdType::History { action: HistoryAction::Open { path: history_path.to_string_lossy().to_string() } },
+    )
+    .await;
+    assert_eq!(count, 2);
+
+    let history = |task_id: u64, action: HistoryAction| {
+        let core = &core;
+        async move {
+            let (_, _, preview) = run_until_finished(core, task_id, CmdType::History { action }).await;
+            serde_json::from_str::<Vec<HistoryEntry>>(&preview).unwrap()
+        }
+    };
+
+    // 最新的在前，记录行数和错误
+    let entries = history(4, HistoryAction::List { pinned_only: false, limit: 0 }).await;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].sql, "SELECT * FROM missing_table");
+    assert!(entries[0].error.is_some());
+    assert_eq!(entries[0].row_count, None);
+    assert_eq!(entries[1].row_count, Some(1));
+    assert!(entries[1].error.is_none());
+
+    let entries = history(5, HistoryAction::Search { text: "ANSWER".to_string(), limit: 10 }).await;
+    assert_eq!(entries.len(), 1);
+    let answer_id = entries[0].id;
+
+    // 置顶
+    let (_, _, preview) = run_until_finished(
+        &core,
+        6,
+        CmdType::History { action: HistoryAction::Pin { id: answer_id, pinned: true } },
+    )
+    .await;
+    assert!(serde_json::from_str::<HistoryEntry>(&preview).unwrap().pinned);
+    let entries = history(7, HistoryAction::List { pinned_only: true, limit: 0 }).await;
+    assert_eq!(entries.iter().map(|e| e.id).collect::<Vec<_>>(), [answer_id]);
+
+    // 重新执行，结果与 ExecuteSql 相同，并记录为新的一条
+    let (row_count, _, preview) = run_until_finished(
+        &core,
+        8,
+        CmdType::History { action: HistoryAction::Rerun { id: answer_id } },
+    )
+    .await;
+    assert_eq!(row_count, 1);
+    assert!(preview.contains("42"));
+
+    // 释放后新实例打开同一文件，记录仍在
+    drop(core);
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        9,
+        CmdType::History { action: HistoryAction::Open { path: history_path.to_string_lossy().to_string() } },
+    )
+    .await;
+    let (_, _, preview) = run_until_finished(
+        &core,
+        10,
+        CmdType::History { action: HistoryAction::List { pinned_only: false, limit: 2 } },
+    )
+    .await;
+    let entries: Vec<HistoryEntry> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].sql, "SELECT 42 AS answer");
+    assert!(!entries[0].pinned);
+    assert!(entries[1].error.is_some());
+}
@@ -1,4 +1,7 @@
-use datawise_core::{DataWise, Command, CmdType, FileFmt, EventKind};
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType, FileFmt, EventKind, ExportOptions, ImportOptions};
 use std::fs;
 use tempfile::TempDir;
 
@@ -24,6 +27,7 @@ async fn test_csv_import() {
             fmt: FileFmt::Csv,
             table_name: Some("test_data".to_string()),
             overwrite: false,
+            options: ImportOptions::default(),
         },
     };
 
@@ -91,6 +95,8 @@ async fn test_csv_export() {
             source: "test_export".to_string(),
             path: export_path.to_string_lossy().to_string(),
             fmt: FileFmt::Csv,
+            overwrite: false,
+            options: ExportOptions::default(),
         },
     };
 
@@ -140,6 +146,7 @@ async fn test_import_then_query() {
                 fmt: FileFmt::Csv,
                 table_name: Some("users".to_string()),
                 overwrite: false,
+                options: ImportOptions::default(),
             },
         };
 
@@ -203,6 +210,7 @@ async fn test_import_export_roundtrip() {
                 fmt: FileFmt::Csv,
                 table_name: Some("scores".to_string()),
                 overwrite: false,
+                options: ImportOptions::default(),
             },
         };
 
@@ -224,6 +232,8 @@ async fn test_import_export_roundtrip() {
                 source: "scores".to_string(),
                 path: export_path.to_string_lossy().to_string(),
                 fmt: FileFmt::Csv,
+                overwrite: false,
+                options: ExportOptions::default(),
             },
         };
 
@@ -267,6 +277,7 @@ async fn test_import_with_preview_data() {
             fmt: FileFmt::Csv,
             table_name: Some("preview_data".to_string()),
             overwrite: false,
+            options: ImportOptions::default(),
         },
     };
 
@@ -282,6 +293,7 @@ async fn test_import_with_preview_data() {
                 row_count: rc,
                 column_count: cc,
                 preview,
+                ..
             } => {
                 preview_received = true;
                 row_count = rc;
@@ -352,6 +364,7 @@ async fn test_import_with_overwrite() {
                 fmt: FileFmt::Csv,
                 table_name: Some("overwrite_table".to_string()),
                 overwrite: false,
+                options: ImportOptions::default(),
             },
         };
 
@@ -380,6 +393,7 @@ async fn test_import_with_overwrite() {
                 fmt: FileFmt::Csv,
                 table_name: Some("overwrite_table".to_string()),
                 overwrite: true,
+                options: ImportOptions::default(),
             },
         };
 
@@ -417,6 +431,7 @@ async fn test_import_progress_events() {
             fmt: FileFmt::Csv,
             table_name: Some("progress_data".to_string()),
             overwrite: false,
+            options: ImportOptions::default(),
         },
     };
 
@@ -444,6 +459,7 @@ async fn test_import_progress_events() {
             EventKind::Error(e) => {
                 panic!("Unexpected error: {}", e);
             }
+            _ => {}
         }
     }
 
@@ -452,6 +468,148 @@ async fn test_import_progress_events() {
     assert!(received_finished, "Did not receive Finished event");
 }
 
+#[tokio::test]
+async fn test_export_progress_and_summary() {
+    use datawise_core::ExportSummary;
+
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("export.csv");
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ExecuteSql {
+            sql: "CREATE TABLE big AS SELECT i AS id, 'row_' || i AS name, i * 0.5 AS value FROM range(2000000) t(i)"
+                .to_string(),
+        },
+    )
+    .await;
+
+    let mut rx = core.subscribe();
+    let (row_count, column_count, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExportFile {
+            source: "big".to_string(),
+            path: csv_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            overwrite: false,
+            options: ExportOptions::default(),
+        },
+    )
+    .await;
+
+    // Finished 报告导出行数、列数，preview 中是导出摘要
+    assert_eq!((row_count, column_count), (2_000_000, 3));
+    let summary: ExportSummary = serde_json::from_str(&preview).unwrap();
+    assert_eq!(summary.rows_exported, 2_000_000);
+    assert_eq!(summary.column_count, 3);
+    assert_eq!(summary.bytes_written, fs::metadata(&csv_path).unwrap().len());
+    assert!(summary.elapsed_ms > 0);
+
+    // 进度单调递增；COPY 执行期间行数未知，最后一次报告全部行
+    let mut last = None;
+    let mut last_pct = 0;
+    while let Ok(event) = rx.try_recv() {
+        if let EventKind::Progress {
+            pct,
+            bytes_processed,
+            rows_processed,
+            total_rows,
+            ..
+        } = event.kind
+        {
+            assert!(pct <= 100);
+            assert!(pct >= last_pct, "Progress should not go backwards");
+            assert!(total_rows.is_none() || total_rows == Some(2_000_000));
+            last_pct = pct;
+            last = Some((rows_processed, bytes_processed));
+        }
+    }
+    assert_eq!(last_pct, 100);
+    assert_eq!(last, Some((Some(2_000_000), summary.bytes_written)));
+}
+
+#[tokio::test]
+async fn test_export_temp_table() {
+    let temp_dir = TempDir::new().unwrap();
+    let export_path = temp_dir.path().join("recent.csv");
+
+    // 临时表只在共享连接中可见，导出不能在单独的进度连接上执行
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ExecuteSql { sql: "CREATE TEMP TABLE recent AS SELECT range AS id FROM range(3)".to_string() },
+    )
+    .await;
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExportFile {
+            source: "recent".to_string(),
+            path: export_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            overwrite: false,
+            options: ExportOptions::default(),
+        },
+    )
+    .await;
+    assert_eq!(row_count, 3);
+    assert_eq!(fs::read_to_string(&export_path).unwrap(), "id\n0\n1\n2\n");
+}
+
+#[tokio::test]
+async fn test_export_overwrite_protection_and_cleanup() {
+    use datawise_core::ExportError;
+
+    let temp_dir = TempDir::new().unwrap();
+    let export_path = temp_dir.path().join("out.csv");
+    let export_cmd = |task_id, source: &str, overwrite| Command {
+        task_id,
+        cmd_type: CmdType::ExportFile {
+            source: source.to_string(),
+            path: export_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            overwrite,
+            options: ExportOptions::default(),
+        },
+    };
+
+    let core = DataWise::new().unwrap();
+    for (task_id, sql) in [
+        (1, "CREATE TABLE first AS SELECT 1 AS id"),
+        (2, "CREATE TABLE second AS SELECT 2 AS id"),
+    ] {
+        run_until_finished(&core, task_id, CmdType::ExecuteSql { sql: sql.to_string() }).await;
+    }
+    core.handle(export_cmd(3, "first", false)).await.unwrap();
+    assert_eq!(fs::read_to_string(&export_path).unwrap(), "id\n1\n");
+
+    // 目标已存在且未设置覆盖：返回类型化的错误，原文件不变
+    let err = core.handle(export_cmd(4, "second", false)).await.unwrap_err();
+    assert!(
+        matches!(err.downcast_ref::<ExportError>(), Some(ExportError::TargetExists(path)) if path == &export_path),
+        "Unexpected error: {:?}",
+        err
+    );
+    assert_eq!(fs::read_to_string(&export_path).unwrap(), "id\n1\n");
+
+    // 导出失败时不留下临时文件，也不影响原文件
+    assert!(core.handle(export_cmd(5, "missing_table", true)).await.is_err());
+    assert_eq!(fs::read_to_string(&export_path).unwrap(), "id\n1\n");
+
+    core.handle(export_cmd(6, "second", true)).await.unwrap();
+    assert_eq!(fs::read_to_string(&export_path).unwrap(), "id\n2\n");
+
+    let entries: Vec<_> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(entries, ["out.csv"], "Temp files left behind");
+}
+
 #[tokio::test]
 async fn test_parquet_import_with_preview() {
     // 创建临时目录
@@ -490,6 +648,8 @@ async fn test_parquet_import_with_preview() {
                 source: "temp_table".to_string(),
                 path: parquet_path.to_string_lossy().to_string(),
                 fmt: FileFmt::Parquet,
+                overwrite: false,
+                options: ExportOptions::default(),
             },
         };
 
@@ -513,6 +673,7 @@ async fn test_parquet_import_with_preview() {
                 fmt: FileFmt::Parquet,
                 table_name: Some("imported_parquet".to_string()),
                 overwrite: false,
+                options: ImportOptions::default(),
             },
         };
 
@@ -525,6 +686,7 @@ async fn test_parquet_import_with_preview() {
                     row_count,
                     column_count,
                     preview,
+                    ..
                 } => {
                     assert_eq!(row_count, 1, "Parquet import should have 1 row");
                     assert_eq!(column_count, 3, "Parquet import should have 3 columns");
@@ -540,6 +702,304 @@ async fn test_parquet_import_with_preview() {
     }
 }
 
+#[tokio::test]
+async fn test_parquet_export_options_and_partitions() {
+    use datawise_core::{ParquetCompression, ParquetOptions};
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("sales.parquet");
+    let dir_path = temp_dir.path().join("sales_by_year");
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ExecuteSql {
+            sql: "CREATE TABLE sales AS SELECT i AS id, 2020 + i % 3 AS year, i * 1.5 AS amount FROM range(10000) t(i)"
+                .to_string(),
+        },
+    )
+    .await;
+
+    // 压缩编码、行组大小和键值元数据
+    let options = ExportOptions {
+        parquet: ParquetOptions {
+            compression: ParquetCompression::Zstd { level: Some(9) },
+            row_group_size: Some(2048),
+            metadata: [("owner".to_string(), "data-lake".to_string())].into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExportFile {
+            source: "sales".to_string(),
+            path: file_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Parquet,
+            overwrite: false,
+            options,
+        },
+    )
+    .await;
+    assert_eq!(row_count, 10000);
+
+    let file = file_path.to_string_lossy();
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::ExecuteSql {
+            sql: format!(
+                "SELECT DISTINCT compression, COUNT(DISTINCT row_group_id) OVER () > 1 FROM parquet_metadata('{}')",
+                file
+            ),
+        },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["col_0"], "ZSTD");
+    assert_eq!(rows[0]["col_1"], true, "10000 rows should span several row groups");
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        4,
+        CmdType::ExecuteSql {
+            sql: format!(
+                "SELECT decode(value) FROM parquet_kv_metadata('{}') WHERE decode(key) = 'owner'",
+                file
+            ),
+        },
+    )
+    .await;
+    assert!(preview.contains("data-lake"), "Unexpected metadata: {}", preview);
+
+    // 分区导出写入 Hive 风格目录，可以按分区重新导入
+    let options = ExportOptions {
+        parquet: ParquetOptions {
+            partition_by: vec!["year".to_string()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (_, _, preview) = run_until_finished(
+        &core,
+        5,
+        CmdType::ExportFile {
+            source: "sales".to_string(),
+            path: dir_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Parquet,
+            overwrite: false,
+            options,
+        },
+    )
+    .await;
+    let summary: datawise_core::ExportSummary = serde_json::from_str(&preview).unwrap();
+    assert!(summary.bytes_written > 0);
+    for year in 2020..2023 {
+        assert!(dir_path.join(format!("year={}", year)).is_dir());
+    }
+
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        6,
+        CmdType::ImportFile {
+            path: dir_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Parquet,
+            table_name: Some("sales_copy".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                hive_partitioning: true,
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    assert_eq!((row_count, column_count), (10, 3));
+
+    // 覆盖分区目录：旧目录整体被替换，不留下备份目录
+    let partitioned = |task_id, source: &str, path: &std::path::Path| Command {
+        task_id,
+        cmd_type: CmdType::ExportFile {
+            source: source.to_string(),
+            path: path.to_string_lossy().to_string(),
+            fmt: FileFmt::Parquet,
+            overwrite: true,
+            options: ExportOptions {
+                parquet: ParquetOptions {
+                    partition_by: vec!["year".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        },
+    };
+    run_until_finished(
+        &core,
+        7,
+        CmdType::ExecuteSql { sql: "CREATE TABLE recent AS SELECT * FROM sales WHERE year = 2022".to_string() },
+    )
+    .await;
+    core.handle(partitioned(8, "recent", &dir_path)).await.unwrap();
+    assert!(dir_path.join("year=2022").is_dir());
+    assert!(!dir_path.join("year=2020").exists());
+    let mut entries: Vec<_> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    entries.sort();
+    assert_eq!(entries, ["sales.parquet", "sales_by_year"], "Backup left behind");
+
+    // 不是分区导出目录时拒绝覆盖，原有内容不变
+    let other_dir = temp_dir.path().join("documents");
+    fs::create_dir(&other_dir).unwrap();
+    fs::write(other_dir.join("notes.txt"), "keep me").unwrap();
+    let err = core.handle(partitioned(9, "recent", &other_dir)).await.unwrap_err();
+    assert!(err.to_string().contains("not a partitioned export"), "Unexpected error: {}", err);
+    assert_eq!(fs::read_to_string(other_dir.join("notes.txt")).unwrap(), "keep me");
+}
+
+#[tokio::test]
+async fn test_csv_dialect_round_trip() {
+    use datawise_core::{CsvDialect, LineEnding, TextEncoding};
+
+    let temp_dir = TempDir::new().unwrap();
+    let source_path = temp_dir.path().join("source.csv");
+    let export_path = temp_dir.path().join("export.csv");
+
+    // 分号分隔、CRLF 换行、GBK 编码，NULL 写作 NA，日期为 日/月/年
+    let content = "id;name;joined;score\r\n1;张三;05/03/2024;1.5\r\n2;李四;17/11/2023;NA\r\n";
+    let (encoded, _, _) = encoding_rs::GBK.encode(content);
+    fs::write(&source_path, &encoded).unwrap();
+
+    let dialect = CsvDialect {
+        delimiter: Some(';'),
+        null_string: Some("NA".to_string()),
+        line_ending: LineEnding::CrLf,
+        encoding: TextEncoding::Gbk,
+        date_format: Some("%d/%m/%Y".to_string()),
+        ..Default::default()
+    };
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: source_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("people".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                csv: Some(dialect.clone()),
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "SELECT name, typeof(joined), score IS NULL FROM people ORDER BY id".to_string(),
+        },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], "张三");
+    assert_eq!(rows[0]["col_1"], "DATE");
+    assert_eq!(rows[1]["col_2"], true);
+
+    // 用相同的方言导出，得到与原始文件相同的字节
+    run_until_finished(
+        &core,
+        3,
+        CmdType::ExportFile {
+            source: "people".to_string(),
+            path: export_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            overwrite: false,
+            options: ExportOptions {
+                csv: dialect,
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    assert_eq!(fs::read(&export_path).unwrap(), encoded.into_owned());
+
+    // 覆盖为 Excel 友好的格式：UTF-8 BOM、制表符分隔、强制引号、无表头
+    run_until_finished(
+        &core,
+        4,
+        CmdType::ExportFile {
+            source: "people".to_string(),
+            path: export_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            overwrite: true,
+            options: ExportOptions {
+                csv: CsvDialect {
+                    delimiter: Some('\t'),
+                    header: false,
+                    force_quote: true,
+                    bom: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    let bytes = fs::read(&export_path).unwrap();
+    assert!(bytes.starts_with(b"\xEF\xBB\xBF"));
+    let text = String::from_utf8(bytes[3..].to_vec()).unwrap();
+    assert!(text.starts_with("\"1\"\t\"张三\"\t\"2024-03-05\"\t\"1.5\"\n"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_csv_import_detects_delimiter_with_only_encoding_set() {
+    use datawise_core::{CsvDialect, TextEncoding};
+
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("gbk.csv");
+    let (encoded, _, _) = encoding_rs::GBK.encode("id;name;city\n1;张三;北京\n2;李四;上海\n");
+    fs::write(&csv_path, &encoded).unwrap();
+
+    // 只设置编码，分隔符由 DuckDB 自动识别
+    let core = DataWise::new().unwrap();
+    let (_, column_count, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: csv_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("gbk_people".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                csv: Some(CsvDialect {
+                    encoding: TextEncoding::Gbk,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    assert_eq!(column_count, 3);
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql { sql: "SELECT name, city FROM gbk_people ORDER BY id".to_string() },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[1]["col_0"], "李四");
+    assert_eq!(rows[1]["col_1"], "上海");
+}
+
 #[tokio::test]
 async fn test_json_import_with_preview() {
     // 创建临时目录
@@ -565,6 +1025,7 @@ async fn test_json_import_with_preview() {
             fmt: FileFmt::Json,
             table_name: Some("json_data".to_string()),
             overwrite: false,
+            options: ImportOptions::default(),
         },
     };
 
@@ -580,6 +1041,7 @@ async fn test_json_import_with_preview() {
                 row_count: rc,
                 column_count: cc,
                 preview,
+                ..
             } => {
                 preview_received = true;
                 row_count = rc;
@@ -647,6 +1109,7 @@ async fn test_json_array_import() {
             fmt: FileFmt::Json,
             table_name: Some("json_array_data".to_string()),
             overwrite: false,
+            options: ImportOptions::default(),
         },
     };
 
@@ -672,3 +1135,1247 @@ async fn test_json_array_import() {
     }
 }
 
+#[tokio::test]
+async fn test_glob_import_with_filename_column() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("sales_2024_01.csv"), "id,amount\n1,10\n2,20\n").unwrap();
+    fs::write(temp_dir.path().join("sales_2024_02.csv"), "id,amount\n3,30\n").unwrap();
+    fs::write(temp_dir.path().join("other.csv"), "id,amount\n99,990\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+
+    // 不指定表名：由 glob 模式推断为 sales_2024
+    let pattern = temp_dir.path().join("sales_2024_*.csv");
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: pattern.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions {
+                filename_column: true,
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+
+    assert_eq!(row_count, 3, "Expected rows from both matching files");
+    assert_eq!(column_count, 3, "Expected id, amount and filename columns");
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "SELECT COUNT(DISTINCT filename), CAST(SUM(amount) AS BIGINT) FROM sales_2024".to_string(),
+        },
+    )
+    .await;
+
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], 2, "Expected two source files");
+    assert_eq!(rows[0]["col_1"], 60, "other.csv should not be imported");
+}
+
+#[tokio::test]
+async fn test_directory_import_with_hive_partitions() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("events");
+    for (year, content) in [("2023", "id,kind\n1,a\n2,b\n"), ("2024", "id,kind\n3,c\n")] {
+        let dir = root.join(format!("year={}", year));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("part-0.csv"), content).unwrap();
+    }
+    // 标记文件应被忽略
+    fs::write(root.join("_SUCCESS"), "").unwrap();
+
+    let core = DataWise::new().unwrap();
+
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: root.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions {
+                hive_partitioning: true,
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+
+    assert_eq!(row_count, 3);
+    assert_eq!(column_count, 3, "Expected partition column 'year'");
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "SELECT COUNT(*) FROM events WHERE year = 2023".to_string(),
+        },
+    )
+    .await;
+
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], 2);
+}
+
+#[tokio::test]
+async fn test_union_by_name_with_explicit_file_list() {
+    let temp_dir = TempDir::new().unwrap();
+    let old_path = temp_dir.path().join("customers_v1.csv");
+    let new_path = temp_dir.path().join("customers_v2.csv");
+    fs::write(&old_path, "id,name\n1,Alice\n2,Bob\n").unwrap();
+    fs::write(&new_path, "name,id,email\nCarol,3,carol@example.com\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: old_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("customers".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                extra_paths: vec![new_path.to_string_lossy().to_string()],
+                union_by_name: true,
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+
+    assert_eq!(row_count, 3);
+    assert_eq!(column_count, 3, "Expected id, name and email columns");
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "SELECT name FROM customers WHERE id = 3 AND email IS NOT NULL".to_string(),
+        },
+    )
+    .await;
+
+    assert!(preview.contains("Carol"), "Columns should be matched by name");
+}
+
+#[tokio::test]
+async fn test_glob_import_without_matches_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let core = DataWise::new().unwrap();
+
+    let pattern = temp_dir.path().join("missing_*.csv");
+    let result = core
+        .handle(Command {
+            task_id: 1,
+            cmd_type: CmdType::ImportFile {
+                path: pattern.to_string_lossy().to_string(),
+                fmt: FileFmt::Csv,
+                table_name: None,
+                overwrite: false,
+                options: ImportOptions::default(),
+            },
+        })
+        .await;
+
+    assert!(result.is_err(), "Import should fail when no files match");
+}
+
+#[tokio::test]
+async fn test_gzip_csv_import_infers_format_and_table_name() {
+    use std::io::Write;
+
+    let temp_dir = TempDir::new().unwrap();
+    let gz_path = temp_dir.path().join("orders.csv.gz");
+    let mut encoder = flate2::write::GzEncoder::new(
+        fs::File::create(&gz_path).unwrap(),
+        flate2::Compression::default(),
+    );
+    encoder.write_all(b"id,total\n1,9.5\n2,12.0\n").unwrap();
+    encoder.finish().unwrap();
+
+    let path = gz_path.to_string_lossy().to_string();
+    let fmt = FileFmt::from_path(&path).expect("Format should be inferred through .gz");
+    assert_eq!(fmt, FileFmt::Csv);
+
+    let core = DataWise::new().unwrap();
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path,
+            fmt,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+    assert_eq!(row_count, 2);
+    assert_eq!(column_count, 2);
+
+    // 表名应去掉 .csv.gz 两层后缀
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "SELECT * FROM orders".to_string(),
+        },
+    )
+    .await;
+    assert_eq!(row_count, 2);
+}
+
+#[tokio::test]
+async fn test_gzip_suffix_and_mixed_compression_import() {
+    use std::io::Write;
+
+    let temp_dir = TempDir::new().unwrap();
+    let gzip_path = temp_dir.path().join("orders_2023.csv.gzip");
+    let mut encoder = flate2::write::GzEncoder::new(
+        fs::File::create(&gzip_path).unwrap(),
+        flate2::Compression::default(),
+    );
+    encoder.write_all(b"id,total\n1,9.5\n2,12.0\n").unwrap();
+    encoder.finish().unwrap();
+    let plain_path = temp_dir.path().join("orders_2024.csv");
+    fs::write(&plain_path, "id,total\n3,7.25\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+
+    // .gzip 后缀由 DuckDB 直接读取
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: gzip_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("orders_2023".to_string()),
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+    assert_eq!(row_count, 2);
+
+    // 压缩与未压缩的文件混合导入同一张表
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        2,
+        CmdType::ImportFile {
+            path: gzip_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("orders".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                extra_paths: vec![plain_path.to_string_lossy().to_string()],
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    assert_eq!(row_count, 3);
+}
+
+#[tokio::test]
+async fn test_zstd_json_and_bz2_csv_import() {
+    use std::io::Write;
+
+    let temp_dir = TempDir::new().unwrap();
+
+    let zst_path = temp_dir.path().join("events.json.zst");
+    let json = b"{\"id\": 1, \"kind\": \"click\"}\n{\"id\": 2, \"kind\": \"view\"}\n";
+    fs::write(&zst_path, zstd::encode_all(&json[..], 3).unwrap()).unwrap();
+
+    let bz2_path = temp_dir.path().join("metrics.csv.bz2");
+    let mut encoder = bzip2::write::BzEncoder::new(
+        fs::File::create(&bz2_path).unwrap(),
+        bzip2::Compression::default(),
+    );
+    encoder.write_all(b"id,value\n1,10\n2,20\n3,30\n").unwrap();
+    encoder.finish().unwrap();
+
+    let core = DataWise::new().unwrap();
+
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: zst_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Json,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+    assert_eq!((row_count, column_count), (2, 2));
+
+    let mut rx = core.subscribe();
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        2,
+        CmdType::ImportFile {
+            path: bz2_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions {
+                filename_column: true,
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    assert_eq!((row_count, column_count), (3, 3));
+
+    // 进度按压缩后的字节数计算
+    let compressed_size = fs::metadata(&bz2_path).unwrap().len();
+    let mut last_progress = None;
+    while let Ok(event) = rx.try_recv() {
+        if let EventKind::Progress { total_bytes, bytes_processed, .. } = event.kind {
+            last_progress = Some((bytes_processed, total_bytes));
+        }
+    }
+    assert_eq!(last_progress, Some((compressed_size, compressed_size)));
+
+    // filename 列应指向原始的 .bz2 文件，而不是临时解压文件
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::ExecuteSql {
+            sql: "SELECT DISTINCT filename FROM metrics".to_string(),
+        },
+    )
+    .await;
+    assert!(preview.contains("metrics.csv.bz2"), "Unexpected filename: {}", preview);
+}
+
+#[tokio::test]
+async fn test_zip_archive_import() {
+    use datawise_core::Importer;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    let temp_dir = TempDir::new().unwrap();
+    let zip_path = temp_dir.path().join("bundle.zip");
+    {
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("2024/jan.csv", options).unwrap();
+        writer.write_all(b"id,amount\n1,10\n2,20\n").unwrap();
+        writer.start_file("2024/feb.csv", options).unwrap();
+        writer.write_all(b"id,amount\n3,30\n").unwrap();
+        writer.start_file("README.txt", options).unwrap();
+        writer.write_all(b"monthly extracts").unwrap();
+        writer.start_file("__MACOSX/2024/._jan.csv", options).unwrap();
+        writer.write_all(b"\x00\x05").unwrap();
+        writer.finish().unwrap();
+    }
+
+    // 列出成员
+    let conn = Arc::new(Mutex::new(duckdb::Connection::open_in_memory().unwrap()));
+    let members = Importer::new(conn).list_archive_members(&zip_path).unwrap();
+    let names: Vec<_> = members.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["2024/jan.csv", "2024/feb.csv", "README.txt", "__MACOSX/2024/._jan.csv"]);
+    assert_eq!(members[0].fmt, Some(FileFmt::Csv));
+    assert_eq!(members[2].fmt, None);
+
+    let core = DataWise::new().unwrap();
+
+    // 默认导入所有 CSV 成员
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: zip_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+    assert_eq!(row_count, 3, "Expected rows from both CSV members");
+
+    // 只导入指定成员
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        2,
+        CmdType::ImportFile {
+            path: zip_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("feb".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                archive_members: vec!["2024/feb.csv".to_string()],
+                filename_column: true,
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    assert_eq!(row_count, 1);
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::ExecuteSql {
+            sql: "SELECT filename FROM feb".to_string(),
+        },
+    )
+    .await;
+    assert!(preview.contains("bundle.zip/2024/feb.csv"), "Unexpected filename: {}", preview);
+}
+
+/// 生成测试用的 Excel 工作簿：
+/// - Orders：合并的表头单元格、日期列和带缓存值的公式列
+/// - Q1 Summary：从 B3 开始的区域，上方有标题行
+fn write_test_workbook(path: &std::path::Path) {
+    use rust_xlsxwriter::{ExcelDateTime, Format, Formula, Workbook};
+
+    let mut workbook = Workbook::new();
+    let date_format = Format::new().set_num_format("yyyy-mm-dd");
+
+    let orders = workbook.add_worksheet();
+    orders.set_name("Orders").unwrap();
+    orders.write_string(0, 0, "id").unwrap();
+    orders.merge_range(0, 1, 0, 2, "amount", &Format::new()).unwrap();
+    orders.write_string(0, 3, "order date").unwrap();
+    orders.write_string(0, 4, "total").unwrap();
+    for (i, (a, b, day)) in [(10.0, 1.5, 15), (20.0, 2.5, 16), (30.0, 3.5, 17)].iter().enumerate() {
+        let row = i as u32 + 1;
+        orders.write_number(row, 0, row as f64).unwrap();
+        orders.write_number(row, 1, *a).unwrap();
+        orders.write_number(row, 2, *b).unwrap();
+        let date = ExcelDateTime::from_ymd(2024, 1, *day).unwrap();
+        orders.write_datetime_with_format(row, 3, &date, &date_format).unwrap();
+        let formula = Formula::new(format!("=B{}+C{}", row + 1, row + 1)).set_result((a + b).to_string());
+        orders.write_formula(row, 4, formula).unwrap();
+    }
+
+    let summary = workbook.add_worksheet();
+    summary.set_name("Q1 Summary").unwrap();
+    summary.write_string(0, 0, "Quarterly report").unwrap();
+    summary.write_string(2, 1, "region").unwrap();
+    summary.write_string(2, 2, "active").unwrap();
+    summary.write_string(3, 1, "north").unwrap();
+    summary.write_boolean(3, 2, true).unwrap();
+    summary.write_string(4, 1, "south").unwrap();
+    summary.write_boolean(4, 2, false).unwrap();
+
+    workbook.save(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_excel_import_with_merged_header_dates_and_formulas() {
+    use datawise_core::Importer;
+    use std::sync::{Arc, Mutex};
+
+    let temp_dir = TempDir::new().unwrap();
+    let xlsx_path = temp_dir.path().join("report.xlsx");
+    write_test_workbook(&xlsx_path);
+
+    let path = xlsx_path.to_string_lossy().to_string();
+    assert_eq!(FileFmt::from_path(&path), Some(FileFmt::Excel));
+
+    let conn = Arc::new(Mutex::new(duckdb::Connection::open_in_memory().unwrap()));
+    let sheets = Importer::new(conn).list_sheets(&xlsx_path).unwrap();
+    assert_eq!(sheets, ["Orders", "Q1 Summary"]);
+
+    let core = DataWise::new().unwrap();
+
+    // 默认导入第一个工作表
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path,
+            fmt: FileFmt::Excel,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+    assert_eq!(row_count, 3);
+    assert_eq!(column_count, 5);
+
+    // 合并的表头展开为 amount、amount_2；日期列为 DATE；公式取缓存值
+    let (_, _, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "SELECT CAST(SUM(amount_2) AS DOUBLE) AS b, CAST(SUM(total) AS DOUBLE) AS total, \
+                  typeof(MIN(\"order date\")) AS date_type, CAST(MIN(\"order date\") AS VARCHAR) AS first_date, \
+                  typeof(MIN(id)) AS id_type FROM report"
+                .to_string(),
+        },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], 7.5);
+    assert_eq!(rows[0]["col_1"], 67.5);
+    assert_eq!(rows[0]["col_2"], "DATE");
+    assert_eq!(rows[0]["col_3"], "2024-01-15");
+    assert_eq!(rows[0]["col_4"], "BIGINT");
+}
+
+#[tokio::test]
+async fn test_excel_import_sheet_range_and_all_sheets() {
+    let temp_dir = TempDir::new().unwrap();
+    let xlsx_path = temp_dir.path().join("report.xlsx");
+    write_test_workbook(&xlsx_path);
+    let path = xlsx_path.to_string_lossy().to_string();
+
+    let core = DataWise::new().unwrap();
+
+    // 指定工作表和区域，跳过上方的标题行
+    let (row_count, column_count, preview) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: path.clone(),
+            fmt: FileFmt::Excel,
+            table_name: Some("regions".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                sheet: Some("Q1 Summary".to_string()),
+                cell_range: Some("B3:C5".to_string()),
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    assert_eq!((row_count, column_count), (2, 2));
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], "north");
+    assert_eq!(rows[0]["col_1"], true);
+
+    // 每个工作表导入为单独的表
+    run_until_finished(
+        &core,
+        2,
+        CmdType::ImportFile {
+            path: path.clone(),
+            fmt: FileFmt::Excel,
+            table_name: Some("wb".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                all_sheets: true,
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::ExecuteSql {
+            sql: "SELECT (SELECT COUNT(*) FROM wb_orders) AS orders, \
+                  (SELECT COUNT(*) FROM wb_q1_summary) AS summary"
+                .to_string(),
+        },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], 3);
+    // 整个工作表：标题行作为表头，空行被跳过
+    assert_eq!(rows[0]["col_1"], 3);
+
+    // 不存在的工作表
+    let result = core
+        .handle(Command {
+            task_id: 4,
+            cmd_type: CmdType::ImportFile {
+                path,
+                fmt: FileFmt::Excel,
+                table_name: Some("missing".to_string()),
+                overwrite: false,
+                options: ImportOptions {
+                    sheet: Some("Nope".to_string()),
+                    ..Default::default()
+                },
+            },
+        })
+        .await;
+    assert!(result.is_err(), "Import should fail for an unknown sheet");
+}
+
+#[tokio::test]
+async fn test_excel_workbook_export_with_multiple_sheets() {
+    use calamine::{open_workbook, Data, Reader, Xlsx};
+    use datawise_core::SheetExport;
+
+    let temp_dir = TempDir::new().unwrap();
+    let xlsx_path = temp_dir.path().join("deliverable.xlsx");
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ExecuteSql {
+            sql: "CREATE TABLE orders AS SELECT * FROM (VALUES \
+                  (1, 'Alice', 9.5, true, DATE '2024-01-15', TIMESTAMP '2024-01-15 08:30:00'), \
+                  (2, 'Bob', NULL, false, DATE '2024-02-01', TIMESTAMP '2024-02-01 17:00:00')) \
+                  AS t(id, customer, total, paid, order_date, created_at)"
+                .to_string(),
+        },
+    )
+    .await;
+
+    run_until_finished(
+        &core,
+        2,
+        CmdType::ExportWorkbook {
+            path: xlsx_path.to_string_lossy().to_string(),
+            sheets: vec![
+                SheetExport {
+                    sheet_name: "Orders".to_string(),
+                    source: "orders".to_string(),
+                    is_query: false,
+                },
+                SheetExport {
+                    sheet_name: "Paid/Unpaid".to_string(),
+                    source: "SELECT paid, COUNT(*) AS n FROM orders GROUP BY paid ORDER BY paid".to_string(),
+                    is_query: true,
+                },
+            ],
+            split_large_sheets: false,
+            overwrite: false,
+        },
+    )
+    .await;
+
+    let mut workbook: Xlsx<_> = open_workbook(&xlsx_path).unwrap();
+    assert_eq!(workbook.sheet_names(), ["Orders", "Paid_Unpaid"]);
+
+    let orders = workbook.worksheet_range("Orders").unwrap();
+    assert_eq!(orders.get_size(), (3, 6));
+    assert_eq!(orders.get((0, 1)), Some(&Data::String("customer".to_string())));
+    assert_eq!(orders.get((1, 0)), Some(&Data::Float(1.0)));
+    assert_eq!(orders.get((1, 2)), Some(&Data::Float(9.5)));
+    assert_eq!(orders.get((2, 2)), Some(&Data::Empty), "NULL should be an empty cell");
+    assert_eq!(orders.get((1, 3)), Some(&Data::Bool(true)));
+    match orders.get((1, 4)) {
+        Some(Data::DateTime(dt)) => assert_eq!(dt.as_datetime().unwrap().to_string(), "2024-01-15 00:00:00"),
+        other => panic!("Expected a date cell, got {:?}", other),
+    }
+    match orders.get((1, 5)) {
+        Some(Data::DateTime(dt)) => assert_eq!(dt.as_datetime().unwrap().to_string(), "2024-01-15 08:30:00"),
+        other => panic!("Expected a datetime cell, got {:?}", other),
+    }
+
+    let summary = workbook.worksheet_range("Paid_Unpaid").unwrap();
+    assert_eq!(summary.get_size(), (3, 2));
+    assert_eq!(summary.get((1, 0)), Some(&Data::Bool(false)));
+
+    // ExportFile 也支持 Excel 格式，工作表以表名命名
+    let single_path = temp_dir.path().join("orders.xlsx");
+    run_until_finished(
+        &core,
+        3,
+        CmdType::ExportFile {
+            source: "orders".to_string(),
+            path: single_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Excel,
+            overwrite: false,
+            options: ExportOptions::default(),
+        },
+    )
+    .await;
+    let workbook: Xlsx<_> = open_workbook(&single_path).unwrap();
+    assert_eq!(workbook.sheet_names(), ["orders"]);
+}
+
+#[tokio::test]
+async fn test_arrow_ipc_roundtrip_keeps_nested_and_dictionary_types() {
+    use arrow::array::{
+        ArrayRef, DictionaryArray, Int32Array, Int64Array, ListArray, StringArray, StructArray,
+    };
+    use arrow::datatypes::{DataType, Field, Int32Type, Int64Type, Schema};
+    use arrow::ipc::reader::{FileReader, StreamReader};
+    use arrow::ipc::writer::FileWriter;
+    use arrow::record_batch::RecordBatch;
+    use std::sync::Arc;
+
+    let temp_dir = TempDir::new().unwrap();
+    let feather_path = temp_dir.path().join("events.feather");
+
+    // 模拟 Polars/pyarrow 写出的 Feather v2 文件
+    let kind: DictionaryArray<Int32Type> = vec!["click", "view", "click"].into_iter().collect();
+    let tags = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+        Some(vec![Some(1), Some(2)]),
+        Some(vec![]),
+        None,
+    ]);
+    let meta = StructArray::from(vec![
+        (
+            Arc::new(Field::new("x", DataType::Int32, true)),
+            Arc::new(Int32Array::from(vec![10, 20, 30])) as ArrayRef,
+        ),
+        (
+            Arc::new(Field::new("label", DataType::Utf8, true)),
+            Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+        ),
+    ]);
+    let batch = RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+        ("kind", Arc::new(kind) as ArrayRef),
+        ("tags", Arc::new(tags) as ArrayRef),
+        ("meta", Arc::new(meta) as ArrayRef),
+    ])
+    .unwrap();
+    {
+        let mut writer = FileWriter::try_new(fs::File::create(&feather_path).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+    }
+
+    let path = feather_path.to_string_lossy().to_string();
+    assert_eq!(FileFmt::from_path(&path), Some(FileFmt::Arrow));
+
+    let core = DataWise::new().unwrap();
+    let (row_count, column_count, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path,
+            fmt: FileFmt::Arrow,
+            table_name: None,
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+    assert_eq!((row_count, column_count), (3, 4));
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "SELECT typeof(kind), typeof(tags), typeof(meta), CAST(SUM(meta.x) AS BIGINT) FROM events GROUP BY ALL"
+                .to_string(),
+        },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], "ENUM('click', 'view')");
+    assert_eq!(rows[0]["col_1"], "BIGINT[]");
+    assert_eq!(rows[0]["col_2"], "STRUCT(x INTEGER, \"label\" VARCHAR)");
+    assert_eq!(rows[0]["col_3"], 60);
+
+    // 导出为 IPC 文件和 IPC 流，schema 保持不变
+    let file_path = temp_dir.path().join("out.arrow");
+    let stream_path = temp_dir.path().join("out.arrows");
+    for (task_id, out) in [(3, &file_path), (4, &stream_path)] {
+        run_until_finished(
+            &core,
+            task_id,
+            CmdType::ExportFile {
+                source: "events".to_string(),
+                path: out.to_string_lossy().to_string(),
+                fmt: FileFmt::Arrow,
+                overwrite: false,
+                options: ExportOptions::default(),
+            },
+        )
+        .await;
+    }
+
+    let check_schema = |schema: &Schema| {
+        assert!(matches!(
+            schema.field_with_name("kind").unwrap().data_type(),
+            DataType::Dictionary(_, value) if **value == DataType::Utf8
+        ));
+        assert!(matches!(schema.field_with_name("tags").unwrap().data_type(), DataType::List(_)));
+        assert!(matches!(schema.field_with_name("meta").unwrap().data_type(), DataType::Struct(_)));
+    };
+
+    let reader = FileReader::try_new(fs::File::open(&file_path).unwrap(), None).unwrap();
+    check_schema(&reader.schema());
+    let rows: usize = reader.map(|b| b.unwrap().num_rows()).sum();
+    assert_eq!(rows, 3);
+
+    let reader = StreamReader::try_new(fs::File::open(&stream_path).unwrap(), None).unwrap();
+    check_schema(&reader.schema());
+    let rows: usize = reader.map(|b| b.unwrap().num_rows()).sum();
+    assert_eq!(rows, 3);
+
+    // 导出的 IPC 流也可以再导入
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        5,
+        CmdType::ImportFile {
+            path: stream_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Arrow,
+            table_name: Some("events_copy".to_string()),
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+    assert_eq!(row_count, 3);
+}
+
+/// 生成包含弱类型列的 SQLite 测试数据库
+fn write_test_sqlite(path: &std::path::Path) {
+    let conn = rusqlite::Connection::open(path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE readings (
+             id INTEGER PRIMARY KEY,
+             sensor TEXT,
+             value REAL,
+             reading INTEGER,
+             taken_at DATETIME,
+             day DATE,
+             active BOOLEAN,
+             payload BLOB,
+             extra
+         );
+         INSERT INTO readings VALUES (1, 'a', 1.5, 10, '2024-01-15 08:30:00', '2024-01-15', 1, x'0102', 7);
+         INSERT INTO readings VALUES (2, 'b', 2, 'n/a', '2024-01-16T09:00', '2024-01-16', 0, x'03', 8);
+         INSERT INTO readings VALUES (3, 'c', NULL, 30, NULL, 'unknown', NULL, NULL, 9);
+         CREATE TABLE \"order items\" (sku TEXT, qty NUMERIC);
+         INSERT INTO \"order items\" VALUES ('x-1', 3);
+         INSERT INTO \"order items\" VALUES ('x-2', 2.5);",
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_arrow_ipc_import_with_compressed_buffers() {
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::ipc::writer::{FileWriter, IpcWriteOptions};
+    use arrow::ipc::CompressionType;
+    use arrow::record_batch::RecordBatch;
+    use std::sync::Arc;
+
+    let temp_dir = TempDir::new().unwrap();
+    let batch = RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int64Array::from_iter_values(0..1000)) as ArrayRef),
+        ("name", Arc::new(StringArray::from_iter_values((0..1000).map(|i| format!("user_{}", i)))) as ArrayRef),
+    ])
+    .unwrap();
+
+    // pyarrow 写 Feather v2 默认使用 LZ4 压缩缓冲区
+    let core = DataWise::new().unwrap();
+    for (task_id, compression, table) in [
+        (1, CompressionType::LZ4_FRAME, "users_lz4"),
+        (2, CompressionType::ZSTD, "users_zstd"),
+    ] {
+        let path = temp_dir.path().join(format!("{}.feather", table));
+        let options = IpcWriteOptions::default().try_with_compression(Some(compression)).unwrap();
+        let mut writer =
+            FileWriter::try_new_with_options(fs::File::create(&path).unwrap(), &batch.schema(), options).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+
+        let (_, column_count, _) = run_until_finished(
+            &core,
+            task_id,
+            CmdType::ImportFile {
+                path: path.to_string_lossy().to_string(),
+                fmt: FileFmt::Arrow,
+                table_name: Some(table.to_string()),
+                overwrite: false,
+                options: ImportOptions::default(),
+            },
+        )
+        .await;
+        assert_eq!(column_count, 2);
+    }
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::ExecuteSql {
+            sql: "SELECT COUNT(*) FROM users_lz4 JOIN users_zstd USING (id, name)".to_string(),
+        },
+    )
+    .await;
+    assert!(preview.contains("1000"), "{}", preview);
+}
+
+#[tokio::test]
+async fn test_sqlite_list_and_import_with_loose_typing() {
+    use datawise_core::SqliteAction;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("sensors.db");
+    write_test_sqlite(&db_path);
+    let path = db_path.to_string_lossy().to_string();
+
+    let core = DataWise::new().unwrap();
+
+    // 列出表：类型由声明类型和实际存储的值共同决定
+    let (row_count, table_count, preview) = run_until_finished(
+        &core,
+        1,
+        CmdType::OpenSqlite {
+            path: path.clone(),
+            action: SqliteAction::ListTables,
+        },
+    )
+    .await;
+    assert_eq!((row_count, table_count), (5, 2));
+    let tables: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(tables[0]["name"], "order items");
+    assert_eq!(tables[1]["name"], "readings");
+    let types: Vec<&str> = tables[1]["columns"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["duckdb_type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        ["BIGINT", "VARCHAR", "DOUBLE", "VARCHAR", "TIMESTAMP", "VARCHAR", "BOOLEAN", "BLOB", "BIGINT"]
+    );
+
+    // 只导入选中的表
+    let (row_count, table_count, _) = run_until_finished(
+        &core,
+        2,
+        CmdType::OpenSqlite {
+            path: path.clone(),
+            action: SqliteAction::Import {
+                tables: vec!["readings".to_string()],
+                overwrite: false,
+            },
+        },
+    )
+    .await;
+    assert_eq!((row_count, table_count), (3, 1));
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::ExecuteSql {
+            sql: "SELECT reading, CAST(taken_at AS VARCHAR), day, active, octet_length(payload), value \
+                  FROM readings ORDER BY id"
+                .to_string(),
+        },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], "10");
+    assert_eq!(rows[1]["col_0"], "n/a");
+    assert_eq!(rows[0]["col_1"], "2024-01-15 08:30:00");
+    assert_eq!(rows[1]["col_1"], "2024-01-16 09:00:00");
+    assert_eq!(rows[2]["col_2"], "unknown");
+    assert_eq!(rows[0]["col_3"], true);
+    assert_eq!(rows[1]["col_3"], false);
+    assert_eq!(rows[0]["col_4"], 2);
+    assert_eq!(rows[1]["col_5"], 2.0);
+
+    // 再导入所有表（覆盖），带空格的表名保持不变
+    let (row_count, table_count, _) = run_until_finished(
+        &core,
+        4,
+        CmdType::OpenSqlite {
+            path,
+            action: SqliteAction::Import {
+                tables: vec![],
+                overwrite: true,
+            },
+        },
+    )
+    .await;
+    assert_eq!((row_count, table_count), (5, 2));
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        5,
+        CmdType::ExecuteSql {
+            sql: "SELECT typeof(qty), SUM(qty) FROM \"order items\" GROUP BY ALL".to_string(),
+        },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], "DOUBLE");
+    assert_eq!(rows[0]["col_1"], 5.5);
+}
+
+#[tokio::test]
+async fn test_sqlite_import_unknown_table_fails() {
+    use datawise_core::SqliteAction;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("sensors.db");
+    write_test_sqlite(&db_path);
+
+    let core = DataWise::new().unwrap();
+    let result = core
+        .handle(Command {
+            task_id: 1,
+            cmd_type: CmdType::OpenSqlite {
+                path: db_path.to_string_lossy().to_string(),
+                action: SqliteAction::Import {
+                    tables: vec!["missing".to_string()],
+                    overwrite: false,
+                },
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("missing"));
+}
+
+#[test]
+fn test_sqlite_import_cancelled() {
+    use datawise_core::Importer;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("sensors.db");
+    write_test_sqlite(&db_path);
+
+    // 已取消的导入不留下任何表
+    let conn = Arc::new(Mutex::new(duckdb::Connection::open_in_memory().unwrap()));
+    let importer = Importer::new(Arc::clone(&conn));
+    let cancel = AtomicBool::new(true);
+    let err = importer.import_sqlite(&db_path, &[], false, Some(&cancel), None).unwrap_err();
+    assert!(err.to_string().contains("cancelled"), "{}", err);
+
+    let count: i64 = conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM information_schema.tables", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+#[ignore = "requires the DuckDB sqlite extension (downloaded on first use)"]
+async fn test_sqlite_attach_for_live_queries() {
+    use datawise_core::SqliteAction;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("sensors.db");
+    write_test_sqlite(&db_path);
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::OpenSqlite {
+            path: db_path.to_string_lossy().to_string(),
+            action: SqliteAction::Attach { alias: None },
+        },
+    )
+    .await;
+
+    // 附加后对源数据库的修改立即可见
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute("INSERT INTO \"order items\" VALUES ('x-3', 1)", []).unwrap();
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::ExecuteSql {
+            sql: "SELECT COUNT(*) FROM sensors.\"order items\"".to_string(),
+        },
+    )
+    .await;
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_0"], 3);
+}
+
+#[tokio::test]
+async fn test_import_files_with_special_characters_in_names() {
+    let temp_dir = TempDir::new().unwrap();
+    let files = [
+        ("O'Brien.csv", "O_Brien"),
+        ("2024-sales.csv", "t_2024_sales"),
+        ("销售 数据.csv", "销售_数据"),
+    ];
+
+    let core = DataWise::new().unwrap();
+    for (task_id, (file_name, table_name)) in files.iter().enumerate() {
+        let path = temp_dir.path().join(file_name);
+        fs::write(&path, "id,amount\n1,10\n2,20\n").unwrap();
+
+        // 不指定表名：由文件名推断并清理，filename 列中保留原始路径
+        let (row_count, _, _) = run_until_finished(
+            &core,
+            task_id as u64 * 2 + 1,
+            CmdType::ImportFile {
+                path: path.to_string_lossy().to_string(),
+                fmt: FileFmt::Csv,
+                table_name: None,
+                overwrite: false,
+                options: ImportOptions {
+                    filename_column: true,
+                    ..Default::default()
+                },
+            },
+        )
+        .await;
+        assert_eq!(row_count, 2, "Unexpected row count for {}", file_name);
+
+        let (_, _, preview) = run_until_finished(
+            &core,
+            task_id as u64 * 2 + 2,
+            CmdType::ExecuteSql {
+                sql: format!("SELECT DISTINCT filename FROM {}", table_name),
+            },
+        )
+        .await;
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&preview).unwrap();
+        assert_eq!(rows[0]["col_0"], path.to_string_lossy().as_ref());
+    }
+}
+
+#[tokio::test]
+async fn test_quoted_table_name_and_export_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let import_path = temp_dir.path().join("input.csv");
+    fs::write(&import_path, "id\n1\n2\n3\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+
+    // 显式表名中包含空格和引号，按标识符原样建表
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ImportFile {
+            path: import_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("it's \"my\" table".to_string()),
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    )
+    .await;
+
+    let export_path = temp_dir.path().join("it's export; DROP TABLE x.csv");
+    run_until_finished(
+        &core,
+        2,
+        CmdType::ExportFile {
+            source: "it's \"my\" table".to_string(),
+            path: export_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            overwrite: false,
+            options: ExportOptions::default(),
+        },
+    )
+    .await;
+
+    assert_eq!(fs::read_to_string(&export_path).unwrap(), "id\n1\n2\n3\n");
+}
+
+#[tokio::test]
+async fn test_csv_import_with_rejected_rows() {
+    use datawise_core::{CsvDialect, RejectsOutput};
+
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("feed.csv");
+    fs::write(&csv_path, "id,name\n1,Alice\n2,Bob,extra\n3,Carol\n4,Dan\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+    let import = |task_id: u64, table_name: &str, rejects: Option<RejectsOutput>| Command {
+        task_id,
+        cmd_type: CmdType::ImportFile {
+            path: csv_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some(table_name.to_string()),
+            overwrite: false,
+            // 指定分隔符：列数不一致的行会让 DuckDB 推断出其他分隔符
+            options: ImportOptions {
+                csv: Some(CsvDialect { delimiter: Some(','), ..Default::default() }),
+                rejects,
+                ..Default::default()
+            },
+        },
+    };
+
+    // 默认遇到错误行导入失败
+    assert!(core.handle(import(1, "strict", None)).await.is_err());
+
+    // 容错导入：正常行导入，错误行写入 <表名>_rejects
+    let mut rx = core.subscribe();
+    core.handle(import(2, "feed", Some(RejectsOutput::Table))).await.unwrap();
+    let mut rejected = None;
+    while let Ok(event) = rx.recv().await {
+        match event.kind {
+            EventKind::Finished { rejected_rows, .. } => {
+                rejected = rejected_rows;
+                break;
+            }
+            EventKind::Error(e) => panic!("Unexpected error: {}", e),
+            _ => {}
+        }
+    }
+    assert_eq!(rejected, Some(1));
+
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::ExecuteSql { sql: "SELECT COUNT(*) AS n FROM feed".to_string() },
+    )
+    .await;
+    assert!(preview.contains("3"), "{}", preview);
+
+    let (row_count, _, preview) = run_until_finished(
+        &core,
+        4,
+        CmdType::ExecuteSql { sql: "SELECT file, line, reason, content FROM feed_rejects".to_string() },
+    )
+    .await;
+    assert_eq!(row_count, 1);
+    let rows: serde_json::Value = serde_json::from_str(&preview).unwrap();
+    assert_eq!(rows[0]["col_1"], 3);
+    assert_eq!(rows[0]["col_3"], "2,Bob,extra");
+    assert!(rows[0]["col_0"].as_str().unwrap().ends_with("feed.csv"));
+    assert!(!rows[0]["col_2"].as_str().unwrap().is_empty());
+
+    // 写入 CSV 文件
+    let rejects_path = temp_dir.path().join("rejects.csv");
+    let (_, _, _) = run_until_finished(
+        &core,
+        5,
+        CmdType::ImportFile {
+            path: csv_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("feed_file".to_string()),
+            overwrite: false,
+            options: ImportOptions {
+                csv: Some(CsvDialect { delimiter: Some(','), ..Default::default() }),
+                rejects: Some(RejectsOutput::File { path: rejects_path.to_string_lossy().to_string() }),
+                ..Default::default()
+            },
+        },
+    )
+    .await;
+    let rejects = fs::read_to_string(&rejects_path).unwrap();
+    let lines: Vec<&str> = rejects.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", rejects);
+    assert_eq!(lines[0], "file,line,column_name,error_type,reason,content");
+    assert!(lines[1].contains("feed.csv,3,"), "{}", rejects);
+    assert!(lines[1].ends_with("\"2,Bob,extra\""), "{}", rejects);
+
+    // 仅支持 CSV
+    let result = core
+        .handle(Command {
+            task_id: 6,
+            cmd_type: CmdType::ImportFile {
+                path: csv_path.to_string_lossy().to_string(),
+                fmt: FileFmt::Json,
+                table_name: Some("feed_json".to_string()),
+                overwrite: false,
+                options: ImportOptions { rejects: Some(RejectsOutput::Table), ..Default::default() },
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("only supported for CSV"));
+}
@@ -5,7 +5,7 @@
 /// - 100 万行渲染时间
 /// - SQL 查询响应时间
 
-use datawise_core::{DataWise, Command, CmdType, FileFmt};
+use datawise_core::{DataWise, Command, CmdType, FileFmt, ImportOptions};
 use std::fs::File;
 use std::io::Write;
 use std::time::Instant;
@@ -37,6 +37,7 @@ async fn benchmark_csv_import_1gb() {
             fmt: FileFmt::Csv,
             table_name: Some("benchmark_data".to_string()),
             overwrite: true,
+            options: ImportOptions::default(),
         },
     };
     
@@ -0,0 +1,226 @@
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType, EventKind};
+
+#[tokio::test]
+async fn test_profile_table() {
+    use datawise_core::TableProfile;
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ExecuteSql {
+            sql: "CREATE TABLE people AS SELECT * FROM (VALUES \
+                  (1, 'Alice', 30.0, DATE '2024-01-01'), \
+                  (2, 'Bob', 40.0, DATE '2024-02-01'), \
+                  (3, 'Bob', NULL, DATE '2024-03-01'), \
+                  (4, NULL, 50.0, NULL)) AS t(id, name, score, joined)"
+                .to_string(),
+        },
+    )
+    .await;
+
+    let mut rx = core.subscribe();
+    core.handle(Command {
+        task_id: 2,
+        cmd_type: CmdType::ProfileTable {
+            table: "people".to_string(),
+            columns: Vec::new(),
+            sample: None,
+        },
+    })
+    .await
+    .unwrap();
+
+    // 统计量和最常见的值各报告一次进度
+    let mut progress = Vec::new();
+    let profile: TableProfile = loop {
+        match rx.recv().await.unwrap().kind {
+            EventKind::Progress { pct, .. } => progress.push(pct),
+            EventKind::Finished { row_count, column_count, preview, .. } => {
+                assert_eq!((row_count, column_count), (4, 4));
+                break serde_json::from_str(&preview).unwrap();
+            }
+            EventKind::Error(e) => panic!("Unexpected error: {}", e),
+            _ => {}
+        }
+    };
+    assert_eq!(progress, [50, 100]);
+    assert_eq!(profile.profiled_rows, 4);
+
+    let name = &profile.columns[1];
+    assert_eq!(name.null_count, 1);
+    assert_eq!(name.distinct_count, 2);
+    assert_eq!(name.min.as_deref(), Some("Alice"));
+    assert_eq!(name.max.as_deref(), Some("Bob"));
+    assert_eq!(name.top_values[0].value, "Bob");
+    assert_eq!(name.top_values[0].count, 2);
+    let length = name.length.as_ref().unwrap();
+    assert_eq!((length.min, length.max), (3, 5));
+    assert!(name.mean.is_none());
+
+    let score = &profile.columns[2];
+    assert_eq!(score.null_count, 1);
+    assert_eq!(score.mean, Some(40.0));
+    assert_eq!(score.stddev, Some(10.0));
+    assert!(score.length.is_none());
+
+    let joined = &profile.columns[3];
+    assert_eq!(joined.min.as_deref(), Some("2024-01-01"));
+    assert_eq!(joined.max.as_deref(), Some("2024-03-01"));
+    assert!(joined.mean.is_none());
+
+    // 指定列并抽样
+    let (_, column_count, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::ProfileTable {
+            table: "people".to_string(),
+            columns: vec!["SCORE".to_string()],
+            sample: Some(2),
+        },
+    )
+    .await;
+    let profile: TableProfile = serde_json::from_str(&preview).unwrap();
+    assert_eq!(column_count, 1);
+    assert_eq!(profile.row_count, 4);
+    assert_eq!(profile.profiled_rows, 2);
+    assert_eq!(profile.columns[0].name, "score");
+
+    // 视图抽样时总行数另外精确统计
+    run_until_finished(
+        &core,
+        4,
+        CmdType::ExecuteSql { sql: "CREATE VIEW people_view AS SELECT * FROM people".to_string() },
+    )
+    .await;
+    let (row_count, _, preview) = run_until_finished(
+        &core,
+        5,
+        CmdType::ProfileTable {
+            table: "people_view".to_string(),
+            columns: Vec::new(),
+            sample: Some(2),
+        },
+    )
+    .await;
+    let profile: TableProfile = serde_json::from_str(&preview).unwrap();
+    assert_eq!((row_count, profile.row_count, profile.profiled_rows), (4, 4, 2));
+
+    let result = core
+        .handle(Command {
+            task_id: 6,
+            cmd_type: CmdType::ProfileTable {
+                table: "people".to_string(),
+                columns: vec!["missing".to_string()],
+                sample: None,
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("Column not found"));
+}
+
+#[tokio::test]
+async fn test_histograms() {
+    use datawise_core::{BinEdges, Binning, Histogram, TimeBucket};
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ExecuteSql {
+            sql: "CREATE TABLE sales AS SELECT \
+                  i AS amount, \
+                  CASE WHEN i % 2 = 0 THEN 'even' ELSE 'odd' END AS parity, \
+                  DATE '2024-01-30' + CAST(i AS INTEGER) AS sold_on \
+                  FROM range(10) t(i) \
+                  UNION ALL SELECT NULL, 'odd', NULL"
+                .to_string(),
+        },
+    )
+    .await;
+
+    let histogram = |task_id, column: &str, binning, group_by: Option<&str>| {
+        let cmd_type = CmdType::Histogram {
+            table: "sales".to_string(),
+            column: column.to_string(),
+            binning,
+            group_by: group_by.map(str::to_string),
+        };
+        let core = &core;
+        async move {
+            let (_, _, preview) = run_until_finished(core, task_id, cmd_type).await;
+            serde_json::from_str::<Histogram>(&preview).unwrap()
+        }
+    };
+
+    // 等宽分箱：0-9 分为 3 箱，最大值落在最后一箱
+    let result = histogram(2, "amount", Binning::EqualWidth { bins: 3 }, None).await;
+    assert_eq!(result.edges, BinEdges::Numeric(vec![0.0, 3.0, 6.0, 9.0]));
+    assert_eq!(result.series.len(), 1);
+    assert_eq!(result.series[0].group, None);
+    assert_eq!(result.series[0].counts, [3, 3, 4]);
+    assert_eq!(result.excluded_count, 1);
+
+    // 分位数分箱，按奇偶分组
+    let result = histogram(3, "amount", Binning::Quantile { bins: 2 }, Some("parity")).await;
+    assert_eq!(result.edges, BinEdges::Numeric(vec![0.0, 4.5, 9.0]));
+    assert_eq!(result.group_by.as_deref(), Some("parity"));
+    let counts: Vec<_> = result.series.iter().map(|s| (s.group.as_deref(), s.counts.clone())).collect();
+    assert_eq!(counts, [(Some("even"), vec![3, 2]), (Some("odd"), vec![2, 3])]);
+
+    // 按月分桶：1 月 30 日起的 10 天跨越两个月
+    let result = histogram(4, "sold_on", Binning::Time { unit: TimeBucket::Month }, None).await;
+    assert_eq!(
+        result.edges,
+        BinEdges::Temporal(vec![
+            "2024-01-01 00:00:00".to_string(),
+            "2024-02-01 00:00:00".to_string(),
+            "2024-03-01 00:00:00".to_string(),
+        ])
+    );
+    assert_eq!(result.series[0].counts, [2, 8]);
+
+    // 只返回行数最多的 20 个分组
+    run_until_finished(
+        &core,
+        6,
+        CmdType::ExecuteSql {
+            sql: "CREATE TABLE readings AS SELECT i % 25 AS sensor, i AS value FROM range(100 + 24) t(i)".to_string(),
+        },
+    )
+    .await;
+    let (_, _, preview) = run_until_finished(
+        &core,
+        7,
+        CmdType::Histogram {
+            table: "readings".to_string(),
+            column: "value".to_string(),
+            binning: Binning::EqualWidth { bins: 4 },
+            group_by: Some("sensor".to_string()),
+        },
+    )
+    .await;
+    let result: Histogram = serde_json::from_str(&preview).unwrap();
+    assert_eq!(result.series.len(), 20);
+    assert_eq!(result.omitted_groups, 5);
+    // 传感器 0-23 各 5 行，传感器 24 只有 4 行，不在前 20 个分组中
+    assert!(result.series.iter().all(|s| s.counts.iter().sum::<u64>() == 5));
+    assert_eq!(result.series[0].group.as_deref(), Some("0"));
+
+    // 列类型与分箱方式不匹配
+    let result = core
+        .handle(Command {
+            task_id: 5,
+            cmd_type: CmdType::Histogram {
+                table: "sales".to_string(),
+                column: "parity".to_string(),
+                binning: Binning::default(),
+                group_by: None,
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("numeric, date or timestamp"));
+}
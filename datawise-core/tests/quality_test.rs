@@ -0,0 +1,136 @@
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType, FileFmt, EventKind, ImportOptions};
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_validate_rules() {
+    use datawise_core::{Rule, ValidationReport};
+    use serde_json::json;
+
+    let core = DataWise::new().unwrap();
+    for sql in [
+        "CREATE TABLE customers AS SELECT * FROM (VALUES (1), (2)) t(id)",
+        "CREATE TABLE orders AS SELECT * FROM (VALUES \
+         (1, 1, 10.0, 'paid', 'A-001'), \
+         (2, 2, -5.0, 'paid', 'A-002'), \
+         (2, 3, 20.0, 'lost', 'bad'), \
+         (4, NULL, 30.0, NULL, 'A-004')) t(order_id, customer_id, amount, status, code)",
+    ] {
+        run_until_finished(&core, 1, CmdType::ExecuteSql { sql: sql.to_string() }).await;
+    }
+
+    let rules = vec![
+        Rule::NotNull { column: "customer_id".to_string() },
+        Rule::Unique { columns: vec!["order_id".to_string()] },
+        Rule::Range { column: "amount".to_string(), min: Some(json!(0)), max: None },
+        Rule::Regex { column: "code".to_string(), pattern: "A-[0-9]{3}".to_string() },
+        Rule::AllowedValues { column: "status".to_string(), values: vec![json!("paid"), json!("refunded")] },
+        Rule::References {
+            column: "customer_id".to_string(),
+            table: "customers".to_string(),
+            referenced_column: Some("id".to_string()),
+        },
+        Rule::RowCount { min: Some(10), max: None },
+    ];
+    let (row_count, failed_rules, preview) = run_until_finished(
+        &core,
+        2,
+        CmdType::Validate { table: "orders".to_string(), rules },
+    )
+    .await;
+    let report: ValidationReport = serde_json::from_str(&preview).unwrap();
+
+    assert_eq!(row_count, 4);
+    assert_eq!(failed_rules, 7);
+    assert!(!report.passed);
+    let failing: Vec<_> = report.results.iter().map(|r| r.failing_rows).collect();
+    assert_eq!(failing, [1, 2, 1, 1, 1, 1, 0]);
+
+    // 示例行按列顺序给出文本形式的值
+    assert_eq!(report.columns, ["order_id", "customer_id", "amount", "status", "code"]);
+    let sample: Vec<_> = report.results[3].samples[0].iter().map(|v| v.as_deref()).collect();
+    assert_eq!(sample, [Some("2"), Some("3"), Some("20.0"), Some("lost"), Some("bad")]);
+    assert!(report.results[6].message.as_deref().unwrap().contains("below the minimum 10"));
+
+    // 未附加规则时需要在命令中给出规则
+    let result = core
+        .handle(Command {
+            task_id: 3,
+            cmd_type: CmdType::Validate { table: "customers".to_string(), rules: Vec::new() },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("No rules attached"));
+}
+
+#[tokio::test]
+async fn test_rules_file_and_validation_on_import() {
+    use datawise_core::{Rule, RuleSet, RulesAction};
+
+    let temp_dir = TempDir::new().unwrap();
+    let rules_path = temp_dir.path().join("rules.yaml");
+    let csv_path = temp_dir.path().join("feed.csv");
+    fs::write(&csv_path, "id,name\n1,Alice\n1,Bob\n2,\n").unwrap();
+
+    let core = DataWise::new().unwrap();
+    let rules_command = |task_id: u64, action: RulesAction| run_until_finished(&core, task_id, CmdType::Rules { action });
+
+    // 保存后，附加的规则自动写回规则文件
+    rules_command(1, RulesAction::Save { path: rules_path.to_string_lossy().to_string() }).await;
+    let (tables, rule_count, _) = rules_command(
+        2,
+        RulesAction::Set {
+            table: "feed".to_string(),
+            rules: vec![
+                Rule::Unique { columns: vec!["id".to_string()] },
+                Rule::NotNull { column: "name".to_string() },
+            ],
+        },
+    )
+    .await;
+    assert_eq!((tables, rule_count), (1, 2));
+    let saved = fs::read_to_string(&rules_path).unwrap();
+    assert!(saved.contains("rule: unique"), "{}", saved);
+
+    // 重新加载规则文件的新实例导入时自动检查
+    let core = DataWise::new().unwrap();
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::Rules { action: RulesAction::Load { path: rules_path.to_string_lossy().to_string() } },
+    )
+    .await;
+    let loaded: RuleSet = serde_json::from_str(&preview).unwrap();
+    assert_eq!(loaded.rule_count(), 2);
+
+    let mut rx = core.subscribe();
+    core.handle(Command {
+        task_id: 4,
+        cmd_type: CmdType::ImportFile {
+            path: csv_path.to_string_lossy().to_string(),
+            fmt: FileFmt::Csv,
+            table_name: Some("feed".to_string()),
+            overwrite: false,
+            options: ImportOptions::default(),
+        },
+    })
+    .await
+    .unwrap();
+
+    let mut validated = None;
+    while let Ok(event) = rx.recv().await {
+        match event.kind {
+            EventKind::Validated { table, passed, report } => validated = Some((table, passed, report)),
+            EventKind::Finished { .. } => break,
+            EventKind::Error(e) => panic!("Unexpected error: {}", e),
+            _ => {}
+        }
+    }
+    let (table, passed, report) = validated.expect("Import should emit Validated");
+    assert_eq!(table, "feed");
+    assert!(!passed);
+    let failing: Vec<_> = report.results.iter().map(|r| r.failing_rows).collect();
+    assert_eq!(failing, [2, 1]);
+}
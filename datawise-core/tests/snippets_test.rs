@@ -0,0 +1,194 @@
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType};
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_snippet_library() {
+    use datawise_core::{HistoryAction, HistoryEntry, Snippet, SnippetAction};
+
+    let temp_dir = TempDir::new().unwrap();
+    let library_path = temp_dir.path().join("snippets.json");
+    let bundle_path = temp_dir.path().join("bundle.json");
+
+    let core = DataWise::new().unwrap();
+    run_until_finished(
+        &core,
+        1,
+        CmdType::ExecuteSql {
+            sql: "CREATE TABLE sales AS SELECT * FROM (VALUES ('north', 10), ('north', 30), ('south', 20)) t(region, amount)"
+                .to_string(),
+        },
+    )
+    .await;
+    run_until_finished(
+        &core,
+        2,
+        CmdType::Snippets { action: SnippetAction::Open { path: library_path.to_string_lossy().to_string() } },
+    )
+    .await;
+
+    let snippet = |name: &str, folder: &str, tags: &[&str], sql: &str| Snippet {
+        name: name.to_string(),
+        folder: folder.to_string(),
+        description: String::new(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        sql: sql.to_string(),
+        parameters: Vec::new(),
+        updated_at: None,
+    };
+
+    // 保存时提取参数名
+    let (_, _, preview) = run_until_finished(
+        &core,
+        3,
+        CmdType::Snippets {
+            action: SnippetAction::Save {
+                snippet: snippet(
+                    "big_sales",
+                    "reports/sales",
+                    &["sales"],
+                    "SELECT * FROM sales WHERE region = {{ region }} AND amount >= {{min_amount}}",
+                ),
+            },
+        },
+    )
+    .await;
+    let saved: Snippet = serde_json::from_str(&preview).unwrap();
+    assert_eq!(saved.parameters, ["region", "min_amount"]);
+    assert!(saved.updated_at.is_some());
+
+    run_until_finished(
+        &core,
+        4,
+        CmdType::Snippets {
+            action: SnippetAction::Save { snippet: snippet("row_count", "", &["admin"], "SELECT COUNT(*) FROM sales") },
+        },
+    )
+    .await;
+
+    // 同名片段不能再保存
+    let result = core
+        .handle(Command {
+            task_id: 5,
+            cmd_type: CmdType::Snippets {
+                action: SnippetAction::Save { snippet: snippet("BIG_SALES", "", &[], "SELECT 1") },
+            },
+        })
+        .await;
+    assert!(result.is_err());
+
+    // 按文件夹和标签过滤
+    let list = |task_id: u64, folder: Option<&str>, tag: Option<&str>| {
+        let core = &core;
+        let action = SnippetAction::List { folder: folder.map(str::to_string), tag: tag.map(str::to_string) };
+        async move {
+            let (_, _, preview) = run_until_finished(core, task_id, CmdType::Snippets { action }).await;
+            serde_json::from_str::<Vec<Snippet>>(&preview)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.name)
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(list(6, None, None).await, ["row_count", "big_sales"]);
+    assert_eq!(list(7, Some("reports"), None).await, ["big_sales"]);
+    assert_eq!(list(8, None, Some("ADMIN")).await, ["row_count"]);
+
+    // 代入参数执行，代入后的 SQL 记录到查询历史
+    let (row_count, _, _) = run_until_finished(
+        &core,
+        9,
+        CmdType::Snippets {
+            action: SnippetAction::Execute {
+                name: "big_sales".to_string(),
+                params: [
+                    ("region".to_string(), serde_json::json!("north")),
+                    ("min_amount".to_string(), serde_json::json!(20)),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        },
+    )
+    .await;
+    assert_eq!(row_count, 1);
+    let (_, _, preview) = run_until_finished(
+        &core,
+        10,
+        CmdType::History { action: HistoryAction::List { pinned_only: false, limit: 1 } },
+    )
+    .await;
+    let entries: Vec<HistoryEntry> = serde_json::from_str(&preview).unwrap();
+    assert_eq!(entries[0].sql, "SELECT * FROM sales WHERE region = 'north' AND amount >= 20");
+
+    // 缺少参数时报错
+    let result = core
+        .handle(Command {
+            task_id: 11,
+            cmd_type: CmdType::Snippets {
+                action: SnippetAction::Execute { name: "big_sales".to_string(), params: Default::default() },
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("region, min_amount"));
+
+    // 修改（改名）
+    run_until_finished(
+        &core,
+        12,
+        CmdType::Snippets {
+            action: SnippetAction::Update {
+                name: "row_count".to_string(),
+                snippet: snippet("sales_count", "admin", &["admin"], "SELECT COUNT(*) FROM sales"),
+            },
+        },
+    )
+    .await;
+    assert_eq!(list(13, None, Some("admin")).await, ["sales_count"]);
+
+    // 导出一个片段，再导入到另一个实例
+    let (count, _, _) = run_until_finished(
+        &core,
+        14,
+        CmdType::Snippets {
+            action: SnippetAction::Export {
+                path: bundle_path.to_string_lossy().to_string(),
+                names: vec!["big_sales".to_string()],
+            },
+        },
+    )
+    .await;
+    assert_eq!(count, 1);
+
+    let other = DataWise::new().unwrap();
+    let (count, _, _) = run_until_finished(
+        &other,
+        1,
+        CmdType::Snippets {
+            action: SnippetAction::Import { path: bundle_path.to_string_lossy().to_string(), overwrite: false },
+        },
+    )
+    .await;
+    assert_eq!(count, 1);
+    let result = other
+        .handle(Command {
+            task_id: 2,
+            cmd_type: CmdType::Snippets {
+                action: SnippetAction::Import { path: bundle_path.to_string_lossy().to_string(), overwrite: false },
+            },
+        })
+        .await;
+    assert!(result.is_err());
+
+    // 片段库文件中保存了所有修改
+    let core = DataWise::new().unwrap();
+    let (count, _, _) = run_until_finished(
+        &core,
+        1,
+        CmdType::Snippets { action: SnippetAction::Open { path: library_path.to_string_lossy().to_string() } },
+    )
+    .await;
+    assert_eq!(count, 2);
+}
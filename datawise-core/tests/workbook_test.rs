@@ -0,0 +1,110 @@
+mod common;
+
+use common::run_until_finished;
+use datawise_core::{DataWise, Command, CmdType};
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_workbook() {
+    use datawise_core::{CellKind, Workbook, WorkbookAction, WorkbookCell};
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("analysis.workbook.json").to_string_lossy().to_string();
+
+    let core = DataWise::new().unwrap();
+    let workbook_command = |task_id: u64, action: WorkbookAction| {
+        let core = &core;
+        let path = path.clone();
+        async move {
+            let (row_count, _, preview) =
+                run_until_finished(core, task_id, CmdType::Workbook { path, action }).await;
+            (row_count, serde_json::from_str::<Workbook>(&preview).unwrap())
+        }
+    };
+
+    // 打开不存在的工作簿时报错，不创建文件
+    let result = core
+        .handle(Command {
+            task_id: 1,
+            cmd_type: CmdType::Workbook { path: path.clone(), action: WorkbookAction::Open },
+        })
+        .await;
+    assert!(result.is_err());
+    assert!(!std::path::Path::new(&path).exists());
+
+    // 创建空工作簿；已存在时不能再次创建
+    let (count, workbook) = workbook_command(1, WorkbookAction::Create).await;
+    assert_eq!(count, 0);
+    assert!(workbook.cells.is_empty());
+    let result = core
+        .handle(Command {
+            task_id: 1,
+            cmd_type: CmdType::Workbook { path: path.clone(), action: WorkbookAction::Create },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("already exists"));
+
+    // 单元格的文档顺序与依赖顺序不同
+    let workbook = Workbook {
+        cells: vec![
+            WorkbookCell::markdown("", "# Sales by region"),
+            WorkbookCell::sql("report", "SELECT region, total FROM by_region ORDER BY region"),
+            WorkbookCell::sql(
+                "by_region",
+                "CREATE OR REPLACE VIEW by_region AS SELECT region, SUM(amount) AS total FROM sales GROUP BY region",
+            ),
+            WorkbookCell::sql(
+                "sales",
+                "CREATE OR REPLACE TABLE sales AS SELECT * FROM (VALUES ('north', 10), ('north', 30), ('south', 20)) t(region, amount)",
+            ),
+        ],
+    };
+    let (count, workbook) = workbook_command(2, WorkbookAction::Save { workbook }).await;
+    assert_eq!(count, 4);
+    assert_eq!(workbook.cells[0].id, "cell-1");
+    assert_eq!(workbook.cells[0].kind, CellKind::Markdown);
+
+    // 单独执行时依赖的表还不存在
+    let result = core
+        .handle(Command {
+            task_id: 3,
+            cmd_type: CmdType::Workbook {
+                path: path.clone(),
+                action: WorkbookAction::RunCell { id: "report".to_string(), with_dependencies: false },
+            },
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("report"));
+
+    // 带依赖执行，失败的结果被新的结果替换
+    let (count, workbook) = workbook_command(
+        4,
+        WorkbookAction::RunCell { id: "report".to_string(), with_dependencies: true },
+    )
+    .await;
+    assert_eq!(count, 3);
+    let report = workbook.cells[1].result.as_ref().unwrap();
+    assert!(report.error.is_none());
+    assert_eq!(report.row_count, 2);
+    assert_eq!(report.column_count, 2);
+    assert!(report.preview.contains("south"));
+    assert!(workbook.cells[0].result.is_none());
+
+    // 全部执行；结果摘要保存在文件中
+    let (count, _) = workbook_command(5, WorkbookAction::RunAll).await;
+    assert_eq!(count, 3);
+    let (_, workbook) = workbook_command(6, WorkbookAction::Open).await;
+    assert!(workbook.cells[1..].iter().all(|c| c.result.as_ref().is_some_and(|r| r.error.is_none())));
+
+    // 非 SQL 单元格不能执行
+    let result = core
+        .handle(Command {
+            task_id: 7,
+            cmd_type: CmdType::Workbook {
+                path: path.clone(),
+                action: WorkbookAction::RunCell { id: "cell-1".to_string(), with_dependencies: false },
+            },
+        })
+        .await;
+    assert!(result.is_err());
+}
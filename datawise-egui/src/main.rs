@@ -144,12 +144,20 @@ impl DataWiseApp {
             EventKind::Progress { pct, .. } => {
                 self.status = format!("Progress: {}%", pct);
             }
-            EventKind::Finished { row_count, column_count, preview } => {
+            EventKind::Finished { row_count, column_count, preview, .. } => {
                 self.is_executing = false;
                 self.status = format!("Completed: {} rows, {} columns", row_count, column_count);
                 self.results = format!("Rows: {}\nColumns: {}\n\nPreview:\n{}",
                     row_count, column_count, preview);
             }
+            EventKind::CatalogChanged { .. } => {
+                // 目录变化不影响当前的查询结果
+            }
+            EventKind::Validated { table, passed, .. } => {
+                if !passed {
+                    self.status = format!("Data quality rules failed for table: {}", table);
+                }
+            }
             EventKind::Error(e) => {
                 self.is_executing = false;
                 self.status = format!("Error: {}", e);
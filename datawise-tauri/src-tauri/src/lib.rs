@@ -1,4 +1,7 @@
-use datawise_core::{DataWise, Command, CmdType, EventKind, FileFmt};
+use datawise_core::{
+    DataWise, Command, CmdType, EventKind, ExportOptions, ExportSummary, FileFmt, ImportOptions, TableAction,
+    CatalogChange, TableChange, TableInfo, UiEvent,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tauri::Emitter;
@@ -26,6 +29,12 @@ pub struct OperationResult {
     /// 导入操作时的列数
     #[serde(skip_serializing_if = "Option::is_none")]
     pub column_count: Option<usize>,
+    /// 导出操作时写入的字节数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_written: Option<u64>,
+    /// 导出操作的耗时（毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
 }
 
 /// 应用状态，包含 DataWise Core 实例
@@ -76,6 +85,7 @@ async fn execute_sql(
                 row_count,
                 column_count,
                 preview,
+                ..
             } => {
                 result = Some(QueryResult {
                     row_count,
@@ -98,7 +108,7 @@ async fn execute_sql(
 ///
 /// # 参数
 /// - `path`: 文件路径
-/// - `format`: 文件格式 ("csv" 或 "parquet")
+/// - `format`: 文件格式 ("csv"、"parquet"、"xlsx" 或 "arrow")
 /// - `table_name`: 导入到的表名（可选）
 /// - `window`: Tauri 窗口（用于发送进度事件）
 ///
@@ -117,6 +127,8 @@ async fn import_file(
     let fmt = match format.to_lowercase().as_str() {
         "csv" => FileFmt::Csv,
         "parquet" | "pq" => FileFmt::Parquet,
+        "xlsx" | "xls" | "excel" => FileFmt::Excel,
+        "arrow" | "arrows" | "feather" | "ipc" => FileFmt::Arrow,
         _ => return Err(format!("Unsupported format: {}", format)),
     };
 
@@ -131,6 +143,7 @@ async fn import_file(
             fmt,
             table_name: table_name.clone(),
             overwrite: false,
+            options: ImportOptions::default(),
         },
     };
 
@@ -183,6 +196,8 @@ async fn import_file(
                     table_name: None,
                     row_count: None,
                     column_count: None,
+                    bytes_written: None,
+                    elapsed_ms: None,
                 });
             }
             _ => {}
@@ -204,6 +219,8 @@ async fn import_file(
         table_name: final_table_name,
         row_count,
         column_count,
+        bytes_written: None,
+        elapsed_ms: None,
     })
 }
 
@@ -212,7 +229,9 @@ async fn import_file(
 /// # 参数
 /// - `source`: 源表名或 SQL 查询
 /// - `path`: 导出路径
-/// - `format`: 导出格式 ("csv" 或 "parquet")
+/// - `format`: 导出格式 ("csv"、"parquet"、"xlsx" 或 "arrow")
+/// - `overwrite`: 是否覆盖已存在的文件（可选，默认 false）
+/// - `options`: 导出选项（可选，如 Parquet 压缩、分区列）
 ///
 /// # 返回
 /// 操作结果
@@ -221,6 +240,8 @@ async fn export_file(
     source: String,
     path: String,
     format: String,
+    overwrite: Option<bool>,
+    options: Option<ExportOptions>,
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
 ) -> Result<OperationResult, String> {
     tracing::info!("Exporting to: {} (format: {})", path, format);
@@ -228,6 +249,8 @@ async fn export_file(
     let fmt = match format.to_lowercase().as_str() {
         "csv" => FileFmt::Csv,
         "parquet" | "pq" => FileFmt::Parquet,
+        "xlsx" | "excel" => FileFmt::Excel,
+        "arrow" | "arrows" | "feather" | "ipc" => FileFmt::Arrow,
         _ => return Err(format!("Unsupported format: {}", format)),
     };
 
@@ -241,17 +264,19 @@ async fn export_file(
             source,
             path,
             fmt,
+            overwrite: overwrite.unwrap_or(false),
+            options: options.unwrap_or_default(),
         },
     };
 
     state.core.handle(cmd).await.map_err(|e| e.to_string())?;
 
     // 等待事件
-    let mut success = false;
+    let mut summary: Option<ExportSummary> = None;
     while let Ok(event) = rx.recv().await {
         match event.kind {
-            EventKind::Finished { .. } => {
-                success = true;
+            EventKind::Finished { preview, .. } => {
+                summary = Some(serde_json::from_str(&preview).map_err(|e| e.to_string())?);
                 break;
             }
             EventKind::Error(e) => {
@@ -261,25 +286,107 @@ async fn export_file(
                     table_name: None,
                     row_count: None,
                     column_count: None,
+                    bytes_written: None,
+                    elapsed_ms: None,
                 });
             }
             _ => {}
         }
     }
 
-    Ok(OperationResult {
-        success,
-        message: if success {
-            "File exported successfully".to_string()
-        } else {
-            "Export failed".to_string()
+    Ok(match summary {
+        Some(summary) => OperationResult {
+            success: true,
+            message: format!(
+                "Exported {} rows, {} columns ({} bytes) in {:.2}s",
+                summary.rows_exported,
+                summary.column_count,
+                summary.bytes_written,
+                summary.elapsed_ms as f64 / 1000.0
+            ),
+            table_name: None,
+            row_count: Some(summary.rows_exported as usize),
+            column_count: Some(summary.column_count),
+            bytes_written: Some(summary.bytes_written),
+            elapsed_ms: Some(summary.elapsed_ms),
+        },
+        None => OperationResult {
+            success: false,
+            message: "Export failed".to_string(),
+            table_name: None,
+            row_count: None,
+            column_count: None,
+            bytes_written: None,
+            elapsed_ms: None,
         },
-        table_name: None,
-        row_count: None,
-        column_count: None,
     })
 }
 
+/// 列出工作区中的表和视图（含列信息和数据来源），用于侧边栏目录树
+///
+/// # 参数
+/// - `database`: 只列出该数据库中的表（可选）
+/// - `schema`: 只列出该 schema 中的表（可选）
+#[tauri::command]
+async fn list_tables(
+    database: Option<String>,
+    schema: Option<String>,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<TableInfo>, String> {
+    let state = state.lock().await;
+    let preview = run_catalog_command(&state.core, 4, CmdType::ListTables { database, schema }).await?;
+    serde_json::from_str(&preview).map_err(|e| e.to_string())
+}
+
+/// 描述单张表（精确行数、列信息和数据来源）
+///
+/// # 参数
+/// - `name`: 表名，可以带 schema 或数据库前缀
+#[tauri::command]
+async fn describe_table(
+    name: String,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<TableInfo, String> {
+    let state = state.lock().await;
+    let preview = run_catalog_command(&state.core, 5, CmdType::DescribeTable { name }).await?;
+    serde_json::from_str(&preview).map_err(|e| e.to_string())
+}
+
+/// 管理表：重命名、删除、复制、清空，或把查询保存为视图/表
+///
+/// # 参数
+/// - `action`: 要执行的操作
+///
+/// # 返回
+/// 操作结果（操作后的表、受影响的行数、目录变化），供确认提示使用
+#[tauri::command]
+async fn manage_table(
+    action: TableAction,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<TableChange, String> {
+    let state = state.lock().await;
+    let preview = run_catalog_command(&state.core, 6, CmdType::ManageTable { action }).await?;
+    serde_json::from_str(&preview).map_err(|e| e.to_string())
+}
+
+/// 执行目录命令，返回 `Finished.preview` 中的 JSON
+async fn run_catalog_command(core: &DataWise, task_id: u64, cmd_type: CmdType) -> Result<String, String> {
+    let mut rx = core.subscribe();
+    core.handle(Command { task_id, cmd_type }).await.map_err(|e| e.to_string())?;
+
+    while let Ok(event) = rx.recv().await {
+        if event.task_id != task_id {
+            continue;
+        }
+        match event.kind {
+            EventKind::Finished { preview, .. } => return Ok(preview),
+            EventKind::Error(e) => return Err(format!("Catalog error: {}", e)),
+            _ => {}
+        }
+    }
+    Err("No result received".to_string())
+}
+
 /// 取消任务命令
 ///
 /// # 参数
@@ -310,26 +417,57 @@ async fn cancel_task(
         table_name: None,
         row_count: None,
         column_count: None,
+        bytes_written: None,
+        elapsed_ms: None,
     })
 }
 
+/// 把所有任务的目录变化转发给前端（`catalog-changed` 事件，载荷为 [`CatalogChange`]）
+async fn forward_catalog_changes(app: tauri::AppHandle, mut events: tokio::sync::broadcast::Receiver<UiEvent>) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    loop {
+        match events.recv().await {
+            Ok(UiEvent {
+                kind: EventKind::CatalogChanged { created, dropped, altered },
+                ..
+            }) => {
+                let _ = app.emit("catalog-changed", CatalogChange { created, dropped, altered });
+            }
+            Ok(_) => {}
+            // 落后太多时丢弃旧事件，继续转发
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Skipped {} events while forwarding catalog changes", skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // 初始化日志
     tracing_subscriber::fmt::init();
 
     // 创建应用状态
-    let app_state = Arc::new(Mutex::new(
-        AppState::new().expect("Failed to initialize AppState"),
-    ));
+    let app_state = AppState::new().expect("Failed to initialize AppState");
+    let events = app_state.core.subscribe();
+    let app_state = Arc::new(Mutex::new(app_state));
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .setup(move |app| {
+            tauri::async_runtime::spawn(forward_catalog_changes(app.handle().clone(), events));
+            Ok(())
+        })
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             execute_sql,
             import_file,
             export_file,
+            list_tables,
+            describe_table,
+            manage_table,
             cancel_task
         ])
         .run(tauri::generate_context!())
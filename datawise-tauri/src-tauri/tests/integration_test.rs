@@ -36,6 +36,7 @@ mod tests {
                     row_count,
                     column_count,
                     preview,
+                    ..
                 } => {
                     received_finished = true;
                     assert_eq!(row_count, 1);
@@ -79,6 +80,7 @@ mod tests {
                 row_count,
                 column_count,
                 preview,
+                ..
             } = event.kind
             {
                 assert_eq!(row_count, 1);
@@ -15,6 +15,8 @@ interface OperationResult {
   table_name?: string;
   row_count?: number;
   column_count?: number;
+  bytes_written?: number;
+  elapsed_ms?: number;
 }
 
 type TabType = "query" | "import" | "export";
@@ -47,6 +49,7 @@ function App() {
   const [exportSource, setExportSource] = useState("");
   const [exportPath, setExportPath] = useState("");
   const [exportFormat, setExportFormat] = useState("csv");
+  const [exportOverwrite, setExportOverwrite] = useState(false);
   const [exportLoading, setExportLoading] = useState(false);
   const [exportError, setExportError] = useState<string | null>(null);
   const [exportSuccess, setExportSuccess] = useState<string | null>(null);
@@ -137,6 +140,7 @@ function App() {
         source: exportSource,
         path: exportPath,
         format: exportFormat,
+        overwrite: exportOverwrite,
       });
 
       if (result.success) {
@@ -378,6 +382,18 @@ function App() {
             </select>
           </div>
 
+          <div className="form-group">
+            <label htmlFor="export-overwrite">
+              <input
+                id="export-overwrite"
+                type="checkbox"
+                checked={exportOverwrite}
+                onChange={(e) => setExportOverwrite(e.target.checked)}
+              />{" "}
+              Overwrite existing file
+            </label>
+          </div>
+
           <button onClick={handleExport} disabled={exportLoading || !exportSource || !exportPath}>
             {exportLoading ? "Exporting..." : "Export"}
           </button>
@@ -80,7 +80,7 @@ impl App {
             datawise_core::EventKind::Progress { pct, .. } => {
                 self.status = format!("Progress: {}%", pct);
             }
-            datawise_core::EventKind::Finished { row_count, column_count, preview } => {
+            datawise_core::EventKind::Finished { row_count, column_count, preview, .. } => {
                 self.is_executing = false;
                 self.status = format!("Completed: {} rows, {} columns", row_count, column_count);
                 self.results = vec![
@@ -100,6 +100,14 @@ impl App {
                     self.results.push(preview);
                 }
             }
+            datawise_core::EventKind::CatalogChanged { .. } => {
+                // 目录变化不影响当前的查询结果
+            }
+            datawise_core::EventKind::Validated { table, passed, .. } => {
+                if !passed {
+                    self.status = format!("Data quality rules failed for table: {}", table);
+                }
+            }
             datawise_core::EventKind::Error(e) => {
                 self.is_executing = false;
                 self.status = "Error".to_string();